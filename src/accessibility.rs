@@ -0,0 +1,32 @@
+#[cfg(windows)]
+pub fn prefers_reduced_motion() -> bool {
+    use windows::Win32::{
+        Foundation::BOOL,
+        UI::WindowsAndMessaging::{
+            SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+        },
+    };
+
+    let mut animations = BOOL::from(true);
+
+    let result = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animations as *mut BOOL as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+
+    result.is_ok() && !animations.as_bool()
+}
+
+#[cfg(not(windows))]
+pub fn prefers_reduced_motion() -> bool {
+    use std::process::Command;
+
+    Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.trim_ascii() == b"false")
+}
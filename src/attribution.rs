@@ -0,0 +1,77 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Photo;
+
+pub const SIDECAR_EXTENSION: &str = "json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attribution {
+    pub id: String,
+    pub photographer: String,
+    pub username: String,
+    pub profile_url: Option<String>,
+    pub description: Option<String>,
+    pub page_url: Option<String>,
+}
+
+impl From<&Photo> for Attribution {
+    fn from(photo: &Photo) -> Self {
+        Self {
+            id: photo.id().to_owned(),
+            photographer: photo.user().name().to_owned(),
+            username: photo.user().username().to_owned(),
+            profile_url: photo.user().profile_url().map(ToOwned::to_owned),
+            description: photo
+                .description()
+                .or(photo.alt_description())
+                .map(ToOwned::to_owned),
+            page_url: photo.page_url().map(ToOwned::to_owned),
+        }
+    }
+}
+
+pub fn sidecar_path<P: AsRef<Path>>(image_path: P) -> PathBuf {
+    image_path.as_ref().with_extension(SIDECAR_EXTENSION)
+}
+
+pub fn is_sidecar<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext == SIDECAR_EXTENSION)
+}
+
+pub fn write_sidecar<P: AsRef<Path>>(photo: &Photo, image_path: P) -> io::Result<()> {
+    let content =
+        serde_json::to_string_pretty(&Attribution::from(photo)).map_err(Into::<io::Error>::into)?;
+
+    fs::write(sidecar_path(image_path), content)
+}
+
+pub fn move_sidecar<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    let from = sidecar_path(from);
+    if !from.exists() {
+        return Ok(());
+    }
+
+    fs::rename(from, sidecar_path(to))
+}
+
+pub fn remove_sidecar<P: AsRef<Path>>(image_path: P) -> io::Result<()> {
+    match fs::remove_file(sidecar_path(image_path)) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+pub fn read_sidecar<P: AsRef<Path>>(image_path: P) -> io::Result<Option<Attribution>> {
+    match fs::read_to_string(sidecar_path(image_path)) {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
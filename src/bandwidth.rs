@@ -0,0 +1,86 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{self, Migration};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Estimate {
+    pub bytes_per_second: f64,
+    pub bytes_per_photo: f64,
+    pub samples: u32,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Estimate {
+    pub fn is_known(&self) -> bool {
+        self.samples > 0 && self.bytes_per_second > 0.0 && self.bytes_per_photo > 0.0
+    }
+
+    pub fn photo_duration(&self) -> Option<Duration> {
+        self.is_known()
+            .then(|| Duration::from_secs_f64(self.bytes_per_photo / self.bytes_per_second))
+    }
+
+    pub fn affordable(&self, budget: Duration) -> Option<u32> {
+        let per_photo = self.photo_duration()?;
+
+        Some((budget.as_secs_f64() / per_photo.as_secs_f64().max(f64::EPSILON)) as u32)
+    }
+}
+
+#[derive(Debug)]
+pub struct Bandwidth {
+    path: PathBuf,
+    estimate: Estimate,
+}
+
+impl Bandwidth {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let estimate = state::load(&path, MIGRATIONS)?;
+
+        Ok(Self { path, estimate })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        state::save(&self.path, &self.estimate, MIGRATIONS)
+    }
+
+    pub fn estimate(&self) -> &Estimate {
+        &self.estimate
+    }
+
+    pub fn observe(&mut self, photos: usize, bytes: u64, elapsed: Duration) {
+        if photos == 0 || bytes == 0 || elapsed.is_zero() {
+            return;
+        }
+
+        let bytes_per_second = bytes as f64 / elapsed.as_secs_f64();
+        let bytes_per_photo = bytes as f64 / photos as f64;
+
+        let estimate = &mut self.estimate;
+        if estimate.is_known() {
+            estimate.bytes_per_second = smooth(estimate.bytes_per_second, bytes_per_second);
+            estimate.bytes_per_photo = smooth(estimate.bytes_per_photo, bytes_per_photo);
+        } else {
+            estimate.bytes_per_second = bytes_per_second;
+            estimate.bytes_per_photo = bytes_per_photo;
+        }
+        estimate.samples += 1;
+        estimate.updated_at = Some(Utc::now());
+    }
+}
+
+fn smooth(previous: f64, sample: f64) -> f64 {
+    SMOOTHING * sample + (1.0 - SMOOTHING) * previous
+}
@@ -1,160 +1,621 @@
 #![windows_subsystem = "windows"]
 
 use std::{
-    collections::VecDeque,
     fs, io,
     path::{Path, PathBuf},
-    time::UNIX_EPOCH,
+    time::Duration,
 };
 
-use bytes::Bytes;
-use serde::{Deserialize, Serialize};
-use thiserror::Error;
-use tokio::task::JoinSet;
-use windows::Win32::System::Console::{AttachConsole, FreeConsole, ATTACH_PARENT_PROCESS};
+use chrono::{Datelike, Local, TimeDelta};
+use clap::{Args, Parser, Subcommand};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+use backdrop::{
+    commands::{
+        self, apply_bundle, approve_photos, block_photos_or_current, create_bundle,
+        delete_old_photos, doctor, import_spotlight, init, list_topics, migrate, photo_files,
+        photo_json, plan, play_sequence, preview_search, print_accent, print_features, print_stats,
+        print_taste, print_why, refetch, refresh_metadata, reject_photos, rotate_offline, serve,
+        set_pinned, simulate_cleanup, spotlight, status, tune, Error, Result,
+        BUNDLE_STAGING_FOLDER, HISTORY_FILE, LOGS_FOLDER, LOG_FILE, PENDING_FOLDER,
+        POOL_PHOTOGRAPHERS, SECONDS_PER_DAY, STATS_FILE, TASTE_FILE, TOPICS_FILE, TRACK_QUEUE_FILE,
+    },
+    config::{self, Config, LogLevel, RefreshMetadata, CONFIG_FILE, LEGACY_CONFIG_FILE},
+    gallery,
+    histogram::PoolHistograms,
+    history::History,
+    recap, scheduler,
+    snapshot::Snapshots,
+    stats::Stats,
+    taste::Taste,
+    timing::Timer,
+    unsplash, TopicOrder, TrackQueue,
+};
 
-use backdrop::{unsplash, Client, Download, Fetch, Photo};
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Override the configured fetch settings, e.g. "topic:nature orientation:portrait count:5"
+    #[arg(long, global = true, env = "BACKDROP_QUERY")]
+    query: Option<String>,
 
-#[derive(Debug, Error)]
-enum Error {
-    #[error("{0}")]
-    Io(#[from] io::Error),
+    /// Use one of the named queries from the configuration
+    #[arg(long, global = true, env = "BACKDROP_QUERY_NAME")]
+    query_name: Option<String>,
 
-    #[error("{0}")]
-    Unsplash(#[from] unsplash::Error),
+    /// Use one of the named profiles from the configuration
+    #[arg(long, global = true, env = "BACKDROP_PROFILE")]
+    profile: Option<String>,
 
-    #[error("A default configuration file has been created, please review it before proceeding")]
-    RequiresConfigure,
-}
+    /// Print machine-readable JSON on stdout
+    #[arg(long, global = true)]
+    json: bool,
 
-type Result<T> = core::result::Result<T, Error>;
+    #[command(flatten)]
+    overrides: Overrides,
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Config {
-    folder: PathBuf,
-    max_size: u64,
-    fetch: Fetch,
-    download: Download,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        let folder = dirs::picture_dir().unwrap().join("Backdrop");
+#[derive(Debug, Clone, Args)]
+struct Overrides {
+    /// Folder the photos are downloaded to
+    #[arg(long, global = true, env = "BACKDROP_FOLDER")]
+    folder: Option<PathBuf>,
+
+    /// Number of photos to fetch per run
+    #[arg(long, global = true, env = "BACKDROP_COUNT")]
+    count: Option<u32>,
+
+    /// Maximum total size of the folder in bytes
+    #[arg(long, global = true, env = "BACKDROP_MAX_SIZE")]
+    max_size: Option<u64>,
+
+    /// Maximum number of photos kept in the folder
+    #[arg(long, global = true, env = "BACKDROP_MAX_COUNT")]
+    max_count: Option<usize>,
 
-        Self {
+    /// Delete photos downloaded more than this many days ago
+    #[arg(long, global = true, env = "BACKDROP_MAX_AGE_DAYS")]
+    max_age_days: Option<u64>,
+
+    /// Whether to set the wallpaper after downloading
+    #[arg(long, global = true, env = "BACKDROP_APPLY_WALLPAPER")]
+    apply_wallpaper: Option<bool>,
+
+    /// Number of photos downloaded at the same time
+    #[arg(long, global = true, env = "BACKDROP_MAX_CONCURRENT_DOWNLOADS")]
+    max_concurrent_downloads: Option<usize>,
+}
+
+impl Overrides {
+    fn apply(self, config: &mut Config) {
+        let Overrides {
             folder,
-            max_size: 100_000_000,
-            fetch: Default::default(),
-            download: Default::default(),
+            count,
+            max_size,
+            max_count,
+            max_age_days,
+            apply_wallpaper,
+            max_concurrent_downloads,
+        } = self;
+
+        if let Some(folder) = folder {
+            config.folder = folder;
         }
-    }
-}
 
-async fn download_photos(config: &Config) -> Result<()> {
-    let client = Client::new_from_env()?;
+        if let Some(count) = count {
+            config.fetch.count = count;
+        }
+
+        if let Some(max_size) = max_size {
+            config.max_size = max_size;
+        }
 
-    let photos = client.fetch_photos(&config.fetch).await?;
+        if max_count.is_some() {
+            config.max_count = max_count;
+        }
 
-    let mut tasks = JoinSet::<backdrop::Result<(Photo, Bytes)>>::new();
-    for photo in photos {
-        let client = client.clone();
-        let download = config.download.clone();
+        if max_age_days.is_some() {
+            config.max_age_days = max_age_days;
+        }
 
-        tasks.spawn(async move {
-            let data = client.download_photo(&photo, &download).await?;
+        if let Some(apply_wallpaper) = apply_wallpaper {
+            config.apply_wallpaper = apply_wallpaper;
+        }
 
-            Ok((photo, data))
-        });
+        if let Some(max_concurrent_downloads) = max_concurrent_downloads {
+            config.max_concurrent_downloads = max_concurrent_downloads;
+        }
     }
+}
 
-    fs::create_dir_all(&config.folder)?;
+#[cfg(feature = "keyring")]
+#[derive(Debug, Subcommand)]
+enum AuthAction {
+    /// Store the Unsplash access key in the OS credential store
+    Set {
+        /// The access key, prompted for when omitted
+        key: Option<String>,
+    },
+
+    /// Store the secret access key of the S3 storage in the OS credential store
+    #[cfg(feature = "s3")]
+    S3 {
+        /// The secret access key, prompted for when omitted
+        key: Option<String>,
+    },
+
+    /// Store the password of the WebDAV storage in the OS credential store
+    #[cfg(feature = "webdav")]
+    Webdav {
+        /// The password, prompted for when omitted
+        password: Option<String>,
+    },
+}
 
-    let photos = tasks.join_all().await;
-    for photo in photos {
-        let (photo, data) = photo?;
+#[derive(Debug, Subcommand)]
+enum BundleAction {
+    /// Download photos into a bundle for a machine without internet access
+    Create { output: PathBuf },
 
-        let path = config.folder.join(format!("{}.png", photo.id()));
+    /// Import the photos of a bundle into the local pool
+    Apply { bundle: PathBuf },
+}
 
-        fs::write(&path, &data)?;
-    }
+#[derive(Debug, Subcommand)]
+enum FeaturesAction {
+    /// List the optional subsystems, whether this build includes them and whether they are enabled
+    List,
+}
 
-    Ok(())
+#[derive(Debug, Subcommand)]
+enum TasteAction {
+    /// Show the weights learned from blocked, unpinned and skipped photos
+    Show,
+
+    /// Forget everything learned so far
+    Reset,
 }
 
-fn delete_old_photos(config: &Config) -> io::Result<()> {
-    let mut files: Vec<_> = config
-        .folder
-        .read_dir()?
-        .filter_map(|file| file.ok())
-        .collect();
+#[derive(Debug, Subcommand)]
+enum CleanupAction {
+    /// Show which photos a retention policy would delete over the next days
+    Simulate {
+        /// Maximum total size of the folder, e.g. "500MB" or "2GB"
+        #[arg(long, value_parser = parse_size)]
+        max_size: Option<u64>,
+
+        /// Maximum number of photos in the folder
+        #[arg(long)]
+        max_count: Option<usize>,
+
+        /// Maximum age of a photo, e.g. "14d"
+        #[arg(long, value_parser = parse_interval)]
+        max_age: Option<Duration>,
+
+        /// Also delete photos that have never been shown
+        #[arg(long)]
+        delete_unshown: bool,
+
+        /// Number of days to simulate
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+
+        /// How often photos are fetched, defaults to the configured schedule or once a day
+        #[arg(long, value_parser = parse_interval)]
+        every: Option<Duration>,
+    },
+}
 
-    let mut size: u64 = files
-        .iter()
-        .filter_map(|file| file.metadata().ok())
-        .map(|metadata| metadata.len())
-        .sum();
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Fetch and download new photos (the default)
+    Run {
+        /// Run even if the previous run was too recent
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List photos matching a search without downloading them
+    Search {
+        text: String,
+
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
+
+    /// Print the dominant color of the current wallpaper
+    Accent,
+
+    /// List Unsplash topics to use in topic queries
+    Topics {
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+
+        #[arg(long, default_value_t = 20)]
+        per_page: u32,
+
+        /// One of featured, latest, oldest or position
+        #[arg(long, default_value = "position", value_parser = parse_topic_order)]
+        order: TopicOrder,
+    },
+
+    /// Interactively create the configuration and .env files
+    Init,
+
+    /// Register Backdrop with the system scheduler to run periodically
+    InstallSchedule {
+        /// How often to run, e.g. "30m", "1h" or "1d"
+        #[arg(long, default_value = "1h", value_parser = parse_interval)]
+        every: Duration,
+    },
+
+    /// Remove Backdrop from the system scheduler
+    UninstallSchedule,
+
+    /// Interactively refine the configured query
+    Tune,
+
+    /// Show what the next run would do without doing it
+    Plan,
+
+    /// Check the configuration for problems
+    Doctor,
+
+    /// Set a random already downloaded photo as the wallpaper without going online
+    Rotate,
+
+    /// Manage the access keys in the OS credential store
+    #[cfg(feature = "keyring")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+
+    /// Move photos to and from machines without internet access
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
+    /// Inspect or reset the preferences learned from your feedback
+    Taste {
+        #[command(subcommand)]
+        action: TasteAction,
+    },
+
+    /// Try out retention policies before enabling them
+    Cleanup {
+        #[command(subcommand)]
+        action: CleanupAction,
+    },
+
+    /// Send new photos to the photo frame and remove the ones that left the pool
+    #[cfg(feature = "frame")]
+    Push,
+
+    /// Download the photos recorded in a run manifest again
+    Refetch {
+        #[arg(long)]
+        manifest: PathBuf,
+    },
+
+    /// Ask whether you like the current wallpaper
+    Spotlight,
+
+    /// Index photos left by older versions and move them into the configured layout
+    Migrate,
+
+    /// Update the likes and availability of downloaded photos from Unsplash
+    Refresh {
+        /// Refresh every photo, not only the ones due
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Copy the Windows Spotlight images into the photo folder
+    ImportSpotlight {
+        /// Folder to import from instead of the Windows Spotlight cache
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+
+    /// Show the photo of the configured sequence matching the time of day
+    Sequence {
+        /// Keep running and switch photos as the day progresses
+        #[arg(long)]
+        watch: bool,
+    },
+
+    /// Never show these photos again and remove them from the pool
+    Block {
+        ids: Vec<String>,
+
+        /// Block the current wallpaper
+        #[arg(long)]
+        current: bool,
+    },
+
+    /// Protect photos from cleanup
+    Pin { ids: Vec<String> },
+
+    /// Allow pinned photos to be cleaned up again
+    Unpin { ids: Vec<String> },
+
+    /// List the photos waiting for approval
+    Pending,
+
+    /// Move pending photos into rotation
+    Approve {
+        ids: Vec<String>,
+
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+
+    /// Discard pending photos
+    Reject {
+        ids: Vec<String>,
+
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+
+    /// Restore the configuration and history from before the last change
+    Rollback,
+
+    /// Export the downloaded photos as an offline HTML gallery
+    Gallery {
+        #[arg(long, default_value = "index.html")]
+        out: PathBuf,
+    },
+
+    /// Export a wallpaper year in review as an HTML page
+    Recap {
+        output: PathBuf,
+
+        /// The year to review, defaults to the current one
+        #[arg(long)]
+        year: Option<i32>,
+    },
+
+    /// Keep rotating wallpapers in the background, with a web UI to control them
+    Serve,
+
+    /// Switch the wallpaper of the running `serve` instance
+    Next,
+
+    /// Pause the rotation of the running `serve` instance
+    Pause,
+
+    /// Resume the rotation of the running `serve` instance
+    Resume,
+
+    /// Show the API quota, the photo pool, the current wallpaper and the last and next runs
+    Status,
+
+    /// Explain why a photo was chosen as the wallpaper, the current one by default
+    Why {
+        /// Photo ID
+        id: Option<String>,
+    },
+
+    /// Show the optional subsystems and whether they are enabled
+    Features {
+        #[command(subcommand)]
+        action: FeaturesAction,
+    },
+
+    /// Show local usage statistics
+    Stats {
+        /// Only show the top photographers
+        #[arg(long)]
+        summary: bool,
+
+        /// Also show how the photos in the pool are distributed by resolution, aspect ratio, hue and photographer
+        #[arg(long)]
+        pool: bool,
+
+        /// Draw the pool distributions as sparklines instead of tables
+        #[arg(long, requires = "pool")]
+        sparklines: bool,
+    },
+}
 
-    if size <= config.max_size {
-        return Ok(());
+impl Command {
+    fn mutates_state(&self) -> bool {
+        matches!(
+            self,
+            Command::Run { .. }
+                | Command::Tune
+                | Command::Spotlight
+                | Command::ImportSpotlight { .. }
+                | Command::Migrate
+                | Command::Refresh { .. }
+                | Command::Block { .. }
+                | Command::Pin { .. }
+                | Command::Unpin { .. }
+                | Command::Approve { .. }
+                | Command::Reject { .. }
+                | Command::Bundle {
+                    action: BundleAction::Apply { .. }
+                }
+        )
     }
 
-    files.sort_by_key(|file| {
-        file.metadata()
-            .ok()
-            .and_then(|metadata| metadata.created().ok())
-            .unwrap_or(UNIX_EPOCH)
-    });
+    fn control_request(&self) -> Option<backdrop::control::Request> {
+        use backdrop::control::Request;
 
-    let mut files = VecDeque::from(files);
-    while size > config.max_size {
-        let file = files.pop_front().unwrap();
+        match self {
+            Command::Next => Some(Request::Next),
+            Command::Pause => Some(Request::Pause),
+            Command::Resume => Some(Request::Resume),
+            _ => None,
+        }
+    }
 
-        fs::remove_file(file.path())?;
-        size -= file.metadata()?.len();
+    fn shared_request(&self) -> Option<backdrop::control::Request> {
+        use backdrop::control::Request;
+
+        match self {
+            Command::Pin { ids } => Some(Request::Pin(ids.clone())),
+            Command::Unpin { ids } => Some(Request::Unpin(ids.clone())),
+            Command::Block { ids, current } => Some(Request::Block {
+                ids: ids.clone(),
+                current: *current,
+            }),
+            _ => None,
+        }
     }
 
-    Ok(())
+    #[cfg(feature = "sandbox")]
+    fn is_unattended(&self) -> bool {
+        matches!(self, Command::Run { .. } | Command::Serve)
+    }
 }
 
-fn configure<P: AsRef<Path>>(config_folder: P) -> Result<Config> {
-    let config_folder = config_folder.as_ref();
+#[cfg(feature = "notifications")]
+fn notify_error(error: &Error) {
+    let message = match error {
+        Error::Unsplash(unsplash::Error::RateLimitExceeded) => format!(
+            "The Unsplash request quota is used up, retrying after {}",
+            (Local::now() + TimeDelta::hours(1)).format("%H:%M")
+        ),
+        e => e.to_string(),
+    };
 
-    if !config_folder.exists() {
-        fs::create_dir_all(&config_folder)?;
+    if let Err(e) = backdrop::notification::error(&message) {
+        tracing::warn!("failed to show notification: {}", e);
     }
+}
 
-    let env_path = config_folder.join(".env");
-    let config_path = config_folder.join("config.json");
-    let requires_config = !env_path.exists() || !config_path.exists();
-
-    if requires_config {
-        if !env_path.exists() {
-            fs::copy(".env.example", &env_path)?;
-        }
+fn init_logging<P: AsRef<Path>>(
+    config_folder: P,
+) -> (
+    WorkerGuard,
+    reload::Handle<LevelFilter, tracing_subscriber::Registry>,
+) {
+    let appender =
+        tracing_appender::rolling::daily(config_folder.as_ref().join(LOGS_FOLDER), LOG_FILE);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, handle) = reload::Layer::new(LevelFilter::from(LogLevel::default()));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false),
+        )
+        .init();
+
+    (guard, handle)
+}
 
-        if !config_path.exists() {
-            let config = Config::default();
-            let content = serde_json::to_string_pretty(&config)
-                .map_err(|err| Into::<io::Error>::into(err))?;
+#[cfg(feature = "sandbox")]
+fn enter_sandbox(config: &Config, config_folder: &Path) -> Result<()> {
+    use backdrop::sandbox::{self, Status};
 
-            fs::write(&config_path, &content)?;
-        }
+    fs::create_dir_all(&config.folder)?;
 
-        return Err(Error::RequiresConfigure);
+    let mut writable = vec![config_folder.to_owned(), config.folder.clone()];
+    writable.extend(config.keep_folder.clone());
+    #[cfg(feature = "parallax")]
+    writable.extend(
+        config
+            .parallax
+            .as_ref()
+            .map(|parallax| parallax.folder.clone()),
+    );
+
+    match sandbox::restrict(&writable)? {
+        Status::Enforced => tracing::info!("sandbox enforced"),
+        Status::PartiallyEnforced => tracing::info!("sandbox partially enforced"),
+        Status::Unsupported => tracing::warn!("sandboxing is not supported on this system"),
     }
 
-    dotenvy::from_path(env_path).map_err(|err| match err {
-        dotenvy::Error::Io(err) => err,
+    Ok(())
+}
+
+fn parse_topic_order(value: &str) -> std::result::Result<TopicOrder, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_owned()))
+        .map_err(|_| format!("Invalid topic order {}", value))
+}
 
-        _ => unreachable!(),
-    })?;
+fn parse_size(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: f64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid size {}", value))?;
+
+    let bytes = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        unit => {
+            return Err(format!(
+                "Invalid size unit {}, expected B, KB, MB or GB",
+                unit
+            ))
+        }
+    };
 
-    let config = {
-        let content = fs::read_to_string(&config_path)?;
+    Ok((amount * bytes as f64) as u64)
+}
 
-        serde_json::from_str(&content).map_err(|err| Into::<io::Error>::into(err))?
+fn parse_interval(value: &str) -> std::result::Result<Duration, String> {
+    let unit = value
+        .char_indices()
+        .last()
+        .map(|(index, _)| index)
+        .ok_or("The interval is empty")?;
+    let (amount, unit) = value.split_at(unit);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid interval {}", value))?;
+
+    let seconds = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Invalid interval unit {}, expected m, h or d",
+                unit
+            ))
+        }
     };
 
+    Ok(Duration::from_secs(amount * seconds))
+}
+
+fn load_config<P: AsRef<Path>>(config_folder: P, cli: &Cli) -> Result<Config> {
+    let mut config = config::load(config_folder)?;
+    config.json = cli.json;
+    if let Some(profile) = &cli.profile {
+        config.apply_profile(profile)?;
+    }
+    config.apply_theme();
+    config.apply_docking_profile();
+
+    config.apply_queries(
+        Local::now().weekday(),
+        cli.query_name.as_deref(),
+        cli.query.as_deref(),
+    )?;
+
+    cli.overrides.clone().apply(&mut config);
+
     Ok(config)
 }
 
@@ -162,23 +623,367 @@ fn configure<P: AsRef<Path>>(config_folder: P) -> Result<Config> {
 async fn main() {
     #[cfg(windows)]
     unsafe {
+        use windows::Win32::System::Console::{AttachConsole, FreeConsole, ATTACH_PARENT_PROCESS};
+
         if AttachConsole(ATTACH_PARENT_PROCESS).is_err() {
             FreeConsole().unwrap();
         }
     }
 
-    async fn run() -> Result<()> {
-        let path = dirs::config_dir().unwrap().join("Backdrop");
+    async fn run(
+        mut cli: Cli,
+        log_level: reload::Handle<LevelFilter, tracing_subscriber::Registry>,
+        #[cfg_attr(not(feature = "notifications"), allow(unused_variables))]
+        notifications: &mut bool,
+    ) -> Result<()> {
+        let path = config::folder();
+        let config_path = config::path(&path);
+        let pending_folder = path.join(PENDING_FOLDER);
+        let mut history = History::open(path.join(HISTORY_FILE))?;
+        let mut stats = Stats::open(path.join(STATS_FILE))?;
+        let snapshots = Snapshots::new(&path);
+        let timer = Timer::default();
+
+        match cli.command {
+            Some(Command::Init) => return init(&path).await,
+            Some(Command::InstallSchedule { every }) => {
+                scheduler::install(std::env::current_exe()?, every)?;
+                println!("Backdrop will run every {} minutes", every.as_secs() / 60);
+
+                return Ok(());
+            }
+            Some(Command::UninstallSchedule) => {
+                scheduler::uninstall()?;
+                println!("Backdrop has been removed from the scheduler");
+
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if let Some(request) = cli.command.as_ref().and_then(Command::control_request) {
+            return backdrop::control::send(&path, request)
+                .await
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => Error::NotRunning,
+                    _ => err.into(),
+                });
+        }
+
+        if let Some(request) = cli.command.as_ref().and_then(Command::shared_request) {
+            match backdrop::control::send(&path, request).await {
+                Ok(()) => return Ok(()),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                    ) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        #[cfg(feature = "keyring")]
+        if let Some(Command::Auth { action }) = cli.command {
+            return match action {
+                AuthAction::Set { key } => commands::store_api_key(key).await,
+
+                #[cfg(feature = "s3")]
+                AuthAction::S3 { key } => commands::store_s3_secret(key),
+
+                #[cfg(feature = "webdav")]
+                AuthAction::Webdav { password } => commands::store_webdav_password(password),
+            };
+        }
+
+        let mut config = load_config(&path, &cli)?;
+        let _ = log_level.reload(LevelFilter::from(config.log_level));
+        #[cfg(feature = "notifications")]
+        {
+            *notifications = config.notifications;
+        }
+
+        let command = cli.command.take().unwrap_or(Command::Run { force: false });
+        if matches!(command, Command::Run { .. } | Command::Plan) {
+            config.apply_auto_count(&history, &stats)?;
+        }
+        if command.mutates_state() {
+            snapshots.create(&[CONFIG_FILE, LEGACY_CONFIG_FILE, HISTORY_FILE])?;
+        }
 
-        let config = configure(&path)?;
+        #[cfg(feature = "sandbox")]
+        if config.sandbox && command.is_unattended() {
+            enter_sandbox(&config, &path)?;
+        }
 
-        download_photos(&config).await?;
-        delete_old_photos(&config)?;
+        match command {
+            Command::Run { force } => {
+                commands::run(&config, &mut history, &mut stats, &timer, &path, force).await?
+            }
+
+            Command::Search { text, limit } => preview_search(&config, &text, limit).await?,
+
+            Command::Accent => print_accent(&config, &history)?,
+
+            Command::Topics {
+                page,
+                per_page,
+                order,
+            } => list_topics(&config, page, per_page, order).await?,
+
+            Command::Init | Command::InstallSchedule { .. } | Command::UninstallSchedule => {
+                unreachable!()
+            }
+
+            #[cfg(feature = "keyring")]
+            Command::Auth { .. } => unreachable!(),
+
+            Command::Next | Command::Pause | Command::Resume => unreachable!(),
+
+            Command::Tune => tune(&config, &config_path).await?,
+
+            Command::Plan => plan(&config, &history, &pending_folder)?,
+
+            Command::Doctor => doctor(&config, path.join(TOPICS_FILE)).await?,
+
+            Command::Refetch { manifest } => refetch(&config, &manifest).await?,
+
+            #[cfg(feature = "frame")]
+            Command::Push => {
+                commands::print_push(&config, commands::push_to_frame(&config, &history).await?)
+            }
+
+            Command::Rotate => rotate_offline(&config, &mut history, &mut stats, &timer)?,
+
+            Command::Bundle { action } => match action {
+                BundleAction::Create { output } => {
+                    create_bundle(&config, &history, path.join(BUNDLE_STAGING_FOLDER), &output)
+                        .await?
+                }
+
+                BundleAction::Apply { bundle } => apply_bundle(&config, &mut history, &bundle)?,
+            },
+
+            Command::Taste { action } => {
+                let mut taste = Taste::open(path.join(TASTE_FILE))?;
+                match action {
+                    TasteAction::Show => print_taste(&config, &taste),
+
+                    TasteAction::Reset => {
+                        taste.reset();
+                        taste.save()?;
+                        config.print("Forgot all learned preferences");
+                    }
+                }
+            }
+
+            Command::Cleanup {
+                action:
+                    CleanupAction::Simulate {
+                        max_size,
+                        max_count,
+                        max_age,
+                        delete_unshown,
+                        days,
+                        every,
+                    },
+            } => {
+                let mut policy = config.retention_policy();
+                if max_size.is_some() {
+                    policy.max_size = max_size;
+                }
+                if max_count.is_some() {
+                    policy.max_count = max_count;
+                }
+                if let Some(max_age) = max_age {
+                    policy.max_age_days = Some(max_age.as_secs().div_ceil(SECONDS_PER_DAY));
+                }
+                policy.delete_unshown |= delete_unshown;
+
+                let every = every
+                    .or(config
+                        .schedule
+                        .min_interval_minutes
+                        .map(|minutes| Duration::from_secs(minutes * 60)))
+                    .unwrap_or(Duration::from_secs(SECONDS_PER_DAY));
+
+                simulate_cleanup(&config, &history, &policy, days, every)?
+            }
+
+            Command::Spotlight => spotlight(&config, &mut history, &config_path)?,
+
+            Command::Migrate => migrate(&config, &mut history).await?,
+
+            Command::Refresh { all } => {
+                let every = match (all, &config.refresh_metadata) {
+                    (true, _) => TimeDelta::zero(),
+                    (false, Some(refresh)) => TimeDelta::days(refresh.every_days.into()),
+                    (false, None) => TimeDelta::days(RefreshMetadata::default_every_days().into()),
+                };
+
+                let (refreshed, removed) =
+                    refresh_metadata(&config, &mut history, every, None).await?;
+                println!(
+                    "Refreshed {} photos, {} are no longer on Unsplash",
+                    refreshed, removed
+                );
+            }
+
+            Command::ImportSpotlight { from } => import_spotlight(&config, &mut history, from)?,
+
+            Command::Block { ids, current } => {
+                block_photos_or_current(&config, &mut history, &config_path, ids, current)?
+            }
+
+            Command::Sequence { watch } => {
+                let folder = match &config.sequence {
+                    Some(sequence) => path.join("sequences").join(&sequence.collection),
+                    None => return Err(Error::NoSequence),
+                };
+
+                play_sequence(&config, &mut history, &mut stats, &timer, &folder, watch).await?
+            }
+
+            Command::Pin { ids } => set_pinned(&config, &mut history, &ids, true)?,
+
+            Command::Unpin { ids } => set_pinned(&config, &mut history, &ids, false)?,
+
+            Command::Pending => {
+                let files = photo_files(&pending_folder)?;
+                if config.json {
+                    let photos: Vec<_> = files
+                        .iter()
+                        .map(|file| photo_json(&history, file))
+                        .collect();
+                    println!("{}", serde_json::Value::Array(photos));
+                } else {
+                    for file in files {
+                        println!("{}", history.photo_id(&file));
+                    }
+                }
+            }
+
+            Command::Approve { ids, all } => {
+                approve_photos(&config, &mut history, &pending_folder, &ids, all)?;
+                delete_old_photos(&config, &history, &timer).await?;
+            }
+
+            Command::Reject { ids, all } => reject_photos(&history, &pending_folder, &ids, all)?,
+
+            Command::Status => status(&config, &history, &stats, &path).await?,
+
+            Command::Why { id } => print_why(&config, &history, id)?,
+
+            Command::Features {
+                action: FeaturesAction::List,
+            } => print_features(&config),
+
+            Command::Stats {
+                summary,
+                pool,
+                sparklines,
+            } => {
+                let tracks = TrackQueue::open(path.join(TRACK_QUEUE_FILE))?;
+                let pool = pool
+                    .then(|| photo_files(&config.folder))
+                    .transpose()?
+                    .map(|files| PoolHistograms::new(&files, &history, POOL_PHOTOGRAPHERS));
+
+                print_stats(&config, &stats, &tracks, summary, pool.as_ref(), sparklines)
+            }
+
+            Command::Serve => {
+                serve(config, history, stats, &config_path, || {
+                    load_config(&path, &cli)
+                })
+                .await?
+            }
+
+            Command::Gallery { out } => {
+                let mut photos = photo_files(&config.folder)?;
+                photos.sort();
+
+                fs::write(out, gallery::gallery(&history, &photos))?;
+            }
+
+            Command::Recap { output, year } => {
+                let year = year.unwrap_or_else(|| Local::now().year());
+                fs::write(output, recap::year_in_review(&history, &stats, year))?;
+            }
+
+            Command::Rollback => {
+                let snapshot = snapshots.rollback()?.ok_or(Error::NoSnapshot)?;
+                println!("Restored the state from {}", snapshot.display());
+            }
+        }
 
         Ok(())
     }
 
-    if let Err(e) = run().await {
-        eprintln!("{}", e);
+    let (_guard, log_level) = init_logging(config::folder());
+
+    let cli = Cli::parse();
+    let json = cli.json;
+    let mut notifications = false;
+    let result = run(cli, log_level, &mut notifications).await;
+    if let Err(e) = &result {
+        tracing::error!("{}", e);
+
+        #[cfg(feature = "notifications")]
+        if notifications {
+            notify_error(e);
+        }
+    }
+
+    match result {
+        Err(Error::Unsplash(unsplash::Error::RateLimitExceeded)) if json => {
+            println!(
+                "{}",
+                serde_json::json!({ "skipped": "rate_limit_exceeded" })
+            );
+
+            return;
+        }
+
+        Err(Error::Unsplash(unsplash::Error::RateLimitExceeded)) => {
+            println!("The Unsplash request quota is used up, skipping this run");
+
+            return;
+        }
+
+        Err(Error::Unsplash(unsplash::Error::BudgetSpent(available_at))) if json => {
+            println!(
+                "{}",
+                serde_json::json!({ "skipped": "budget_spent", "available_at": available_at })
+            );
+
+            return;
+        }
+
+        Err(Error::Unsplash(e @ unsplash::Error::BudgetSpent(_))) => {
+            println!("{}, skipping this run", e);
+
+            return;
+        }
+
+        Err(Error::Unsplash(e)) if json => println!(
+            "{}",
+            serde_json::json!({ "error": e.to_string(), "hint": e.hint() })
+        ),
+
+        Err(e) if json => println!("{}", serde_json::json!({ "error": e.to_string() })),
+
+        Err(Error::Unsplash(e)) => {
+            eprintln!("{}", e);
+
+            if let Some(hint) = e.hint() {
+                eprintln!("Hint: {}", hint);
+            }
+        }
+
+        Err(e) => eprintln!("{}", e),
+
+        Ok(()) => return,
     }
+
+    std::process::exit(1);
 }
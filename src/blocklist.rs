@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Photo;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Blocklist {
+    #[serde(default)]
+    pub photos: Vec<String>,
+    #[serde(default)]
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl Blocklist {
+    pub fn blocks(&self, photo: &Photo) -> bool {
+        if self.photos.iter().any(|id| id == photo.id()) {
+            return true;
+        }
+
+        let username = photo.user().username();
+        if self
+            .users
+            .iter()
+            .any(|user| user.eq_ignore_ascii_case(username))
+        {
+            return true;
+        }
+
+        let text = [photo.description(), photo.alt_description()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        self.keywords
+            .iter()
+            .any(|keyword| text.contains(&keyword.to_lowercase()))
+    }
+}
@@ -0,0 +1,126 @@
+use std::{f32::consts::PI, fs, io, path::Path};
+
+const CHARACTERS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+pub const PLACEHOLDER_WIDTH: u32 = 32;
+pub const PLACEHOLDER_HEIGHT: u32 = 20;
+
+pub fn decode(hash: &str, width: u32, height: u32) -> Option<Vec<[u8; 3]>> {
+    if !hash.is_ascii() {
+        return None;
+    }
+
+    let size = decode83(hash.get(..1)?)?;
+    let components_y = (size / 9 + 1) as usize;
+    let components_x = (size % 9 + 1) as usize;
+    if hash.len() != 4 + 2 * components_x * components_y {
+        return None;
+    }
+
+    let max_value = (decode83(&hash[1..2])? + 1) as f32 / 166.0;
+
+    let mut colors = Vec::with_capacity(components_x * components_y);
+    colors.push(decode_dc(decode83(&hash[2..6])?));
+    for component in 1..components_x * components_y {
+        let start = 4 + component * 2;
+        colors.push(decode_ac(decode83(&hash[start..start + 2])?, max_value));
+    }
+
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let mut pixel = [0.0; 3];
+            for j in 0..components_y {
+                for i in 0..components_x {
+                    let basis = (PI * x as f32 * i as f32 / width as f32).cos()
+                        * (PI * y as f32 * j as f32 / height as f32).cos();
+                    let color = colors[i + j * components_x];
+                    for channel in 0..3 {
+                        pixel[channel] += color[channel] * basis;
+                    }
+                }
+            }
+
+            pixels.push(pixel.map(linear_to_srgb));
+        }
+    }
+
+    Some(pixels)
+}
+
+pub fn write_placeholder<P: AsRef<Path>>(hash: &str, path: P) -> io::Result<()> {
+    let pixels = decode(hash, PLACEHOLDER_WIDTH, PLACEHOLDER_HEIGHT)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid blurhash"))?;
+
+    fs::write(path, bmp(PLACEHOLDER_WIDTH, PLACEHOLDER_HEIGHT, &pixels))
+}
+
+fn bmp(width: u32, height: u32, pixels: &[[u8; 3]]) -> Vec<u8> {
+    let row_size = (width * 3).div_ceil(4) * 4;
+    let image_size = row_size * height;
+
+    let mut data = Vec::with_capacity(54 + image_size as usize);
+    data.extend_from_slice(b"BM");
+    data.extend_from_slice(&(54 + image_size).to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&54u32.to_le_bytes());
+
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    data.extend_from_slice(&(height as i32).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&24u16.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&image_size.to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+
+    for row in pixels.chunks(width as usize).rev() {
+        for [r, g, b] in row {
+            data.extend_from_slice(&[*b, *g, *r]);
+        }
+        data.resize(data.len() + (row_size - width * 3) as usize, 0);
+    }
+
+    data
+}
+
+fn decode83(text: &str) -> Option<u32> {
+    text.bytes().try_fold(0, |value, c| {
+        let digit = CHARACTERS.iter().position(|&character| character == c)?;
+
+        Some(value * 83 + digit as u32)
+    })
+}
+
+fn decode_dc(value: u32) -> [f32; 3] {
+    [value >> 16, (value >> 8) & 255, value & 255].map(|channel| srgb_to_linear(channel as u8))
+}
+
+fn decode_ac(value: u32, max_value: f32) -> [f32; 3] {
+    [value / (19 * 19), (value / 19) % 19, value % 19].map(|quantized| {
+        let normalized = (quantized as f32 - 9.0) / 9.0;
+
+        normalized.signum() * normalized.powi(2) * max_value
+    })
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let srgb = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (srgb * 255.0 + 0.5) as u8
+}
@@ -0,0 +1,63 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{self, Migration};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub count: u32,
+    pub last_failed_at: DateTime<Utc>,
+    pub error: String,
+}
+
+#[derive(Debug)]
+pub struct BrokenPhotos {
+    path: PathBuf,
+    failures: BTreeMap<String, Failure>,
+}
+
+impl BrokenPhotos {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let failures = state::load(&path, MIGRATIONS)?;
+
+        Ok(Self { path, failures })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        state::save(&self.path, &self.failures, MIGRATIONS)
+    }
+
+    pub fn record(&mut self, id: &str, error: String) -> u32 {
+        let failure = self
+            .failures
+            .entry(id.to_owned())
+            .or_insert_with(|| Failure {
+                count: 0,
+                last_failed_at: Utc::now(),
+                error: String::new(),
+            });
+        failure.count += 1;
+        failure.last_failed_at = Utc::now();
+        failure.error = error;
+
+        failure.count
+    }
+
+    pub fn clear(&mut self, id: &str) -> bool {
+        self.failures.remove(id).is_some()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&String, &Failure)> {
+        self.failures.iter()
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::Photo;
+
+const METADATA_FILE: &str = "bundle.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    photo: Photo,
+    file_name: String,
+}
+
+pub fn create<P: AsRef<Path>>(output: P, photos: &[(Photo, PathBuf)]) -> io::Result<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(File::create(output)?);
+
+    let mut entries = Vec::new();
+    for (photo, path) in photos {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        zip.start_file(file_name.as_str(), options)?;
+        io::copy(&mut File::open(path)?, &mut zip)?;
+
+        entries.push(Entry {
+            photo: photo.clone(),
+            file_name,
+        });
+    }
+
+    zip.start_file(METADATA_FILE, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&entries).map_err(Into::<io::Error>::into)?)?;
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+pub fn apply<P: AsRef<Path>, Q: AsRef<Path>>(
+    bundle: P,
+    folder: Q,
+) -> io::Result<Vec<(Photo, PathBuf)>> {
+    let folder = folder.as_ref();
+    let mut zip = ZipArchive::new(File::open(bundle)?)?;
+
+    let mut content = String::new();
+    zip.by_name(METADATA_FILE)?.read_to_string(&mut content)?;
+    let entries: Vec<Entry> = serde_json::from_str(&content).map_err(Into::<io::Error>::into)?;
+
+    fs::create_dir_all(folder)?;
+
+    let mut photos = Vec::new();
+    for entry in entries {
+        let file_name = Path::new(&entry.file_name)
+            .file_name()
+            .ok_or_else(|| io::Error::other(format!("Invalid file name `{}`", entry.file_name)))?;
+        let path = folder.join(file_name);
+
+        io::copy(
+            &mut zip.by_name(&entry.file_name)?,
+            &mut File::create(&path)?,
+        )?;
+
+        photos.push((entry.photo, path));
+    }
+
+    Ok(photos)
+}
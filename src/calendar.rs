@@ -0,0 +1,224 @@
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveTime, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+
+const MAX_GAP_MINUTES: i64 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        let time = at.time();
+
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    pub fn end_after<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        next(at, &[self.end])
+    }
+}
+
+pub fn resolve<Tz: TimeZone>(tz: &Tz, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Tz>> {
+    let local = date.and_time(time);
+
+    earliest(tz.from_local_datetime(&local)).or_else(|| {
+        (1..=MAX_GAP_MINUTES).find_map(|minutes| {
+            earliest(tz.from_local_datetime(&(local + TimeDelta::minutes(minutes))))
+        })
+    })
+}
+
+pub fn next<Tz: TimeZone>(after: &DateTime<Tz>, times: &[NaiveTime]) -> Option<DateTime<Tz>> {
+    let today = after.date_naive();
+
+    (0..=2)
+        .filter_map(|days| today.checked_add_signed(TimeDelta::days(days)))
+        .flat_map(|date| {
+            times
+                .iter()
+                .filter_map(move |&time| resolve(&after.timezone(), date, time))
+        })
+        .filter(|at| at > after)
+        .min()
+}
+
+pub fn previous<Tz: TimeZone>(before: &DateTime<Tz>, times: &[NaiveTime]) -> Option<DateTime<Tz>> {
+    let today = before.date_naive();
+
+    (0..=2)
+        .filter_map(|days| today.checked_sub_signed(TimeDelta::days(days)))
+        .flat_map(|date| {
+            times
+                .iter()
+                .filter_map(move |&time| resolve(&before.timezone(), date, time))
+        })
+        .filter(|at| at <= before)
+        .max()
+}
+
+fn earliest<Tz: TimeZone>(result: LocalResult<DateTime<Tz>>) -> Option<DateTime<Tz>> {
+    match result {
+        LocalResult::Single(at) => Some(at),
+        LocalResult::Ambiguous(first, second) => Some(first.min(second)),
+        LocalResult::None => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{FixedOffset, NaiveDateTime, Timelike};
+
+    use super::*;
+
+    // A zone following the 2026 European rules: clocks go from 02:00 to 03:00
+    // on March 29 and from 03:00 back to 02:00 on October 25.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct Dst;
+
+    impl Dst {
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(60 * 60).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(2 * 60 * 60).unwrap()
+        }
+    }
+
+    impl TimeZone for Dst {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Dst
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let offsets: Vec<_> = [Dst::summer(), Dst::winter()]
+                .into_iter()
+                .filter(|&offset| self.offset_from_utc_datetime(&(*local - offset)) == offset)
+                .collect();
+
+            match offsets[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest, ..] => LocalResult::Ambiguous(earliest, latest),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let spring = naive(2026, 3, 29, 1, 0);
+            let fall = naive(2026, 10, 25, 1, 0);
+
+            if (spring..fall).contains(utc) {
+                Dst::summer()
+            } else {
+                Dst::winter()
+            }
+        }
+    }
+
+    fn naive(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    pub(crate) fn local(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Dst> {
+        Dst.from_local_datetime(&naive(2026, month, day, hour, minute))
+            .earliest()
+            .unwrap()
+    }
+
+    pub(crate) fn assert_fires_once_a_day(mut fires: impl Iterator<Item = DateTime<Dst>>) {
+        let mut previous = fires.next().unwrap();
+        for at in fires {
+            assert!(at > previous, "{} fired before {}", at, previous);
+            assert_eq!(at.date_naive(), previous.date_naive().succ_opt().unwrap());
+            previous = at;
+        }
+    }
+
+    fn half_past_two() -> NaiveTime {
+        NaiveTime::from_hms_opt(2, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn nonexistent_time_resolves_to_the_end_of_the_gap() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+
+        let at = resolve(&Dst, date, half_past_two()).unwrap();
+
+        assert_eq!(at.naive_local(), naive(2026, 3, 29, 3, 0));
+    }
+
+    #[test]
+    fn ambiguous_time_resolves_to_the_first_occurrence() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 25).unwrap();
+
+        let at = resolve(&Dst, date, half_past_two()).unwrap();
+
+        assert_eq!(at.naive_utc(), naive(2026, 10, 25, 0, 30));
+    }
+
+    #[test]
+    fn next_fires_once_across_spring_forward() {
+        let mut at = local(3, 27, 12, 0);
+        let fires = std::iter::from_fn(|| {
+            at = next(&at, &[half_past_two()])?;
+            Some(at)
+        });
+
+        assert_fires_once_a_day(fires.take(5));
+    }
+
+    #[test]
+    fn next_fires_once_across_fall_back() {
+        let mut at = local(10, 23, 12, 0);
+        let fires = std::iter::from_fn(|| {
+            at = next(&at, &[half_past_two()])?;
+            Some(at)
+        });
+
+        assert_fires_once_a_day(fires.take(5));
+    }
+
+    #[test]
+    fn previous_ignores_the_repeated_hour() {
+        let first = local(10, 25, 2, 30);
+        let repeated = first + TimeDelta::minutes(75);
+        assert_eq!(repeated.time().hour(), 2);
+
+        assert_eq!(previous(&repeated, &[half_past_two()]), Some(first));
+    }
+
+    #[test]
+    fn quiet_hours_end_after_the_gap() {
+        let quiet_hours = QuietHours {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: half_past_two(),
+        };
+        let at = local(3, 29, 1, 0);
+
+        assert!(quiet_hours.contains(&at));
+        assert_eq!(
+            quiet_hours.end_after(&at).unwrap().naive_local(),
+            naive(2026, 3, 29, 3, 0)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Change {
+    Added { id: String, path: PathBuf },
+    Removed { id: String, path: PathBuf },
+    Set { id: String, path: PathBuf },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Clone)]
+pub struct Changelog {
+    path: PathBuf,
+}
+
+impl Changelog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn append(&self, change: Change) -> io::Result<()> {
+        self.append_all([change])
+    }
+
+    pub fn append_all<I: IntoIterator<Item = Change>>(&self, changes: I) -> io::Result<()> {
+        let at = Utc::now();
+
+        let mut lines = String::new();
+        for change in changes {
+            lines.push_str(&serde_json::to_string(&Record { at, change })?);
+            lines.push('\n');
+        }
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())
+    }
+}
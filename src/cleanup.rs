@@ -0,0 +1,390 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribution,
+    filesystem::{File, Filesystem, RealFs},
+    history::History,
+    lock, PART_EXTENSION,
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub size: u64,
+    pub downloaded_at: SystemTime,
+    pub protected: bool,
+    pub shown: bool,
+    pub expires_at: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Expired,
+    Age,
+    Count,
+    Size,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Eviction {
+    pub path: PathBuf,
+    pub size: u64,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    #[serde(default)]
+    pub delete_unshown: bool,
+}
+
+impl RetentionPolicy {
+    pub fn select<'a>(&self, candidates: &'a [Candidate], now: SystemTime) -> Vec<&'a Candidate> {
+        self.evict(candidates, now)
+            .into_iter()
+            .map(|(candidate, _)| candidate)
+            .collect()
+    }
+
+    pub fn evict<'a>(
+        &self,
+        candidates: &'a [Candidate],
+        now: SystemTime,
+    ) -> Vec<(&'a Candidate, Reason)> {
+        let max_age = self
+            .max_age_days
+            .map(|days| Duration::from_secs(days * SECONDS_PER_DAY));
+
+        let mut size: u64 = candidates.iter().map(|candidate| candidate.size).sum();
+        let mut count = candidates.len();
+
+        let (expired, candidates): (Vec<_>, Vec<_>) = candidates.iter().partition(|candidate| {
+            candidate
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+        });
+
+        let mut evicted = Vec::new();
+        for file in expired {
+            tracing::debug!(path = %file.path.display(), "selected expired photo for cleanup");
+
+            size -= file.size;
+            count -= 1;
+            evicted.push((file, Reason::Expired));
+        }
+
+        let mut files: Vec<_> = candidates
+            .into_iter()
+            .filter(|candidate| !candidate.protected)
+            .filter(|candidate| candidate.shown || self.delete_unshown)
+            .collect();
+        files.sort_by_key(|candidate| candidate.downloaded_at);
+
+        for file in files {
+            let too_old = max_age.is_some_and(|max_age| {
+                now.duration_since(file.downloaded_at)
+                    .is_ok_and(|age| age > max_age)
+            });
+            let too_many = self.max_count.is_some_and(|max_count| count > max_count);
+            let too_large = self.max_size.is_some_and(|max_size| size > max_size);
+
+            let reason = if too_old {
+                Reason::Age
+            } else if too_many {
+                Reason::Count
+            } else if too_large {
+                Reason::Size
+            } else {
+                break;
+            };
+
+            tracing::debug!(
+                path = %file.path.display(),
+                too_old,
+                too_many,
+                too_large,
+                "selected photo for cleanup"
+            );
+
+            size -= file.size;
+            count -= 1;
+            evicted.push((file, reason));
+        }
+
+        evicted
+    }
+}
+
+pub fn scan<P: AsRef<Path>>(folder: P, history: &History) -> io::Result<Vec<Candidate>> {
+    scan_with(&RealFs, folder, history)
+}
+
+pub fn scan_with<F: Filesystem + ?Sized, P: AsRef<Path>>(
+    fs: &F,
+    folder: P,
+    history: &History,
+) -> io::Result<Vec<Candidate>> {
+    let mut candidates = Vec::new();
+    for file in list_recursive(fs, folder.as_ref())? {
+        let path = file.path;
+        if is_partial(&path) || attribution::is_sidecar(&path) || lock::is_lock_file(&path) {
+            continue;
+        }
+
+        let id = history.photo_id(&path);
+        let entry = history.get(&id);
+
+        let downloaded_at = match entry {
+            Some(entry) => entry.downloaded_at.into(),
+            None => file.modified.unwrap_or(UNIX_EPOCH),
+        };
+
+        candidates.push(Candidate {
+            protected: entry.is_some_and(|entry| entry.pinned),
+            shown: entry.is_none_or(|entry| entry.shown),
+            expires_at: entry.and_then(|entry| entry.expires_at).map(Into::into),
+            size: file.size,
+            downloaded_at,
+            path,
+        });
+    }
+
+    Ok(candidates)
+}
+
+pub fn clean<P: AsRef<Path>>(
+    folder: P,
+    policy: &RetentionPolicy,
+    history: &History,
+) -> io::Result<Vec<Eviction>> {
+    clean_with(&RealFs, folder, policy, history, SystemTime::now())
+}
+
+pub fn clean_with<F: Filesystem + ?Sized, P: AsRef<Path>>(
+    fs: &F,
+    folder: P,
+    policy: &RetentionPolicy,
+    history: &History,
+    now: SystemTime,
+) -> io::Result<Vec<Eviction>> {
+    clean_with_quotas(fs, folder, policy, &BTreeMap::new(), history, now)
+}
+
+pub fn clean_with_quotas<F: Filesystem + ?Sized, P: AsRef<Path>>(
+    fs: &F,
+    folder: P,
+    policy: &RetentionPolicy,
+    quotas: &BTreeMap<PathBuf, RetentionPolicy>,
+    history: &History,
+    now: SystemTime,
+) -> io::Result<Vec<Eviction>> {
+    let folder = folder.as_ref();
+    remove_partial_files(fs, folder)?;
+
+    let candidates = scan_with(fs, folder, history)?;
+
+    let mut deleted = Vec::new();
+    for (candidate, reason) in evict_with_quotas(folder, &candidates, policy, quotas, now) {
+        tracing::info!(path = %candidate.path.display(), ?reason, "deleting photo");
+
+        fs.remove_file(&candidate.path)?;
+        match fs.remove_file(&attribution::sidecar_path(&candidate.path)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        deleted.push(Eviction {
+            path: candidate.path,
+            size: candidate.size,
+            reason,
+        });
+    }
+
+    Ok(deleted)
+}
+
+pub fn select_with_quotas(
+    folder: &Path,
+    candidates: &[Candidate],
+    policy: &RetentionPolicy,
+    quotas: &BTreeMap<PathBuf, RetentionPolicy>,
+    now: SystemTime,
+) -> Vec<Candidate> {
+    evict_with_quotas(folder, candidates, policy, quotas, now)
+        .into_iter()
+        .map(|(candidate, _)| candidate)
+        .collect()
+}
+
+pub fn evict_with_quotas(
+    folder: &Path,
+    candidates: &[Candidate],
+    policy: &RetentionPolicy,
+    quotas: &BTreeMap<PathBuf, RetentionPolicy>,
+    now: SystemTime,
+) -> Vec<(Candidate, Reason)> {
+    let mut groups: Vec<(&RetentionPolicy, Vec<Candidate>)> = vec![(policy, Vec::new())];
+    groups.extend(quotas.values().map(|quota| (quota, Vec::new())));
+
+    for candidate in candidates {
+        let group = quotas
+            .keys()
+            .enumerate()
+            .filter(|(_, subfolder)| candidate.path.starts_with(folder.join(subfolder)))
+            .max_by_key(|(_, subfolder)| subfolder.components().count())
+            .map_or(0, |(index, _)| index + 1);
+
+        groups[group].1.push(candidate.clone());
+    }
+
+    groups
+        .iter()
+        .flat_map(|(policy, candidates)| policy.evict(candidates, now))
+        .map(|(candidate, reason)| (candidate.clone(), reason))
+        .collect()
+}
+
+fn list_recursive<F: Filesystem + ?Sized>(fs: &F, folder: &Path) -> io::Result<Vec<File>> {
+    let mut files = fs.list(folder)?;
+    for subfolder in fs.list_folders(folder)? {
+        files.extend(list_recursive(fs, &subfolder)?);
+    }
+
+    Ok(files)
+}
+
+fn remove_partial_files<F: Filesystem + ?Sized>(fs: &F, folder: &Path) -> io::Result<()> {
+    for file in list_recursive(fs, folder)? {
+        if is_partial(&file.path) {
+            tracing::debug!(path = %file.path.display(), "removing partial download");
+            fs.remove_file(&file.path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == PART_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(SECONDS_PER_DAY);
+
+    fn candidate(name: &str, age_days: u32, shown: bool, now: SystemTime) -> Candidate {
+        Candidate {
+            path: PathBuf::from(name),
+            size: 100,
+            downloaded_at: now - DAY * age_days,
+            protected: false,
+            shown,
+            expires_at: None,
+        }
+    }
+
+    fn candidates(now: SystemTime) -> Vec<Candidate> {
+        vec![
+            candidate("unshown-oldest", 40, false, now),
+            candidate("shown-old", 30, true, now),
+            candidate("unshown-old", 20, false, now),
+            candidate("shown-new", 1, true, now),
+        ]
+    }
+
+    fn selected(
+        policy: &RetentionPolicy,
+        candidates: &[Candidate],
+        now: SystemTime,
+    ) -> Vec<String> {
+        policy
+            .select(candidates, now)
+            .into_iter()
+            .map(|candidate| candidate.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn count_keeps_unshown_photos() {
+        let now = SystemTime::now();
+        let policy = RetentionPolicy {
+            max_count: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            selected(&policy, &candidates(now), now),
+            ["shown-old", "shown-new"]
+        );
+    }
+
+    #[test]
+    fn age_keeps_unshown_photos() {
+        let now = SystemTime::now();
+        let policy = RetentionPolicy {
+            max_age_days: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(selected(&policy, &candidates(now), now), ["shown-old"]);
+    }
+
+    #[test]
+    fn size_keeps_unshown_photos() {
+        let now = SystemTime::now();
+        let policy = RetentionPolicy {
+            max_size: Some(0),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            selected(&policy, &candidates(now), now),
+            ["shown-old", "shown-new"]
+        );
+    }
+
+    #[test]
+    fn delete_unshown_includes_unshown_photos() {
+        let now = SystemTime::now();
+        let policy = RetentionPolicy {
+            max_count: Some(1),
+            delete_unshown: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            selected(&policy, &candidates(now), now),
+            ["unshown-oldest", "shown-old", "unshown-old"]
+        );
+    }
+
+    #[test]
+    fn expired_photos_are_evicted_even_when_unshown() {
+        let now = SystemTime::now();
+        let mut candidates = candidates(now);
+        candidates[0].expires_at = Some(now - DAY);
+
+        let evicted = RetentionPolicy::default().evict(&candidates, now);
+
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.path, Path::new("unshown-oldest"));
+        assert_eq!(evicted[0].1, Reason::Expired);
+    }
+}
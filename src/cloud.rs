@@ -0,0 +1,163 @@
+use std::{
+    env,
+    ffi::OsString,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+const RETRIES: u32 = 5;
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncClient {
+    #[serde(rename = "onedrive")]
+    OneDrive,
+    Dropbox,
+}
+
+impl SyncClient {
+    pub fn folder(self) -> Option<PathBuf> {
+        match self {
+            SyncClient::OneDrive => ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"]
+                .into_iter()
+                .find_map(env::var_os)
+                .map(PathBuf::from)
+                .or_else(|| Some(dirs::home_dir()?.join("OneDrive"))),
+
+            SyncClient::Dropbox => dropbox_info_paths()
+                .into_iter()
+                .find_map(|path| dropbox_folder(&path))
+                .or_else(|| Some(dirs::home_dir()?.join("Dropbox"))),
+        }
+        .filter(|folder| folder.is_dir())
+    }
+}
+
+impl fmt::Display for SyncClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SyncClient::OneDrive => "OneDrive",
+            SyncClient::Dropbox => "Dropbox",
+        })
+    }
+}
+
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let path = path.as_ref();
+
+    let mut delay = INITIAL_DELAY;
+    for _ in 0..RETRIES {
+        match fs::read_to_string(path) {
+            Err(err) if is_not_downloaded(path, &err) => {
+                tracing::debug!(
+                    path = %path.display(),
+                    error = %err,
+                    "waiting for the sync client to download the file"
+                );
+
+                thread::sleep(delay);
+                delay *= 2;
+            }
+
+            result => return result,
+        }
+    }
+
+    fs::read_to_string(path).map_err(|err| {
+        if is_not_downloaded(path, &err) {
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "{} has not been downloaded by the sync client yet, \
+                     make sure it is running or keep the file available offline ({})",
+                    path.display(),
+                    err
+                ),
+            )
+        } else {
+            err
+        }
+    })
+}
+
+pub fn exists<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+
+    path.exists() || icloud_placeholder(path).is_some_and(|placeholder| placeholder.exists())
+}
+
+fn dropbox_info_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<_> = [dirs::data_dir(), dirs::data_local_dir()]
+        .into_iter()
+        .flatten()
+        .map(|folder| folder.join("Dropbox").join("info.json"))
+        .collect();
+    paths.extend(dirs::home_dir().map(|home| home.join(".dropbox").join("info.json")));
+
+    paths
+}
+
+fn dropbox_folder(info_path: &Path) -> Option<PathBuf> {
+    let info: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(info_path).ok()?).ok()?;
+
+    ["personal", "business"]
+        .into_iter()
+        .find_map(|account| info[account]["path"].as_str())
+        .map(PathBuf::from)
+}
+
+fn is_not_downloaded(path: &Path, err: &io::Error) -> bool {
+    match err.kind() {
+        io::ErrorKind::NotFound => {
+            icloud_placeholder(path).is_some_and(|placeholder| placeholder.exists())
+        }
+
+        _ => is_cloud_file_error(err),
+    }
+}
+
+fn icloud_placeholder(path: &Path) -> Option<PathBuf> {
+    let mut name = OsString::from(".");
+    name.push(path.file_name()?);
+    name.push(".icloud");
+
+    Some(path.with_file_name(name))
+}
+
+#[cfg(windows)]
+fn is_cloud_file_error(err: &io::Error) -> bool {
+    use windows::Win32::Foundation::{
+        ERROR_CLOUD_FILE_IN_USE, ERROR_CLOUD_FILE_NETWORK_UNAVAILABLE,
+        ERROR_CLOUD_FILE_PROVIDER_NOT_RUNNING, ERROR_CLOUD_FILE_PROVIDER_TERMINATED,
+        ERROR_CLOUD_FILE_REQUEST_ABORTED, ERROR_CLOUD_FILE_REQUEST_CANCELED,
+        ERROR_CLOUD_FILE_REQUEST_TIMEOUT, ERROR_CLOUD_FILE_UNSUCCESSFUL,
+    };
+
+    let Some(code) = err.raw_os_error() else {
+        return false;
+    };
+
+    [
+        ERROR_CLOUD_FILE_IN_USE,
+        ERROR_CLOUD_FILE_NETWORK_UNAVAILABLE,
+        ERROR_CLOUD_FILE_PROVIDER_NOT_RUNNING,
+        ERROR_CLOUD_FILE_PROVIDER_TERMINATED,
+        ERROR_CLOUD_FILE_REQUEST_ABORTED,
+        ERROR_CLOUD_FILE_REQUEST_CANCELED,
+        ERROR_CLOUD_FILE_REQUEST_TIMEOUT,
+        ERROR_CLOUD_FILE_UNSUCCESSFUL,
+    ]
+    .iter()
+    .any(|error| error.0 as i32 == code)
+}
+
+#[cfg(not(windows))]
+fn is_cloud_file_error(_err: &io::Error) -> bool {
+    false
+}
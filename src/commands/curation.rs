@@ -0,0 +1,349 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use super::{
+    changelog, current_photo, explanations, photo_files,
+    rotation::{ask_feedback, Feedback},
+    Error, Result, TASTE_FILE,
+};
+use crate::{
+    attribution,
+    changelog::Change,
+    config::{self, Config},
+    history::{self, History},
+    taste::{Signal, Taste},
+    Query,
+};
+
+pub fn print_why(config: &Config, history: &History, id: Option<String>) -> Result<()> {
+    let id = match id {
+        Some(id) => id,
+        None => match current_photo(config)? {
+            Some(path) => history.photo_id(path),
+            None => {
+                println!("The current wallpaper was not set by Backdrop");
+
+                return Ok(());
+            }
+        },
+    };
+    let explanations = explanations().for_photo(&id)?;
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "id": id,
+                "selections": explanations,
+            })
+        );
+
+        return Ok(());
+    }
+
+    let Some(latest) = explanations.last() else {
+        println!("Photo {} has not been chosen as the wallpaper yet", id);
+
+        return Ok(());
+    };
+    let context = &latest.context;
+
+    println!(
+        "Photo {} was chosen {} time(s), last on {}",
+        id,
+        explanations.len(),
+        latest.at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    );
+    println!("Source: {}", context.source);
+    match context.strategy {
+        Some(strategy) => println!(
+            "Strategy: {} out of {} candidate(s)",
+            strategy, context.candidates
+        ),
+        None => println!("Candidates: {}", context.candidates),
+    }
+    match &context.query {
+        Some(Query::Text(text)) => println!("Query: \"{}\"", text),
+        Some(Query::Topic(topic)) => println!("Query: topic {}", topic),
+        Some(Query::Topics(topics)) => println!("Query: topics {}", topics.join(", ")),
+        None => println!("Query: none"),
+    }
+    if !context.filters.is_empty() {
+        println!("Filters passed: {}", context.filters.join(", "));
+    }
+    if !context.scores.is_empty() {
+        println!("Scores:");
+        for (name, value) in &context.scores {
+            println!("  {}: {:.2}", name, value);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn spotlight<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    config_path: P,
+) -> Result<()> {
+    let Some(path) = current_photo(config)? else {
+        println!("The current wallpaper was not set by Backdrop");
+
+        return Ok(());
+    };
+
+    let id = history.photo_id(&path);
+    let url = history
+        .get(&id)
+        .map_or_else(|| Some(history::photo_url(&id)), history::Entry::photo_url);
+    let message = match url {
+        Some(url) => format!("Photo {} on Unsplash\n{}", id, url),
+        None => format!("Photo {}", id),
+    };
+
+    match ask_feedback(&message)? {
+        Feedback::Like => {
+            if history.contains(&id) {
+                set_pinned(config, history, &[id], true)?;
+            }
+        }
+
+        Feedback::Dislike => block_photos(config, history, config_path, &[id])?,
+
+        Feedback::Dismiss => {}
+    }
+
+    Ok(())
+}
+
+pub fn block_photos_or_current<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    config_path: P,
+    mut ids: Vec<String>,
+    current: bool,
+) -> Result<()> {
+    if current {
+        let path = current_photo(config)?.ok_or(Error::NotBackdropWallpaper)?;
+        ids.push(history.photo_id(&path));
+    }
+
+    block_photos(config, history, config_path, &ids)
+}
+
+fn block_photos<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    config_path: P,
+    ids: &[String],
+) -> Result<()> {
+    learn(config, history, ids, Signal::Block)?;
+
+    let mut unknown = Vec::new();
+
+    for id in ids {
+        if !history.hide(id) {
+            unknown.push(id.clone());
+        }
+
+        for path in photo_files(&config.folder)? {
+            if history.photo_id(&path) == *id {
+                fs::remove_file(&path)?;
+                changelog().append(Change::Removed {
+                    id: id.clone(),
+                    path,
+                })?;
+            }
+        }
+    }
+
+    history.save()?;
+
+    if !unknown.is_empty() {
+        config::update(config_path, |value| {
+            let photos = &mut value["blocklist"]["photos"];
+            if !photos.is_array() {
+                *photos = serde_json::Value::Array(Vec::new());
+            }
+
+            if let Some(photos) = photos.as_array_mut() {
+                photos.extend(unknown.into_iter().map(serde_json::Value::String));
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn learn(
+    config: &Config,
+    history: &History,
+    ids: &[String],
+    signal: Signal,
+) -> Result<()> {
+    if !config.learning.enabled || ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut taste = Taste::open(config::folder().join(TASTE_FILE))?;
+    for entry in ids.iter().filter_map(|id| history.get(id)) {
+        taste.record(
+            entry.photographer.username(),
+            entry.tags.iter().map(String::as_str),
+            signal,
+        );
+    }
+
+    Ok(taste.save()?)
+}
+
+pub fn print_taste(config: &Config, taste: &Taste) {
+    let weights = taste.weights();
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::to_string(weights).unwrap_or_else(|_| "{}".to_owned())
+        );
+
+        return;
+    }
+
+    if weights.authors.is_empty() && weights.tags.is_empty() {
+        println!("Nothing has been learned yet");
+
+        return;
+    }
+
+    for (title, scores) in [("Photographers", &weights.authors), ("Tags", &weights.tags)] {
+        if scores.is_empty() {
+            continue;
+        }
+
+        println!("{}:", title);
+        let mut scores: Vec<_> = scores.iter().collect();
+        scores.sort_by(|(_, a), (_, b)| a.weight.total_cmp(&b.weight));
+        for (name, score) in scores {
+            println!(
+                "  {:<24} {:>6.2} ({} observations, {:.0}% confidence)",
+                name,
+                score.weight,
+                score.observations,
+                score.confidence() * 100.0
+            );
+        }
+    }
+}
+
+fn find_pending_photos<P: AsRef<Path>>(
+    history: &History,
+    pending_folder: P,
+    ids: &[String],
+    all: bool,
+) -> Result<Vec<PathBuf>> {
+    let files = photo_files(pending_folder)?;
+    if all {
+        return Ok(files);
+    }
+
+    ids.iter()
+        .map(|id| {
+            files
+                .iter()
+                .find(|path| history.photo_id(path) == *id)
+                .cloned()
+                .ok_or_else(|| Error::NotPending(id.clone()))
+        })
+        .collect()
+}
+
+pub fn approve_photos<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    pending_folder: P,
+    ids: &[String],
+    all: bool,
+) -> Result<()> {
+    let pending_folder = pending_folder.as_ref();
+    let files = find_pending_photos(history, pending_folder, ids, all)?;
+
+    for file in files {
+        let path = config
+            .folder
+            .join(file.strip_prefix(pending_folder).unwrap());
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::rename(&file, &path)?;
+        attribution::move_sidecar(&file, &path)?;
+
+        let id = history.photo_id(&file);
+        changelog().append(Change::Added {
+            id: id.clone(),
+            path: path.clone(),
+        })?;
+        if let Some(entry) = history.get_mut(&id) {
+            entry.path = path;
+        }
+    }
+
+    history.save()?;
+
+    Ok(())
+}
+
+pub fn reject_photos<P: AsRef<Path>>(
+    history: &History,
+    pending_folder: P,
+    ids: &[String],
+    all: bool,
+) -> Result<()> {
+    let files = find_pending_photos(history, pending_folder, ids, all)?;
+
+    for file in files {
+        fs::remove_file(&file)?;
+        attribution::remove_sidecar(&file)?;
+    }
+
+    Ok(())
+}
+
+pub fn set_pinned(
+    config: &Config,
+    history: &mut History,
+    ids: &[String],
+    pinned: bool,
+) -> Result<()> {
+    let changed: Vec<_> = ids
+        .iter()
+        .filter(|id| history.get(id).is_some_and(|entry| entry.pinned != pinned))
+        .cloned()
+        .collect();
+    learn(
+        config,
+        history,
+        &changed,
+        if pinned { Signal::Pin } else { Signal::Unpin },
+    )?;
+
+    for id in ids {
+        let entry = history
+            .get_mut(id)
+            .ok_or_else(|| Error::UnknownPhoto(id.clone()))?;
+        entry.pinned = pinned;
+
+        if pinned
+            && let Some(keep_folder) = &config.keep_folder
+            && let Some(file_name) = entry.path.file_name()
+        {
+            fs::create_dir_all(keep_folder)?;
+            fs::copy(&entry.path, keep_folder.join(file_name))?;
+        }
+    }
+
+    history.save()?;
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{client, Result, TOPIC_CACHE_DAYS};
+use crate::{
+    config::{self, Config},
+    history, Client, Mode, Photo, Query, Topic, TopicOrder, WeightedQuery,
+};
+
+pub async fn preview_search(config: &Config, text: &str, limit: u32) -> Result<()> {
+    let client = client(config)?;
+
+    let mut fetch = config.fetch.clone();
+    fetch.count = limit;
+    fetch.queries = vec![WeightedQuery {
+        query: Query::Text(text.to_owned()),
+        weight: 1,
+    }];
+
+    if let Mode::Random = fetch.mode {
+        fetch.mode = Mode::Search(Default::default());
+    }
+
+    print_photos(config, &client.fetch_photos(&fetch).await?);
+
+    Ok(())
+}
+
+pub async fn list_topics(
+    config: &Config,
+    page: u32,
+    per_page: u32,
+    order: TopicOrder,
+) -> Result<()> {
+    let client = client(config)?;
+    let topics = client.list_topics(page, per_page, order).await?;
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::to_string(&topics).map_err(Into::<io::Error>::into)?
+        );
+
+        return Ok(());
+    }
+
+    for topic in topics {
+        println!(
+            "{}  {}  {} photos",
+            topic.slug(),
+            topic.title(),
+            topic.total_photos()
+        );
+
+        let description = topic
+            .description()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !description.is_empty() {
+            println!("    {}", description);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_photos(config: &Config, photos: &[Photo]) {
+    if config.json {
+        let photos: Vec<_> = photos
+            .iter()
+            .map(|photo| {
+                serde_json::json!({
+                    "id": photo.id(),
+                    "photographer": photo.user().name(),
+                    "username": photo.user().username(),
+                    "likes": photo.likes(),
+                    "width": photo.width(),
+                    "height": photo.height(),
+                    "thumb_url": photo.thumb_url(),
+                    "url": history::photo_url(photo.id()),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(photos));
+
+        return;
+    }
+
+    for photo in photos {
+        println!(
+            "{}  {} (@{})  {} likes  {}x{}  {}",
+            photo.id(),
+            photo.user().name(),
+            photo.user().username(),
+            photo.likes(),
+            photo.width(),
+            photo.height(),
+            photo.thumb_url(),
+        );
+    }
+}
+
+pub async fn tune<P: AsRef<Path>>(config: &Config, config_path: P) -> Result<()> {
+    let client = client(config)?;
+
+    println!("Type query filters to refine the results, `reset` to start over,");
+    println!("`save` to store the query in the configuration or `quit` to leave");
+
+    let mut query = config.query.clone().unwrap_or_default();
+    loop {
+        let mut fetch = config.fetch.clone();
+        fetch.apply_query(&query)?;
+
+        println!();
+        println!("Query: {}", query);
+        print_photos(config, &client.fetch_photos(&fetch).await?);
+
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        match line.trim() {
+            "" => {}
+            "quit" => return Ok(()),
+            "reset" => query.clear(),
+            "save" => break,
+
+            filters => {
+                let candidate = format!("{} {}", query, filters).trim().to_owned();
+
+                match config.fetch.clone().apply_query(&candidate) {
+                    Ok(()) => query = candidate,
+                    Err(err) => println!("{}", err),
+                }
+            }
+        }
+    }
+
+    config::update(config_path, |value| {
+        value["query"] = serde_json::Value::String(query);
+    })?;
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TopicCache {
+    fetched_at: DateTime<Utc>,
+    topics: Vec<Topic>,
+}
+
+pub(super) async fn cached_topics<P: AsRef<Path>>(
+    client: &Client,
+    cache_path: P,
+) -> Result<Vec<Topic>> {
+    let cache_path = cache_path.as_ref();
+
+    let cache = fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<TopicCache>(&content).ok())
+        .filter(|cache| Utc::now() - cache.fetched_at < TimeDelta::days(TOPIC_CACHE_DAYS));
+
+    if let Some(cache) = cache {
+        return Ok(cache.topics);
+    }
+
+    let cache = TopicCache {
+        fetched_at: Utc::now(),
+        topics: client.fetch_topics().await?,
+    };
+    fs::write(
+        cache_path,
+        serde_json::to_string_pretty(&cache).map_err(Into::<io::Error>::into)?,
+    )?;
+
+    Ok(cache.topics)
+}
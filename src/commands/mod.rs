@@ -0,0 +1,390 @@
+use std::{
+    collections::HashSet,
+    fs,
+    hash::{BuildHasher, Hasher, RandomState},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, Utc};
+use reqwest::Url;
+use thiserror::Error;
+
+use crate::{
+    attribution,
+    changelog::Changelog,
+    config::{self, Config},
+    daily,
+    explain::Explanations,
+    filesystem::RealFs,
+    history::{self, History},
+    lock::{self, FolderLock},
+    stats::Stats,
+    storage::{FolderStorage, Storage},
+    unsplash, wallpaper, Client, RequestBudget, ResponseCache, TrackQueue, PART_EXTENSION,
+};
+
+mod run;
+pub use run::{refresh_metadata, run};
+
+mod rotation;
+pub use rotation::{play_sequence, print_accent, rotate_offline};
+
+mod retention;
+pub use retention::{delete_old_photos, plan, simulate_cleanup};
+
+mod curation;
+pub use curation::{
+    approve_photos, block_photos_or_current, print_taste, print_why, reject_photos, set_pinned,
+    spotlight,
+};
+
+mod explore;
+pub use explore::{list_topics, preview_search, tune};
+
+mod status;
+pub use status::{doctor, print_features, print_stats, status};
+
+mod transfer;
+pub use transfer::{apply_bundle, create_bundle, import_spotlight, migrate, refetch};
+#[cfg(feature = "frame")]
+pub use transfer::{print_push, push_to_frame};
+
+mod serve;
+pub use serve::serve;
+
+mod setup;
+pub use setup::init;
+#[cfg(feature = "keyring")]
+pub use setup::store_api_key;
+#[cfg(all(feature = "keyring", feature = "s3"))]
+pub use setup::store_s3_secret;
+#[cfg(all(feature = "keyring", feature = "webdav"))]
+pub use setup::store_webdav_password;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Unsplash(#[from] unsplash::Error),
+
+    #[error("{0}")]
+    Daily(#[from] daily::Error),
+
+    #[error("{0}")]
+    Config(#[from] config::Error),
+
+    #[error("Photo {0} is not pending approval")]
+    NotPending(String),
+
+    #[error("No sequence is configured")]
+    NoSequence,
+
+    #[error("{0}")]
+    Query(#[from] unsplash::ParseQueryError),
+
+    #[error("The current wallpaper was not set by Backdrop")]
+    NotBackdropWallpaper,
+
+    #[error("Photo {0} was not downloaded by Backdrop")]
+    UnknownPhoto(String),
+
+    #[error("There is no snapshot to roll back to")]
+    NoSnapshot,
+
+    #[cfg(feature = "parallax")]
+    #[error("{0}")]
+    Parallax(#[from] crate::parallax::Error),
+
+    #[cfg(feature = "span")]
+    #[error("{0}")]
+    Span(#[from] crate::span::Error),
+
+    #[error("{0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("No running Backdrop instance was found, start one with `backdrop serve`")]
+    NotRunning,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub const HISTORY_FILE: &str = "history.json";
+pub const PENDING_FOLDER: &str = "pending";
+pub const STATS_FILE: &str = "stats.json";
+const LAST_RUN_FILE: &str = "last-run";
+const LAST_SUCCESS_FILE: &str = "last-success";
+pub const TOPICS_FILE: &str = "topics.json";
+const CACHE_FILE: &str = "cache.json";
+pub const TRACK_QUEUE_FILE: &str = "pending-pings.json";
+const REQUEST_BUDGET_FILE: &str = "request-budget.json";
+const ACCENT_FILE: &str = "accent";
+const PLACEHOLDER_FILE: &str = "placeholder.bmp";
+const LAST_WALLPAPER_FILE: &str = "last-wallpaper";
+#[cfg(feature = "span")]
+const SPAN_FILE: &str = "span.png";
+const MANIFESTS_FOLDER: &str = "manifests";
+pub const LOGS_FOLDER: &str = "logs";
+pub const LOG_FILE: &str = "backdrop.log";
+pub const BUNDLE_STAGING_FOLDER: &str = "bundle";
+const CHANGELOG_FILE: &str = "changes.jsonl";
+const SELECTIONS_FILE: &str = "selections.jsonl";
+const RUN_LOG_FILE: &str = "runs.jsonl";
+pub const TASTE_FILE: &str = "taste.json";
+const BANDWIDTH_FILE: &str = "bandwidth.json";
+const BROKEN_FILE: &str = "broken.json";
+const MAX_BROKEN_FAILURES: u32 = 3;
+pub const POOL_PHOTOGRAPHERS: usize = 10;
+#[cfg(feature = "frame")]
+const FRAME_STATE_FILE: &str = "frame-sync.json";
+const TOPIC_CACHE_DAYS: i64 = 7;
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn hidden_photo_ids(history: &History) -> HashSet<String> {
+    history
+        .entries()
+        .filter(|entry| entry.hidden)
+        .map(|entry| entry.id.clone())
+        .collect()
+}
+
+fn lock_instance<P: AsRef<Path>>(
+    config: &Config,
+    config_folder: P,
+    wait: bool,
+) -> io::Result<Option<FolderLock>> {
+    let config_folder = config_folder.as_ref();
+    if let Some(lock) = FolderLock::try_acquire(config_folder)? {
+        return Ok(Some(lock));
+    }
+
+    if !wait {
+        return Ok(None);
+    }
+
+    config.print("Waiting for the previous run to finish");
+
+    FolderLock::acquire(config_folder).map(Some)
+}
+
+fn lock_folder(config: &Config) -> io::Result<Option<FolderLock>> {
+    if let Some(lock) = FolderLock::try_acquire(&config.folder)? {
+        return Ok(Some(lock));
+    }
+
+    if !config.shared_folder {
+        return Ok(None);
+    }
+
+    config.print("Waiting for another user to finish updating the shared folder");
+
+    FolderLock::acquire(&config.folder).map(Some)
+}
+
+fn existing_photo_ids(history: &History, folders: &[&Path]) -> io::Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+
+    for folder in folders {
+        for path in photo_files(folder)? {
+            ids.insert(history.photo_id(&path));
+        }
+    }
+
+    Ok(ids)
+}
+
+fn until(at: DateTime<Local>) -> Duration {
+    (at - Local::now()).to_std().unwrap_or(Duration::ZERO)
+}
+
+fn remove_if_exists<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn unseen_photos<P: AsRef<Path>>(
+    folder: P,
+    history: &History,
+    stats: &Stats,
+) -> io::Result<usize> {
+    let seen: HashSet<_> = stats
+        .counters()
+        .changes
+        .iter()
+        .map(|change| change.id.as_str())
+        .collect();
+
+    Ok(photo_files(folder)?
+        .iter()
+        .map(|path| history.photo_id(path))
+        .filter(|id| !seen.contains(id.as_str()) && !history.is_hidden(id))
+        .count())
+}
+
+fn is_in_folder(path: &Path, folder: &Path) -> bool {
+    match (path.parent().map(Path::canonicalize), folder.canonicalize()) {
+        (Some(Ok(parent)), Ok(folder)) => parent == folder,
+        _ => false,
+    }
+}
+
+pub fn photo_json(history: &History, path: &Path) -> serde_json::Value {
+    let id = history.photo_id(path);
+    let entry = history.get(&id);
+
+    serde_json::json!({
+        "id": id,
+        "path": path,
+        "photographer": entry.map(|entry| entry.photographer.name()),
+        "username": entry.map(|entry| entry.photographer.username()),
+        "url": entry.and_then(history::Entry::photo_url),
+    })
+}
+
+pub(crate) fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+fn current_photo(config: &Config) -> io::Result<Option<PathBuf>> {
+    Ok(wallpaper::current()?.filter(|path| is_in_folder(path, &config.folder)))
+}
+
+pub fn photo_files<P: AsRef<Path>>(folder: P) -> io::Result<Vec<PathBuf>> {
+    let folder = folder.as_ref();
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for file in folder.read_dir()? {
+        let path = file?.path();
+        if path.is_dir() {
+            files.extend(photo_files(&path)?);
+        } else if path.is_file()
+            && !attribution::is_sidecar(&path)
+            && !lock::is_lock_file(&path)
+            && path.extension().is_none_or(|ext| ext != PART_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+fn changelog() -> Changelog {
+    Changelog::new(config::folder().join(CHANGELOG_FILE))
+}
+
+fn explanations() -> Explanations {
+    Explanations::new(config::folder().join(SELECTIONS_FILE))
+}
+
+fn read_last_run<P: AsRef<Path>>(path: P) -> io::Result<Option<DateTime<Utc>>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(DateTime::parse_from_rfc3339(content.trim())
+            .ok()
+            .map(|last_run| last_run.to_utc())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn client(config: &Config) -> Result<Client> {
+    #[cfg(feature = "keyring")]
+    let api_key = Client::api_key_from_keyring().or_else(|_| Client::api_key_from_env())?;
+    #[cfg(not(feature = "keyring"))]
+    let api_key = Client::api_key_from_env()?;
+
+    let mut client = Client::builder(&api_key)
+        .network(&config.network)?
+        .build()?
+        .with_retry(config.retry.clone())
+        .with_limits(config.limits.clone())
+        .with_cache(ResponseCache::open(config::folder().join(CACHE_FILE))?)
+        .with_track_queue(TrackQueue::open(config::folder().join(TRACK_QUEUE_FILE))?);
+    if let Some(api_url) = config
+        .api_url
+        .as_deref()
+        .and_then(|url| Url::parse(url).ok())
+    {
+        client = client.with_base_url(api_url);
+    }
+    if let Some(shared_cache) = &config.shared_cache {
+        client = client.with_shared_cache(shared_cache);
+    }
+    if let Some(per_hour) = config.request_budget {
+        client = client.with_request_budget(RequestBudget::open(
+            config::folder().join(REQUEST_BUDGET_FILE),
+            per_hour,
+        )?);
+    }
+
+    Ok(client)
+}
+
+fn storage(config: &Config) -> Result<Arc<dyn Storage>> {
+    Ok(remote_storage(config)?.unwrap_or_else(|| Arc::new(RealFs)))
+}
+
+fn remote_storage(config: &Config) -> Result<Option<Arc<dyn Storage>>> {
+    #[cfg(feature = "s3")]
+    if let Some(s3) = &config.s3 {
+        use crate::s3::{Credentials, S3Storage};
+
+        let secret = match &s3.secret_access_key {
+            Some(secret) => secret.clone(),
+            None => Credentials::lookup_secret()?,
+        };
+        let endpoint = Url::parse(&s3.endpoint).map_err(io::Error::other)?;
+        let credentials = Credentials::new(&s3.access_key_id, secret);
+
+        return Ok(Some(Arc::new(
+            S3Storage::new(
+                endpoint,
+                &s3.bucket,
+                &s3.region,
+                credentials,
+                &config.folder,
+            )
+            .with_prefix(&s3.prefix),
+        )));
+    }
+
+    #[cfg(feature = "webdav")]
+    if let Some(webdav) = &config.webdav {
+        use crate::webdav::{self, WebDavStorage};
+
+        let password = match &webdav.password {
+            Some(password) => password.clone(),
+            None => webdav::lookup_password()?,
+        };
+        let url = Url::parse(&webdav.url).map_err(io::Error::other)?;
+
+        return Ok(Some(Arc::new(WebDavStorage::new(
+            url,
+            &webdav.username,
+            password,
+            &config.folder,
+        ))));
+    }
+
+    if let Some(mirror) = &config.mirror {
+        let folder = mirror.folder().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "The folder of the sync client to mirror into was not found",
+            )
+        })?;
+
+        return Ok(Some(Arc::new(FolderStorage::new(&config.folder, folder))));
+    }
+
+    Ok(None)
+}
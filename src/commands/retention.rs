@@ -0,0 +1,209 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use super::{changelog, remote_storage, Result, SECONDS_PER_DAY};
+use crate::{
+    changelog::Change,
+    cleanup::{self, Eviction, RetentionPolicy},
+    config::Config,
+    filesystem::RealFs,
+    history::History,
+    timing::{Stage, Timer},
+    Mode, Query,
+};
+
+pub async fn delete_old_photos(
+    config: &Config,
+    history: &History,
+    timer: &Timer,
+) -> Result<Vec<Eviction>> {
+    let deleted = timer.time(Stage::Cleanup, || {
+        cleanup::clean_with_quotas(
+            &RealFs,
+            &config.folder,
+            &config.retention_policy(),
+            &config.quotas,
+            history,
+            SystemTime::now(),
+        )
+    })?;
+    changelog().append_all(deleted.iter().map(|eviction| Change::Removed {
+        id: history.photo_id(&eviction.path),
+        path: eviction.path.clone(),
+    }))?;
+
+    if let Some(remote) = remote_storage(config)? {
+        let folder = config.folder.clone();
+        let policy = config.remote_retention_policy();
+        let quotas = config.quotas.clone();
+        let history = history.clone();
+        tokio::task::spawn_blocking(move || {
+            cleanup::clean_with_quotas(
+                &*remote,
+                folder,
+                &policy,
+                &quotas,
+                &history,
+                SystemTime::now(),
+            )
+        })
+        .await
+        .unwrap()?;
+    }
+
+    Ok(deleted)
+}
+
+pub fn plan<P: AsRef<Path>>(config: &Config, history: &History, pending_folder: P) -> Result<()> {
+    if config.motion.is_rotation_frozen() {
+        println!("Rotation is frozen while reduced motion is enabled, nothing would be fetched");
+    } else if let Some(source) = config.provider.daily_source() {
+        println!("Provider: {} image of the day", source);
+        println!("Expected downloads: 1 into {}", config.folder.display());
+    } else {
+        let mode = match &config.fetch.mode {
+            Mode::Random => "random photos",
+            Mode::Search(_) => "search results",
+        };
+        println!("Provider: Unsplash ({})", mode);
+
+        for (query, count) in config.fetch.split_count() {
+            match query {
+                Some(Query::Text(text)) => println!("  {} for \"{}\"", count, text),
+                Some(Query::Topic(topic)) => println!("  {} from topic {}", count, topic),
+                Some(Query::Topics(topics)) => {
+                    println!("  {} from topics {}", count, topics.join(", "))
+                }
+                None => println!("  {} without a query", count),
+            }
+        }
+
+        println!(
+            "Estimated API requests: {}",
+            config.fetch.estimated_requests()
+        );
+
+        let folder = if config.require_approval {
+            pending_folder.as_ref()
+        } else {
+            &config.folder
+        };
+        println!(
+            "Expected downloads: up to {} into {}",
+            config.fetch.count,
+            folder.display()
+        );
+    }
+
+    let candidates = cleanup::scan(&config.folder, history)?;
+    let evicted = cleanup::select_with_quotas(
+        &config.folder,
+        &candidates,
+        &config.retention_policy(),
+        &config.quotas,
+        SystemTime::now(),
+    );
+
+    if evicted.is_empty() {
+        println!("Cleanup: nothing to delete");
+    } else {
+        println!("Cleanup: {} file(s) would be deleted", evicted.len());
+        for candidate in evicted {
+            println!("  {}", candidate.path.display());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn simulate_cleanup(
+    config: &Config,
+    history: &History,
+    policy: &RetentionPolicy,
+    days: u64,
+    every: Duration,
+) -> Result<()> {
+    let mut pool = cleanup::scan(&config.folder, history)?;
+
+    let average_size = match pool.len() as u64 {
+        0 => 0,
+        count => pool.iter().map(|candidate| candidate.size).sum::<u64>() / count,
+    };
+    let per_day = config.fetch.count as u64 * (SECONDS_PER_DAY / every.as_secs().max(1)).max(1);
+
+    println!(
+        "Simulating max size {}, max count {}, max age {} over {} days",
+        policy.max_size.map_or("none".to_owned(), |size| format!(
+            "{:.1} MB",
+            size as f64 / 1_000_000.0
+        )),
+        policy
+            .max_count
+            .map_or("none".to_owned(), |count| count.to_string()),
+        policy
+            .max_age_days
+            .map_or("none".to_owned(), |days| format!("{} days", days)),
+        days
+    );
+    println!(
+        "Expected downloads: {} photos of about {:.1} MB per day",
+        per_day,
+        average_size as f64 / 1_000_000.0
+    );
+
+    let mut simulated = HashSet::new();
+    let start = SystemTime::now();
+    for day in 0..=days {
+        let now = start + Duration::from_secs(day * SECONDS_PER_DAY);
+        if day > 0 {
+            for index in 0..per_day {
+                let path = PathBuf::from(format!("simulated-{}-{}", day, index));
+                simulated.insert(path.clone());
+                pool.push(cleanup::Candidate {
+                    path,
+                    size: average_size,
+                    downloaded_at: now,
+                    protected: false,
+                    shown: true,
+                    expires_at: config
+                        .photo_expiry_days
+                        .map(|days| now + Duration::from_secs(days * SECONDS_PER_DAY)),
+                });
+            }
+        }
+
+        let deleted: HashSet<_> = policy
+            .select(&pool, now)
+            .into_iter()
+            .map(|candidate| candidate.path.clone())
+            .collect();
+        pool.retain(|candidate| !deleted.contains(&candidate.path));
+
+        let label = match day {
+            0 => "Today".to_owned(),
+            day => format!("Day {}", day),
+        };
+        if deleted.is_empty() {
+            println!("{}: nothing to delete", label);
+            continue;
+        }
+
+        println!("{}: {} file(s) would be deleted", label, deleted.len());
+        let mut existing: Vec<_> = deleted.difference(&simulated).collect();
+        existing.sort();
+        for path in &existing {
+            println!("  {}", path.display());
+        }
+        if existing.len() < deleted.len() {
+            println!(
+                "  {} photo(s) downloaded during the simulation",
+                deleted.len() - existing.len()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,507 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{self, Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, Timelike, Utc};
+
+use super::{
+    changelog, client, curation::learn, current_photo, explanations, photo_files, remove_if_exists,
+    until, Error, Result, ACCENT_FILE, LAST_WALLPAPER_FILE, PLACEHOLDER_FILE, SECONDS_PER_DAY,
+    TASTE_FILE,
+};
+use crate::{
+    blurhash, calendar,
+    changelog::Change,
+    config::{self, Component, Config, Sequence},
+    display::Monitor,
+    explain,
+    history::{self, History},
+    selection,
+    stats::Stats,
+    taste::{Signal, Taste},
+    timing::{Stage, Timer},
+    wallpaper, Photo,
+};
+
+async fn prepare_sequence<P: AsRef<Path>>(
+    config: &Config,
+    sequence: &Sequence,
+    folder: P,
+) -> Result<Vec<PathBuf>> {
+    let folder = folder.as_ref();
+    let index_path = folder.join("sequence.json");
+
+    if index_path.exists() {
+        let content = fs::read_to_string(&index_path)?;
+
+        return Ok(serde_json::from_str(&content).map_err(Into::<io::Error>::into)?);
+    }
+
+    let client = client(config)?;
+
+    let photos = client.fetch_collection_photos(&sequence.collection).await?;
+    let order: Vec<_> = photos.iter().map(|photo| photo.id().to_owned()).collect();
+
+    let mut saved = client
+        .download_all(
+            photos,
+            &config.download,
+            folder,
+            config.max_concurrent_downloads,
+        )
+        .await?
+        .into_result()?;
+    saved.sort_by_key(|(photo, _)| order.iter().position(|id| id == photo.id()));
+
+    let paths: Vec<_> = saved.into_iter().map(|(_, path)| path).collect();
+    let content = serde_json::to_string_pretty(&paths).map_err(Into::<io::Error>::into)?;
+    fs::write(&index_path, &content)?;
+
+    Ok(paths)
+}
+
+fn sequence_slot(len: usize, time: NaiveTime) -> (usize, NaiveTime) {
+    const MINUTES_PER_DAY: usize = 24 * 60;
+
+    let minute = (time.hour() * 60 + time.minute()) as usize;
+    let slot = minute * len / MINUTES_PER_DAY;
+
+    let next_minute = ((slot + 1) * MINUTES_PER_DAY).div_ceil(len) % MINUTES_PER_DAY;
+    let next =
+        NaiveTime::from_hms_opt((next_minute / 60) as u32, (next_minute % 60) as u32, 0).unwrap();
+
+    (slot, next)
+}
+
+pub async fn play_sequence<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    folder: P,
+    watch: bool,
+) -> Result<()> {
+    let sequence = config.sequence.as_ref().ok_or(Error::NoSequence)?;
+
+    let paths = prepare_sequence(config, sequence, folder).await?;
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    loop {
+        let now = Local::now();
+        let (slot, next) = sequence_slot(paths.len(), now.time());
+
+        set_wallpaper(
+            config,
+            history,
+            stats,
+            timer,
+            &paths[slot],
+            explain::Context::new(explain::Source::Sequence, paths.len())
+                .with_score("slot", slot as f64),
+        )?;
+
+        if !watch {
+            return Ok(());
+        }
+
+        let next = calendar::next(&now, &[next]).unwrap_or(now + TimeDelta::minutes(1));
+        tokio::time::sleep(until(next).max(Duration::from_secs(1))).await;
+    }
+}
+
+pub(super) fn review_photos(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    photos: &[(Photo, PathBuf)],
+) -> Result<()> {
+    if photos.is_empty() {
+        return Ok(());
+    }
+
+    println!("Choose the new wallpaper:");
+    for (index, (photo, _)) in photos.iter().enumerate() {
+        println!("  [{}] {} ({})", index + 1, photo.id(), photo.thumb_url());
+    }
+
+    let choice = loop {
+        print!("Selection (leave empty to keep the current wallpaper): ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            break None;
+        }
+
+        let line = line.trim();
+        if line.is_empty() {
+            break None;
+        }
+
+        match line.parse::<usize>() {
+            Ok(index) if (1..=photos.len()).contains(&index) => break Some(index - 1),
+            _ => println!("Please enter a number between 1 and {}", photos.len()),
+        }
+    };
+
+    if let Some(index) = choice {
+        let (photo, path) = &photos[index];
+        let context = download_context(config, explain::Source::Review, photo, photos.len());
+
+        set_wallpaper(config, history, stats, timer, path, context)?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn set_wallpaper_per_monitor(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    batches: &[(Monitor, Vec<(Photo, PathBuf)>)],
+) -> Result<()> {
+    let chosen: Vec<_> = batches
+        .iter()
+        .filter_map(|(monitor, photos)| Some((monitor, photos.first()?)))
+        .collect();
+
+    if chosen.is_empty() {
+        return Ok(());
+    }
+
+    let assignments: Vec<_> = chosen
+        .iter()
+        .map(|(monitor, (_, path))| ((*monitor).clone(), path.clone()))
+        .collect();
+    if config.apply_to.contains(&wallpaper::Target::Desktop) {
+        timer.time(Stage::Apply, || {
+            wallpaper::set_per_monitor(&assignments, config.wallpaper)
+        })?;
+    }
+    if config.apply_to.contains(&wallpaper::Target::LockScreen)
+        && let Some((_, path)) = assignments.first()
+    {
+        wallpaper::set_lock_screen(path)?;
+    }
+
+    changelog().append_all(chosen.iter().map(|(_, (photo, path))| Change::Set {
+        id: photo.id().to_owned(),
+        path: path.clone(),
+    }))?;
+    for (_, photos) in batches {
+        if let Some((photo, _)) = photos.first() {
+            let context =
+                download_context(config, explain::Source::PerMonitor, photo, photos.len());
+            explanations().append(photo.id(), context)?;
+        }
+    }
+    for (_, (photo, _)) in chosen {
+        history.mark_shown(photo.id());
+        stats.record_wallpaper_set(photo.id());
+    }
+    history.save()?;
+    stats.save()?;
+
+    Ok(())
+}
+
+pub(super) fn set_wallpaper<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    path: P,
+    context: explain::Context,
+) -> Result<()> {
+    let path = path.as_ref();
+    if config.apply_to.contains(&wallpaper::Target::Desktop) {
+        timer.time(Stage::Apply, || wallpaper::set(path, config.wallpaper))?;
+    }
+    if config.apply_to.contains(&wallpaper::Target::LockScreen) {
+        wallpaper::set_lock_screen(path)?;
+    }
+    tracing::info!(path = %path.display(), "set wallpaper");
+    fs::write(
+        config::folder().join(LAST_WALLPAPER_FILE),
+        path::absolute(path)?.to_string_lossy().as_bytes(),
+    )?;
+
+    let id = history.photo_id(path);
+    changelog().append(Change::Set {
+        id: id.clone(),
+        path: path.to_owned(),
+    })?;
+    explanations().append(&id, context)?;
+    if history.mark_shown(&id) {
+        history.save()?;
+    }
+    stats.record_wallpaper_set(&id);
+    stats.save()?;
+
+    if config.accent {
+        write_accent(config::folder(), history.get(&id))?;
+    }
+
+    #[cfg(feature = "notifications")]
+    if config.notifications {
+        notify_wallpaper_changed(config, path, &id);
+    }
+
+    Ok(())
+}
+
+pub(super) fn wallpaper_changed_manually<P: AsRef<Path>>(config_folder: P) -> io::Result<bool> {
+    let last = match fs::read_to_string(config_folder.as_ref().join(LAST_WALLPAPER_FILE)) {
+        Ok(content) => PathBuf::from(content.trim()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    let Ok(Some(current)) = wallpaper::current() else {
+        return Ok(false);
+    };
+
+    let same = match (fs::canonicalize(&last), fs::canonicalize(&current)) {
+        (Ok(last), Ok(current)) => last == current,
+        _ => last == current,
+    };
+
+    Ok(!same)
+}
+
+fn write_accent<P: AsRef<Path>>(
+    config_folder: P,
+    entry: Option<&history::Entry>,
+) -> io::Result<Option<PathBuf>> {
+    let accent_path = config_folder.as_ref().join(ACCENT_FILE);
+    let placeholder_path = config_folder.as_ref().join(PLACEHOLDER_FILE);
+
+    match entry.and_then(|entry| entry.color.as_deref()) {
+        Some(color) => fs::write(&accent_path, format!("{}\n", color))?,
+        None => remove_if_exists(&accent_path)?,
+    }
+
+    if let Some(blur_hash) = entry.and_then(|entry| entry.blur_hash.as_deref()) {
+        match blurhash::write_placeholder(blur_hash, &placeholder_path) {
+            Ok(()) => return Ok(Some(placeholder_path)),
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                tracing::warn!(blur_hash, "ignoring an invalid blurhash");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    remove_if_exists(&placeholder_path)?;
+
+    Ok(None)
+}
+
+pub fn print_accent(config: &Config, history: &History) -> Result<()> {
+    let entry = current_photo(config)?.and_then(|path| history.get(&history.photo_id(path)));
+    let placeholder = write_accent(config::folder(), entry)?;
+    let color = entry.and_then(|entry| entry.color.as_deref());
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "id": entry.map(|entry| &entry.id),
+                "color": color,
+                "blur_hash": entry.and_then(|entry| entry.blur_hash.as_deref()),
+                "placeholder": placeholder,
+            })
+        );
+    } else {
+        match color {
+            Some(color) => println!("{}", color),
+            None => println!("The color of the current wallpaper is unknown"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "notifications")]
+fn notify_wallpaper_changed(config: &Config, path: &Path, id: &str) {
+    use super::HISTORY_FILE;
+    use crate::attribution;
+
+    let attribution = attribution::read_sidecar(path).ok().flatten().or_else(|| {
+        let history = History::open(config::folder().join(HISTORY_FILE)).ok()?;
+        let entry = history.get(id)?;
+
+        Some(attribution::Attribution {
+            id: entry.id.clone(),
+            photographer: entry.photographer.name().to_owned(),
+            username: entry.photographer.username().to_owned(),
+            profile_url: entry.photographer.profile_url().map(ToOwned::to_owned),
+            description: None,
+            page_url: None,
+        })
+    });
+
+    if let Err(e) =
+        crate::notification::wallpaper_changed(attribution.as_ref(), &config.notification_template)
+    {
+        tracing::warn!("failed to show notification: {}", e);
+    }
+}
+
+pub(super) enum Feedback {
+    Like,
+    Dislike,
+    Dismiss,
+}
+
+#[cfg(windows)]
+pub(super) fn ask_feedback(message: &str) -> io::Result<Feedback> {
+    use windows::{
+        core::HSTRING,
+        Win32::UI::WindowsAndMessaging::{
+            MessageBoxW, IDNO, IDYES, MB_ICONQUESTION, MB_YESNOCANCEL,
+        },
+    };
+
+    let message = format!("{}\n\nYes: pin it\nNo: remove it from the pool", message);
+
+    let result = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(message),
+            &HSTRING::from("Like what you see?"),
+            MB_YESNOCANCEL | MB_ICONQUESTION,
+        )
+    };
+
+    Ok(match result {
+        IDYES => Feedback::Like,
+        IDNO => Feedback::Dislike,
+        _ => Feedback::Dismiss,
+    })
+}
+
+#[cfg(not(windows))]
+pub(super) fn ask_feedback(message: &str) -> io::Result<Feedback> {
+    println!("{}", message);
+    print!("Like what you see? [y] pin it, [n] remove it from the pool: ");
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    Ok(match line.trim() {
+        "y" | "Y" => Feedback::Like,
+        "n" | "N" => Feedback::Dislike,
+        _ => Feedback::Dismiss,
+    })
+}
+
+pub fn rotate_offline(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+) -> Result<()> {
+    let current = current_photo(config)?;
+
+    let candidates: Vec<_> = photo_files(&config.folder)?
+        .into_iter()
+        .filter(|path| {
+            !history.is_hidden(&history.photo_id(path)) && current.as_ref() != Some(path)
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        config.print("There are no downloaded photos to rotate to");
+
+        return Ok(());
+    }
+
+    let choices = selection::choices(&candidates, history, stats);
+    let Some(choice) = config.selection.strategy().select(&choices) else {
+        return Ok(());
+    };
+    let context = rotation_context(config, history, choice, choices.len());
+
+    set_wallpaper(config, history, stats, timer, &choice.path, context)
+}
+
+pub(super) fn skip_wallpaper(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+) -> Result<()> {
+    if let Some(path) = current_photo(config)? {
+        learn(config, history, &[history.photo_id(&path)], Signal::Skip)?;
+    }
+
+    rotate_offline(config, history, stats, timer)
+}
+
+pub(super) fn download_context(
+    config: &Config,
+    source: explain::Source,
+    photo: &Photo,
+    candidates: usize,
+) -> explain::Context {
+    let mut context =
+        explain::Context::new(source, candidates).with_score("likes", photo.likes() as f64);
+    context.query = photo.query().cloned();
+    context.filters = config
+        .pipeline()
+        .iter()
+        .filter_map(Component::filter_description)
+        .collect();
+
+    if config.learning.enabled
+        && let Ok(taste) = Taste::open(config::folder().join(TASTE_FILE))
+    {
+        context.filters.push(format!(
+            "taste score of at least {}",
+            config.learning.min_score
+        ));
+        context = context.with_score("taste", taste.score_photo(photo));
+    }
+
+    context
+}
+
+fn rotation_context(
+    config: &Config,
+    history: &History,
+    choice: &selection::Choice,
+    candidates: usize,
+) -> explain::Context {
+    let now = Utc::now();
+    let days = |since: DateTime<Utc>| (now - since).num_seconds() as f64 / SECONDS_PER_DAY as f64;
+
+    let mut context = explain::Context::new(explain::Source::Rotation, candidates)
+        .with_score("times_shown", choice.times_shown as f64);
+    context.query = history
+        .get(&choice.id)
+        .and_then(|entry| entry.query.clone());
+    context.filters = vec![
+        "not hidden".to_owned(),
+        "not the current wallpaper".to_owned(),
+    ];
+    context.strategy = Some(config.selection);
+
+    if let Some(likes) = choice.likes {
+        context = context.with_score("likes", likes as f64);
+    }
+    if let Some(last_shown) = choice.last_shown {
+        context = context.with_score("days_since_shown", days(last_shown));
+    }
+    if let Some(downloaded_at) = choice.downloaded_at {
+        context = context.with_score("age_days", days(downloaded_at));
+    }
+
+    context
+}
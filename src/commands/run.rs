@@ -0,0 +1,807 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, TimeDelta, Utc};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::StatusCode;
+
+use tokio::{sync::mpsc, task::JoinHandle};
+
+#[cfg(feature = "frame")]
+use super::transfer::push_to_frame;
+use super::{
+    changelog, client, current_photo, existing_photo_ids, hidden_photo_ids, lock_folder,
+    lock_instance, photo_json, read_last_run,
+    retention::delete_old_photos,
+    rotation::{
+        download_context, review_photos, rotate_offline, set_wallpaper, set_wallpaper_per_monitor,
+        wallpaper_changed_manually,
+    },
+    storage, Error, Result, BANDWIDTH_FILE, BROKEN_FILE, HISTORY_FILE, LAST_RUN_FILE,
+    LAST_SUCCESS_FILE, MANIFESTS_FOLDER, MAX_BROKEN_FAILURES, PENDING_FOLDER, REQUEST_BUDGET_FILE,
+    RUN_LOG_FILE, STATS_FILE, TASTE_FILE,
+};
+use crate::{
+    attribution,
+    bandwidth::{Bandwidth, Estimate},
+    broken::BrokenPhotos,
+    changelog::Change,
+    config::{self, Component, ComponentKind, Config, ManualChange},
+    daily,
+    display::{self, Monitor},
+    explain,
+    filesystem::RealFs,
+    history::{self, History},
+    manifest::Manifest,
+    pipeline,
+    stats::Stats,
+    storage::Storage,
+    summary::{RunLog, RunSummary},
+    taste::Taste,
+    timing::Timer,
+    Download, Fetch, Photo, ProgressEvent, RequestBudget, Workflow,
+};
+
+pub async fn run(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    config_folder: &Path,
+    force: bool,
+) -> Result<()> {
+    if config.motion.is_rotation_frozen() {
+        config.print("Rotation is frozen while reduced motion is enabled");
+
+        return print_run(config, history, &[]);
+    }
+
+    let Some(_instance) = lock_instance(config, config_folder, force)? else {
+        config.print("The previous run is still in progress, skipping this run");
+
+        return print_run(config, history, &[]);
+    };
+    *history = History::open(config_folder.join(HISTORY_FILE))?;
+    *stats = Stats::open(config_folder.join(STATS_FILE))?;
+
+    let last_run_path = config_folder.join(LAST_RUN_FILE);
+    let last_run = read_last_run(&last_run_path)?;
+    if !force && config.schedule.is_quiet(&Local::now()) {
+        config.print("Skipping this run during quiet hours");
+
+        return print_run(config, history, &[]);
+    }
+    if !force && !config.schedule.is_due(last_run) {
+        config.print("Skipping this run, the previous one was too recent");
+
+        return print_run(config, history, &[]);
+    }
+
+    let started_at = Utc::now();
+    let start = Instant::now();
+    fs::write(&last_run_path, started_at.to_rfc3339())?;
+
+    if !force {
+        tokio::time::sleep(config.schedule.jitter_delay()).await;
+    }
+
+    let Some(_lock) = lock_folder(config)? else {
+        config.print("Another Backdrop instance is updating the folder, skipping this run");
+
+        return print_run(config, history, &[]);
+    };
+
+    let apply_wallpaper = if config.apply_wallpaper
+        && config.manual_change == ManualChange::Pause
+        && wallpaper_changed_manually(config_folder)?
+    {
+        config.print("The wallpaper was changed outside Backdrop, leaving it in place");
+
+        false
+    } else {
+        config.apply_wallpaper
+    };
+
+    let downloaded: Vec<PathBuf> = if let Some(source) = config.provider.daily_source() {
+        let path = download_daily(config, source).await?;
+
+        if apply_wallpaper {
+            set_wallpaper(
+                config,
+                history,
+                stats,
+                timer,
+                &path,
+                explain::Context::new(explain::Source::Daily, 1),
+            )?;
+        }
+
+        vec![path]
+    } else if config.require_approval {
+        download_photos(
+            config,
+            history,
+            stats,
+            timer,
+            config_folder.join(PENDING_FOLDER),
+        )
+        .await?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect()
+    } else if let Some(downloaded) =
+        span_wallpaper(config, history, stats, timer, apply_wallpaper).await?
+    {
+        downloaded
+    } else if config.per_monitor {
+        let batches = download_per_monitor(config, history, stats, timer).await?;
+
+        #[cfg(feature = "parallax")]
+        for (_, photos) in &batches {
+            export_parallax(config, photos)?;
+        }
+
+        if apply_wallpaper {
+            set_wallpaper_per_monitor(config, history, stats, timer, &batches)?;
+        }
+
+        batches
+            .into_iter()
+            .flat_map(|(_, photos)| photos)
+            .map(|(_, path)| path)
+            .collect()
+    } else {
+        let mut photos = match download_photos(config, history, stats, timer, &config.folder).await
+        {
+            Err(Error::Unsplash(e)) if config.offline_fallback && e.is_unavailable() => {
+                config.print(format_args!(
+                    "{}, rotating through downloaded photos instead",
+                    e
+                ));
+                rotate_offline(config, history, stats, timer)?;
+
+                return print_run(config, history, &[]);
+            }
+
+            result => result?,
+        };
+
+        for fallback in &config.fallbacks {
+            if !photos.is_empty() {
+                break;
+            }
+
+            config.print(format_args!(
+                "No new photos were found, falling back to {}",
+                fallback.description()
+            ));
+
+            let Some(fetch) = fallback.fetch(&config.fetch)? else {
+                rotate_offline(config, history, stats, timer)?;
+
+                return print_run(config, history, &[]);
+            };
+
+            let config = Config {
+                fetch,
+                ..config.clone()
+            };
+            photos = download_photos(&config, history, stats, timer, &config.folder).await?;
+        }
+
+        if photos.is_empty() {
+            config.print("No new photos were found");
+        }
+
+        #[cfg(feature = "parallax")]
+        export_parallax(config, &photos)?;
+
+        if config.review {
+            review_photos(config, history, stats, timer, &photos)?;
+        } else if apply_wallpaper && let Some((photo, path)) = photos.first() {
+            let context = download_context(config, explain::Source::Download, photo, photos.len());
+
+            set_wallpaper(config, history, stats, timer, path, context)?;
+        }
+
+        photos.into_iter().map(|(_, path)| path).collect()
+    };
+
+    if let Some(refresh) = &config.refresh_metadata
+        && let Err(e) = refresh_metadata(
+            config,
+            history,
+            TimeDelta::days(refresh.every_days.into()),
+            Some(refresh.per_run),
+        )
+        .await
+    {
+        tracing::warn!(error = %e, "failed to refresh photo metadata");
+    }
+
+    let evicted = delete_old_photos(config, history, timer).await?;
+
+    #[cfg(feature = "frame")]
+    if let Err(e) = push_to_frame(config, history).await {
+        tracing::warn!(error = %e, "failed to push photos to the frame");
+    }
+
+    fs::write(
+        config_folder.join(LAST_SUCCESS_FILE),
+        Utc::now().to_rfc3339(),
+    )?;
+
+    let summary = RunSummary {
+        started_at,
+        duration_ms: start.elapsed().as_millis() as u64,
+        fetched: downloaded.len(),
+        bytes_downloaded: downloaded
+            .iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        evicted,
+        timings: timer.take(),
+        budget_remaining: config
+            .request_budget
+            .map(|per_hour| {
+                RequestBudget::open(config_folder.join(REQUEST_BUDGET_FILE), per_hour)
+                    .map(|budget| budget.remaining(Utc::now()))
+            })
+            .transpose()?,
+    };
+    summary.log();
+    if config.run_log {
+        RunLog::new(config_folder.join(RUN_LOG_FILE)).append(&summary)?;
+    }
+
+    print_run(config, history, &downloaded)
+}
+
+fn plan_for_bandwidth(config: &Config, estimate: &Estimate) -> (Fetch, Download) {
+    let mut fetch = config.fetch.clone();
+    let mut download = config.download.clone();
+
+    let Some(budget) = config
+        .limits
+        .max_duration_minutes
+        .map(|minutes| Duration::from_secs(minutes * 60))
+    else {
+        return (fetch, download);
+    };
+    let Some(affordable) = estimate.affordable(budget) else {
+        return (fetch, download);
+    };
+
+    if affordable == 0
+        && let Some(fallback) = &download.slow_fallback
+    {
+        tracing::info!(
+            resolution = ?fallback.resolution,
+            "not even one photo fits in the time budget, starting with the fallback resolution"
+        );
+        download.resolution = fallback.resolution.clone();
+    }
+
+    let count = affordable.max(1).min(fetch.count);
+    if count < fetch.count {
+        tracing::info!(
+            count,
+            configured = fetch.count,
+            "fetching fewer photos to fit in the time budget"
+        );
+        fetch.count = count;
+    }
+
+    (fetch, download)
+}
+
+pub(super) async fn download_photos<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    folder: P,
+) -> Result<Vec<(Photo, PathBuf)>> {
+    let client = client(config)?;
+    match client.retry_pending_tracks().await {
+        Ok(0) => {}
+        Ok(tracked) => tracing::info!(tracked, "sent queued download pings"),
+        Err(err) => tracing::warn!(error = %err, "failed to send queued download pings"),
+    }
+
+    let components = config.pipeline();
+    let existing = existing_photo_ids(history, &[&config.folder, folder.as_ref()])?;
+    let in_pool = folder.as_ref() == config.folder;
+    let storage: Arc<dyn Storage> = if in_pool {
+        storage(config)?
+    } else {
+        Arc::new(RealFs)
+    };
+    let hidden = hidden_photo_ids(history);
+    let blocklist = config.blocklist.clone();
+    let taste = config
+        .learning
+        .enabled
+        .then(|| Taste::open(config::folder().join(TASTE_FILE)))
+        .transpose()?;
+    let min_score = config.learning.min_score;
+    let mut bandwidth = Bandwidth::open(config::folder().join(BANDWIDTH_FILE))?;
+    let (fetch, download) = plan_for_bandwidth(config, bandwidth.estimate());
+    let mut broken = BrokenPhotos::open(config::folder().join(BROKEN_FILE))?;
+    let given_up: HashSet<String> = broken
+        .failures()
+        .filter(|(_, failure)| failure.count >= MAX_BROKEN_FAILURES)
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    let (progress, progress_task) = progress_bars();
+    let download_client = match progress {
+        Some(progress) => client.clone().with_progress(progress),
+        None => client.clone(),
+    };
+
+    let filters: Vec<_> = components
+        .iter()
+        .filter(|component| component.kind() == ComponentKind::Filter)
+        .cloned()
+        .collect();
+
+    let workflow = Workflow::new(download_client, fetch, download)
+        .with_max_concurrent_downloads(config.max_concurrent_downloads)
+        .with_processing_workers(config.processing_workers())
+        .with_storage(storage)
+        .with_filter(move |photo| {
+            !given_up.contains(photo.id())
+                && filters.iter().all(|filter| match filter {
+                    Component::SkipExisting => !existing.contains(photo.id()),
+                    Component::SkipHidden => !hidden.contains(photo.id()),
+                    Component::Blocklist => !blocklist.blocks(photo),
+                    Component::MinLikes { likes } => photo.likes() >= *likes,
+                    Component::RequireTags { tags } => tags.iter().all(|tag| {
+                        photo
+                            .tags()
+                            .any(|photo_tag| photo_tag.eq_ignore_ascii_case(tag))
+                    }),
+                    _ => true,
+                })
+                && taste
+                    .as_ref()
+                    .is_none_or(|taste| taste.score_photo(photo) >= min_score)
+        });
+
+    #[cfg(not(any(feature = "metadata", feature = "processing")))]
+    let processor = |_: &pipeline::Downloaded| Ok(());
+
+    #[cfg(any(feature = "metadata", feature = "processing"))]
+    let processor = {
+        let processors: Vec<_> = components
+            .iter()
+            .filter(|component| component.kind() == ComponentKind::Processor)
+            .cloned()
+            .collect();
+        let format = config.download.format.clone();
+        #[cfg(feature = "processing")]
+        let processing = config.processing.clone();
+
+        move |downloaded: &pipeline::Downloaded| {
+            for processor in &processors {
+                match processor {
+                    #[cfg(feature = "processing")]
+                    Component::Process => processing
+                        .apply(&downloaded.photo, &downloaded.temp_path, &format)
+                        .map_err(io::Error::other)?,
+
+                    #[cfg(feature = "metadata")]
+                    Component::EmbedMetadata => crate::metadata::embed_attribution(
+                        &downloaded.photo,
+                        &downloaded.temp_path,
+                        &format,
+                    )?,
+
+                    _ => {}
+                }
+            }
+
+            Ok(())
+        }
+    };
+
+    let start = Instant::now();
+    let (mut report, limit) = workflow
+        .with_processor(processor)
+        .run(folder, history)
+        .await?;
+    timer.merge(std::mem::take(&mut report.timings));
+    if let Some(progress_task) = progress_task {
+        let _ = progress_task.await;
+    }
+
+    let elapsed = start.elapsed();
+    let failed = report.failed.len();
+    for (id, err) in report
+        .failed
+        .iter()
+        .filter_map(|err| Some((err.broken_photo()?, err)))
+    {
+        if broken.record(id, err.to_string()) >= MAX_BROKEN_FAILURES {
+            tracing::warn!(
+                id,
+                failures = MAX_BROKEN_FAILURES,
+                "photo keeps failing verification and will no longer be downloaded"
+            );
+        }
+    }
+    for saved in &report.saved {
+        broken.clear(saved.photo.id());
+    }
+    if let Err(e) = broken.save() {
+        tracing::warn!(error = %e, "failed to save the broken photo index");
+    }
+    let saved = report.into_result()?;
+
+    let bytes: u64 = saved
+        .iter()
+        .filter_map(|saved| fs::metadata(&saved.path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    bandwidth.observe(saved.len(), bytes, elapsed);
+    if let Err(e) = bandwidth.save() {
+        tracing::warn!(error = %e, "failed to save the bandwidth estimate");
+    }
+
+    if failed > 0 {
+        config.print(format_args!(
+            "{} photos could not be downloaded and were skipped",
+            failed
+        ));
+    }
+
+    if !saved.is_empty() {
+        let download_time: Duration = saved.iter().map(|saved| saved.download_time).sum();
+        let processing_time: Duration = saved.iter().map(|saved| saved.processing_time).sum();
+
+        config.print(format_args!(
+            "Downloaded {} photos in {:.1?} (downloading {:.1?}, processing {:.1?})",
+            saved.len(),
+            start.elapsed(),
+            download_time,
+            processing_time,
+        ));
+    }
+
+    if let Some(limit) = limit {
+        config.print(format_args!(
+            "Stopped early, the {} limit was reached after {} requests and {:.1} MB",
+            limit,
+            client.usage().requests(),
+            client.usage().bytes() as f64 / 1_000_000.0
+        ));
+    }
+
+    let manifest = components
+        .iter()
+        .any(|component| matches!(component, Component::Manifest));
+    if manifest && !saved.is_empty() {
+        let mut manifest = Manifest::new(&config.download);
+        for saved in &saved {
+            manifest.push(saved);
+        }
+
+        let manifest_folder = config::folder().join(MANIFESTS_FOLDER);
+        fs::create_dir_all(&manifest_folder)?;
+        manifest.save(
+            manifest_folder.join(format!("{}.json", Local::now().format("%Y%m%d-%H%M%S%3f"))),
+        )?;
+    }
+
+    if in_pool {
+        changelog().append_all(saved.iter().map(|saved| Change::Added {
+            id: saved.photo.id().to_owned(),
+            path: saved.path.clone(),
+        }))?;
+    }
+
+    let sidecars = components
+        .iter()
+        .any(|component| matches!(component, Component::Sidecar));
+    for saved in &saved {
+        if sidecars {
+            attribution::write_sidecar(&saved.photo, &saved.path)?;
+        }
+
+        history.record(
+            history::Entry::new(&saved.photo, &saved.path)
+                .with_sha256(saved.sha256.clone())
+                .with_expires_at(config.photo_expiry()),
+        );
+        if !config.apply_wallpaper {
+            history.mark_shown(saved.photo.id());
+        }
+        stats.record_download(&saved.photo, fs::metadata(&saved.path)?.len());
+    }
+    history.save()?;
+    stats.save()?;
+
+    Ok(saved
+        .into_iter()
+        .map(|saved| (saved.photo, saved.path))
+        .collect())
+}
+
+fn progress_bars() -> (
+    Option<mpsc::UnboundedSender<ProgressEvent>>,
+    Option<JoinHandle<()>>,
+) {
+    if !io::stdout().is_terminal() {
+        return (None, None);
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move {
+        let multi = MultiProgress::new();
+        let mut bars = HashMap::new();
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                ProgressEvent::Started { id, total } => {
+                    let bar = match total {
+                        Some(total) => ProgressBar::new(total).with_style(
+                            ProgressStyle::with_template(
+                                "{msg:12} {bar:30} {bytes}/{total_bytes} {bytes_per_sec}",
+                            )
+                            .unwrap(),
+                        ),
+                        None => ProgressBar::new_spinner().with_style(
+                            ProgressStyle::with_template("{msg:12} {spinner} {bytes}").unwrap(),
+                        ),
+                    };
+
+                    bars.insert(id.clone(), multi.add(bar.with_message(id)));
+                }
+
+                ProgressEvent::Advanced { id, bytes } => {
+                    if let Some(bar) = bars.get(&id) {
+                        bar.inc(bytes);
+                    }
+                }
+
+                ProgressEvent::Finished { id } => {
+                    if let Some(bar) = bars.remove(&id) {
+                        bar.finish_and_clear();
+                    }
+                }
+            }
+        }
+    });
+
+    (Some(tx), Some(task))
+}
+
+pub async fn refresh_metadata(
+    config: &Config,
+    history: &mut History,
+    every: TimeDelta,
+    limit: Option<usize>,
+) -> Result<(usize, usize)> {
+    let cutoff = Utc::now() - every;
+    let mut due: Vec<_> = history
+        .entries()
+        .filter(|entry| entry.is_from_unsplash())
+        .map(|entry| (entry.refreshed_at.unwrap_or(entry.downloaded_at), &entry.id))
+        .filter(|(refreshed_at, _)| *refreshed_at <= cutoff)
+        .collect();
+    due.sort();
+    let mut due: Vec<_> = due.into_iter().map(|(_, id)| id.clone()).collect();
+    if let Some(limit) = limit {
+        due.truncate(limit);
+    }
+
+    if due.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let client = client(config)?;
+
+    let mut refreshed = 0;
+    let mut removed = 0;
+    for id in due {
+        let result = client.fetch_photo(&id).await;
+        let Some(entry) = history.get_mut(&id) else {
+            continue;
+        };
+
+        match result {
+            Ok(photo) => entry.refresh(&photo),
+
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                entry.mark_removed();
+                removed += 1;
+            }
+
+            Err(e) => {
+                history.save()?;
+
+                return Err(e.into());
+            }
+        }
+        refreshed += 1;
+    }
+    history.save()?;
+
+    Ok((refreshed, removed))
+}
+
+#[cfg(feature = "parallax")]
+fn export_parallax(config: &Config, photos: &[(Photo, PathBuf)]) -> Result<()> {
+    use crate::parallax::{self, OnnxDepthEstimator};
+
+    let Some(settings) = &config.parallax else {
+        return Ok(());
+    };
+
+    let estimator = OnnxDepthEstimator::new(&settings.model)?;
+    for (photo, path) in photos {
+        let folder = settings.folder.join(photo.id());
+
+        parallax::export_layers(&estimator, path, folder, settings.layers)?;
+    }
+
+    Ok(())
+}
+
+async fn download_per_monitor(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+) -> Result<Vec<(Monitor, Vec<(Photo, PathBuf)>)>> {
+    let mut batches = Vec::new();
+    for monitor in display::monitors() {
+        let mut monitor_config = config.clone();
+        monitor_config.fetch.orientation = monitor.orientation();
+        monitor_config.download.resolution = monitor.resolution();
+
+        let photos =
+            download_photos(&monitor_config, history, stats, timer, &config.folder).await?;
+        batches.push((monitor, photos));
+    }
+
+    Ok(batches)
+}
+
+#[cfg(feature = "span")]
+async fn span_wallpaper(
+    config: &Config,
+    history: &mut History,
+    stats: &mut Stats,
+    timer: &Timer,
+    apply_wallpaper: bool,
+) -> Result<Option<Vec<PathBuf>>> {
+    use std::path;
+
+    use super::{explanations, LAST_WALLPAPER_FILE, SPAN_FILE};
+    use crate::{
+        config::Span, experimental::Feature, span, timing::Stage, unsplash::Orientation, wallpaper,
+        Resolution,
+    };
+
+    let Some(mode) = config
+        .span
+        .filter(|_| config.experimental.is_enabled(Feature::Span))
+    else {
+        return Ok(None);
+    };
+
+    let monitors = display::monitors();
+    let (_, _, width, height) = display::bounds(&monitors).ok_or(span::Error::NoMonitors)?;
+
+    let photos: Vec<_> = match mode {
+        Span::Stitch => download_per_monitor(config, history, stats, timer)
+            .await?
+            .into_iter()
+            .flat_map(|(_, photos)| photos.into_iter().take(1))
+            .collect(),
+
+        Span::Crop => {
+            let mut span_config = config.clone();
+            span_config.fetch.orientation = Orientation::Landscape;
+            span_config.download.resolution = Resolution::Custom { width, height };
+
+            download_photos(&span_config, history, stats, timer, &config.folder)
+                .await?
+                .into_iter()
+                .take(1)
+                .collect()
+        }
+    };
+
+    if apply_wallpaper && !photos.is_empty() {
+        let paths: Vec<_> = photos.iter().map(|(_, path)| path).collect();
+        let output = config::folder().join(SPAN_FILE);
+        span::compose(&monitors, &paths, &output)?;
+
+        if config.apply_to.contains(&wallpaper::Target::Desktop) {
+            timer.time(Stage::Apply, || {
+                wallpaper::set_spanned(&output, config.wallpaper)
+            })?;
+        }
+        if config.apply_to.contains(&wallpaper::Target::LockScreen) {
+            wallpaper::set_lock_screen(paths[0])?;
+        }
+        tracing::info!(path = %output.display(), "set spanned wallpaper");
+        fs::write(
+            config::folder().join(LAST_WALLPAPER_FILE),
+            path::absolute(&output)?.to_string_lossy().as_bytes(),
+        )?;
+
+        changelog().append_all(photos.iter().map(|(photo, path)| Change::Set {
+            id: photo.id().to_owned(),
+            path: path.clone(),
+        }))?;
+        for (photo, _) in &photos {
+            explanations().append(
+                photo.id(),
+                download_context(config, explain::Source::Span, photo, photos.len()),
+            )?;
+            history.mark_shown(photo.id());
+            stats.record_wallpaper_set(photo.id());
+        }
+        history.save()?;
+        stats.save()?;
+    }
+
+    Ok(Some(photos.into_iter().map(|(_, path)| path).collect()))
+}
+
+#[cfg(not(feature = "span"))]
+async fn span_wallpaper(
+    _config: &Config,
+    _history: &mut History,
+    _stats: &mut Stats,
+    _timer: &Timer,
+    _apply_wallpaper: bool,
+) -> Result<Option<Vec<PathBuf>>> {
+    Ok(None)
+}
+
+async fn download_daily(config: &Config, source: daily::Source) -> Result<PathBuf> {
+    let http = reqwest::Client::new();
+
+    let image = daily::fetch(&http, source).await?;
+    let path = daily::download(&http, &image, &config.folder).await?;
+
+    match &image.copyright {
+        Some(copyright) => config.print(format_args!(
+            "{} image of the day: {} ({})",
+            source, image.title, copyright
+        )),
+        None => config.print(format_args!("{} image of the day: {}", source, image.title)),
+    }
+
+    Ok(path)
+}
+
+fn print_run(config: &Config, history: &History, downloaded: &[PathBuf]) -> Result<()> {
+    if config.json {
+        let wallpaper = current_photo(config)?;
+        println!(
+            "{}",
+            serde_json::json!({
+                "downloaded": downloaded
+                    .iter()
+                    .map(|path| photo_json(history, path))
+                    .collect::<Vec<_>>(),
+                "wallpaper": wallpaper.map(|path| photo_json(history, &path)),
+            })
+        );
+    }
+
+    Ok(())
+}
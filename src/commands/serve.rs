@@ -0,0 +1,349 @@
+use std::{fs, path::Path, time::Duration};
+
+use chrono::{Local, Utc};
+
+use tokio::sync::mpsc;
+
+use super::{
+    curation::{block_photos_or_current, set_pinned},
+    lock_folder, lock_instance, read_last_run,
+    retention::delete_old_photos,
+    rotation::{download_context, rotate_offline, set_wallpaper, skip_wallpaper},
+    run::download_photos,
+    until, Error, Result, LAST_RUN_FILE,
+};
+use crate::{
+    config::{self, Config},
+    experimental::Feature,
+    explain,
+    history::History,
+    stats::Stats,
+    timing::Timer,
+};
+
+const CONFIG_RELOAD_DELAY: Duration = Duration::from_millis(250);
+
+pub async fn serve<P, F>(
+    mut config: Config,
+    mut history: History,
+    mut stats: Stats,
+    config_path: P,
+    reload: F,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: Fn() -> Result<Config>,
+{
+    use crate::control::{self, Action};
+
+    let config_path = config_path.as_ref();
+    let (_watcher, mut changes) = watch_config(config_path)?;
+    let control = control::Listener::bind(config::folder())?;
+
+    let (tx, mut rx) = mpsc::channel(8);
+    tokio::spawn(control.serve(tx.clone()));
+
+    let web_ui = config.experimental.is_enabled(Feature::WebUi);
+    #[cfg(feature = "web")]
+    let status = std::sync::Arc::new(std::sync::RwLock::new(pool_status(
+        &config, &history, false,
+    )?));
+    #[cfg(feature = "web")]
+    let server = {
+        if web_ui {
+            println!("Serving the web UI on http://{}", config.web.address);
+        }
+
+        crate::web::serve(
+            config.web.address,
+            config.web.token.clone(),
+            status.clone(),
+            tx,
+        )
+    };
+    // Without the web UI the daemon is only driven by its schedule and the control channel.
+    #[cfg(not(feature = "web"))]
+    let server = std::future::pending::<std::io::Result<()>>();
+    tokio::pin!(server);
+
+    let every = |config: &Config| config.schedule.every(&config.web);
+    let last_run_path = config::folder().join(LAST_RUN_FILE);
+    let mut next_cycle = if config.schedule.on_login.unwrap_or(true) {
+        Local::now()
+    } else {
+        config.schedule.next_cycle(Local::now(), every(&config))
+    };
+    let mut paused = false;
+
+    loop {
+        let result = tokio::select! {
+            result = &mut server, if web_ui => return Ok(result?),
+
+            _ = tokio::time::sleep(until(next_cycle)), if !paused => {
+                let now = Local::now();
+                next_cycle = config.schedule.next_cycle(now, every(&config));
+
+                if config.schedule.is_quiet(&now) {
+                    tracing::info!("skipping this cycle during quiet hours");
+                    Ok(())
+                } else if config.schedule.is_too_recent(read_last_run(&last_run_path).ok().flatten()) {
+                    tracing::info!("the previous run was too recent, skipping this cycle");
+                    Ok(())
+                } else {
+                    if let Err(err) = fs::write(&last_run_path, Utc::now().to_rfc3339()) {
+                        tracing::warn!(error = %err, "could not record the time of this cycle");
+                    }
+                    serve_cycle(&config, &mut history, &mut stats).await
+                }
+            }
+
+            Some(action) = rx.recv() => match action {
+                Action::Next => skip_wallpaper(&config, &mut history, &mut stats, &Timer::default()),
+
+                Action::Pause => {
+                    paused = true;
+                    Ok(())
+                }
+
+                Action::Resume => {
+                    paused = false;
+                    next_cycle = config.schedule.next_cycle(Local::now(), every(&config));
+                    Ok(())
+                }
+
+                Action::SetQuery(query, reply) => {
+                    let result = set_served_query(&mut config, config_path, query);
+                    let _ = reply.send(result.map_err(|err| err.to_string()));
+                    Ok(())
+                }
+
+                Action::Pin(ids, pinned, reply) => {
+                    let result = set_pinned(&config, &mut history, &ids, pinned);
+                    let _ = reply.send(result.map_err(|err| err.to_string()));
+                    Ok(())
+                }
+
+                Action::Block(ids, current, reply) => {
+                    let result = block_photos_or_current(&config, &mut history, config_path, ids, current);
+                    let _ = reply.send(result.map_err(|err| err.to_string()));
+                    Ok(())
+                }
+            },
+
+            Some(()) = changes.recv() => {
+                tokio::time::sleep(CONFIG_RELOAD_DELAY).await;
+                while changes.try_recv().is_ok() {}
+
+                match reload() {
+                    Ok(reloaded) => {
+                        if reloaded.schedule.every(&reloaded.web) != every(&config)
+                            || reloaded.schedule != config.schedule
+                        {
+                            next_cycle =
+                                reloaded.schedule.next_cycle(Local::now(), every(&reloaded));
+                        }
+
+                        log_config_changes(&config, &reloaded);
+                        config = reloaded;
+                    }
+
+                    Err(err) => {
+                        tracing::warn!(error = %err, "keeping the previous configuration");
+                    }
+                }
+
+                Ok(())
+            }
+        };
+
+        if let Err(err) = result {
+            tracing::error!(error = %err, "serve cycle failed");
+        }
+        match history.reload_if_changed() {
+            Ok(true) => tracing::info!("the history was changed by another process, reloaded it"),
+            Ok(false) => {}
+            Err(err) => tracing::warn!(error = %err, "failed to reload the history"),
+        }
+
+        #[cfg(feature = "web")]
+        match pool_status(&config, &history, paused) {
+            Ok(pool) => *status.write().unwrap() = pool,
+            Err(err) => tracing::warn!(error = %err, "keeping the previous pool status"),
+        }
+    }
+}
+
+fn watch_config(config_path: &Path) -> Result<(notify::RecommendedWatcher, mpsc::Receiver<()>)> {
+    use notify::{RecursiveMode, Watcher};
+
+    let names = [
+        config_path.file_name().map(ToOwned::to_owned),
+        Some(config::ENV_FILE.into()),
+    ];
+    let (tx, rx) = mpsc::channel(1);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        if !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|path| names.iter().any(|name| name.as_deref() == path.file_name()))
+        {
+            let _ = tx.try_send(());
+        }
+    })?;
+
+    let folder = config_path.parent().unwrap_or(Path::new("."));
+    watcher.watch(folder, RecursiveMode::NonRecursive)?;
+
+    Ok((watcher, rx))
+}
+
+fn log_config_changes(old: &Config, new: &Config) {
+    use std::collections::BTreeSet;
+
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return;
+    };
+
+    let keys: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    let changed: Vec<_> = keys
+        .into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(String::as_str)
+        .collect();
+
+    if changed.is_empty() {
+        tracing::debug!("the configuration was reloaded without changes");
+    } else {
+        tracing::info!(changed = %changed.join(", "), "the configuration was reloaded");
+    }
+}
+
+async fn serve_cycle(config: &Config, history: &mut History, stats: &mut Stats) -> Result<()> {
+    let Some(_instance) = lock_instance(config, config::folder(), false)? else {
+        tracing::info!("a scheduled run is in progress, skipping this cycle");
+
+        return Ok(());
+    };
+    let Some(_lock) = lock_folder(config)? else {
+        tracing::info!("another Backdrop instance is updating the folder, skipping this cycle");
+
+        return Ok(());
+    };
+
+    let timer = &Timer::default();
+    match download_photos(config, history, stats, timer, &config.folder).await {
+        Ok(photos) => {
+            if let Some((photo, path)) = photos.first() {
+                let context =
+                    download_context(config, explain::Source::Download, photo, photos.len());
+
+                set_wallpaper(config, history, stats, timer, path, context)?;
+            }
+        }
+
+        Err(Error::Unsplash(e)) if config.offline_fallback && e.is_unavailable() => {
+            rotate_offline(config, history, stats, timer)?
+        }
+
+        Err(err) => return Err(err),
+    }
+
+    delete_old_photos(config, history, timer).await?;
+    crate::timing::log(&timer.take());
+
+    Ok(())
+}
+
+fn set_served_query(config: &mut Config, config_path: &Path, query: String) -> Result<()> {
+    let mut fetch = config.fetch.clone();
+    fetch.apply_query(&query)?;
+
+    let mut problems = Vec::new();
+    config::validate_fetch("fetch", &fetch, &mut problems);
+    if !problems.is_empty() {
+        return Err(config::Error::InvalidConfig(problems).into());
+    }
+
+    config::update(config_path, |value| {
+        value["query"] = serde_json::Value::String(query.clone());
+    })?;
+
+    config.fetch = fetch;
+    config.query = Some(query);
+
+    Ok(())
+}
+
+#[cfg(feature = "web")]
+fn pool_status(config: &Config, history: &History, paused: bool) -> Result<crate::web::Status> {
+    use super::{current_photo, photo_files};
+    use crate::history;
+
+    let mut files = photo_files(&config.folder)?;
+    files.sort();
+
+    let photos = files
+        .into_iter()
+        .map(|path| {
+            let id = history.photo_id(&path);
+            let entry = history.get(&id);
+
+            crate::web::PoolPhoto {
+                photographer: entry.map(|entry| entry.photographer.name().to_owned()),
+                profile_url: entry
+                    .and_then(|entry| entry.photographer.profile_url())
+                    .map(ToOwned::to_owned),
+                url: entry.map_or_else(|| Some(history::photo_url(&id)), history::Entry::photo_url),
+                id,
+                path,
+            }
+        })
+        .filter(|photo| !history.is_hidden(&photo.id))
+        .collect();
+
+    let current = current_photo(config)?.map(|path| history.photo_id(path));
+
+    Ok(crate::web::Status {
+        paused,
+        query: config.query.clone(),
+        current,
+        photos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Fetch};
+
+    #[test]
+    fn served_query_keeps_the_resolved_fetch() {
+        let path = testing::temp_path("served-query.json");
+        fs::write(&path, r#"{ "fetch": { "count": 10 } }"#).unwrap();
+
+        let mut config = Config {
+            fetch: Fetch {
+                count: 7,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = set_served_query(&mut config, &path, "topic:nature".to_owned());
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        result.unwrap();
+        assert_eq!(config.fetch.count, 7);
+        assert_eq!(config.fetch.queries.len(), 1);
+        assert_eq!(saved["query"], "topic:nature");
+        assert_eq!(saved["fetch"]["count"], 10);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use super::Result;
+use crate::{
+    config::{self, Config},
+    redact::Redacted,
+    Client,
+};
+
+#[cfg(all(feature = "keyring", feature = "s3"))]
+pub fn store_s3_secret(key: Option<String>) -> Result<()> {
+    let secret = Redacted::new(match key {
+        Some(key) => key,
+        None => prompt("S3 secret access key", "")?,
+    });
+
+    crate::s3::Credentials::store_secret(&secret)?;
+
+    println!("Stored the secret access key in the credential store");
+
+    Ok(())
+}
+
+#[cfg(all(feature = "keyring", feature = "webdav"))]
+pub fn store_webdav_password(password: Option<String>) -> Result<()> {
+    let password = Redacted::new(match password {
+        Some(password) => password,
+        None => prompt("WebDAV password", "")?,
+    });
+
+    crate::webdav::store_password(&password)?;
+
+    println!("Stored the WebDAV password in the credential store");
+
+    Ok(())
+}
+
+#[cfg(feature = "keyring")]
+pub async fn store_api_key(key: Option<String>) -> Result<()> {
+    let api_key = Redacted::new(match key {
+        Some(key) => key,
+        None => prompt("Unsplash access key", "")?,
+    });
+
+    Client::new(&api_key)?.verify_access().await?;
+    Client::store_api_key(&api_key)?;
+
+    println!("Stored the access key in the credential store");
+
+    Ok(())
+}
+
+fn prompt(question: &str, default: &str) -> io::Result<String> {
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let line = line.trim();
+    Ok(if line.is_empty() { default } else { line }.to_owned())
+}
+
+pub async fn init<P: AsRef<Path>>(config_folder: P) -> Result<()> {
+    let config_folder = config_folder.as_ref();
+    fs::create_dir_all(config_folder)?;
+
+    if config::path(config_folder).exists()
+        && !prompt("A configuration already exists, overwrite it? [y/N]", "")?
+            .eq_ignore_ascii_case("y")
+    {
+        return Ok(());
+    }
+
+    let api_key = loop {
+        let api_key = Redacted::new(prompt("Unsplash access key", "")?);
+        if api_key.expose().is_empty() {
+            continue;
+        }
+
+        match Client::new(&api_key)?.verify_access().await {
+            Ok(()) => break api_key,
+            Err(err) => println!("The access key could not be verified: {}", err),
+        }
+    };
+
+    let mut config = Config::default();
+
+    config.folder = prompt(
+        "Folder to download photos to",
+        &config.folder.to_string_lossy(),
+    )?
+    .into();
+
+    config.fetch.count = loop {
+        match prompt("Photos per run", &config.fetch.count.to_string())?.parse() {
+            Ok(count) if count > 0 => break count,
+            _ => println!("Please enter a number greater than 0"),
+        }
+    };
+
+    config.query = loop {
+        let query = prompt(
+            "Query, e.g. \"topic:nature orientation:portrait\" (leave empty for random photos)",
+            "",
+        )?;
+        if query.is_empty() {
+            break None;
+        }
+
+        match config.fetch.clone().apply_query(&query) {
+            Ok(()) => break Some(query),
+            Err(err) => println!("{}", err),
+        }
+    };
+
+    config.validate()?;
+
+    fs::write(
+        config_folder.join(config::ENV_FILE),
+        format!("UNSPLASH_API_KEY={}\n", api_key.expose()),
+    )?;
+
+    let config_path = config_folder.join(config::CONFIG_FILE);
+    fs::write(&config_path, config::to_toml(&config)?)?;
+
+    println!("Wrote {}", config_path.display());
+
+    Ok(())
+}
@@ -0,0 +1,374 @@
+use std::{fs, path::Path, slice, time::Duration};
+
+use chrono::{Local, TimeDelta};
+use reqwest::StatusCode;
+
+use super::{
+    client, current_photo, explore::cached_topics, photo_files, photo_json, read_last_run,
+    unseen_photos, Error, Result, BANDWIDTH_FILE, LAST_SUCCESS_FILE,
+};
+use crate::{
+    bandwidth::Bandwidth,
+    config::{AutoCount, Config},
+    experimental::Feature,
+    histogram::{Histogram, PoolHistograms},
+    history::History,
+    scheduler,
+    stats::Stats,
+    Mode, Query, TrackQueue,
+};
+
+pub async fn status<P: AsRef<Path>>(
+    config: &Config,
+    history: &History,
+    stats: &Stats,
+    config_folder: P,
+) -> Result<()> {
+    let quota = match client(config) {
+        Ok(client) => client
+            .verify_access()
+            .await
+            .map(|_| client.rate_limit())
+            .map_err(Error::from),
+        Err(err) => Err(err),
+    };
+
+    let files = photo_files(&config.folder)?;
+    let size: u64 = files
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    let unseen = unseen_photos(&config.folder, history, stats)?;
+    let refill_hours = stats
+        .changes_per_day(TimeDelta::days(AutoCount::WINDOW_DAYS))
+        .map(|changes_per_day| unseen as f64 / changes_per_day * 24.0);
+    let current = current_photo(config)?;
+    let last_run = read_last_run(config_folder.as_ref().join(LAST_SUCCESS_FILE))?;
+    let next_run = scheduler::next_run().ok().flatten();
+    let bandwidth = *Bandwidth::open(config_folder.as_ref().join(BANDWIDTH_FILE))?.estimate();
+    let affordable = config
+        .limits
+        .max_duration_minutes
+        .and_then(|minutes| bandwidth.affordable(Duration::from_secs(minutes * 60)));
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "quota": quota.ok().flatten().map(|rate_limit| serde_json::json!({
+                    "remaining": rate_limit.remaining,
+                    "limit": rate_limit.limit,
+                })),
+                "photos": files.len(),
+                "size": size,
+                "max_size": config.max_size,
+                "unseen": unseen,
+                "refill_hours": refill_hours,
+                "wallpaper": current.map(|path| photo_json(history, &path)),
+                "last_run": last_run,
+                "next_run": next_run,
+                "bandwidth": bandwidth.is_known().then(|| serde_json::json!({
+                    "bytes_per_second": bandwidth.bytes_per_second,
+                    "bytes_per_photo": bandwidth.bytes_per_photo,
+                    "affordable_photos": affordable,
+                })),
+            })
+        );
+
+        return Ok(());
+    }
+
+    match quota {
+        Ok(Some(rate_limit)) => println!(
+            "Unsplash quota: {} of {} requests left this hour",
+            rate_limit.remaining, rate_limit.limit
+        ),
+        Ok(None) => println!("Unsplash quota: unknown"),
+        Err(err) => println!("Unsplash quota: unknown ({})", err),
+    }
+
+    println!(
+        "Photos: {} ({:.1} MB of {:.1} MB)",
+        files.len(),
+        size as f64 / 1_000_000.0,
+        config.max_size as f64 / 1_000_000.0
+    );
+
+    println!("Unseen photos: {}", unseen);
+
+    match refill_hours {
+        Some(hours) if hours < 48.0 => println!("Refill needed in about {:.0} hours", hours),
+        Some(hours) => println!("Refill needed in about {:.0} days", hours / 24.0),
+        None => println!("Refill needed: unknown until the wallpaper has changed a few times"),
+    }
+
+    match current {
+        Some(path) => {
+            let id = history.photo_id(&path);
+            match history.get(&id) {
+                Some(entry) => println!(
+                    "Current wallpaper: {} by {} (@{}){}",
+                    id,
+                    entry.photographer.name(),
+                    entry.photographer.username(),
+                    entry
+                        .photo_url()
+                        .map_or(String::new(), |url| format!(", {}", url))
+                ),
+                None => println!("Current wallpaper: {}", path.display()),
+            }
+        }
+
+        None => println!("Current wallpaper: not set by Backdrop"),
+    }
+
+    match last_run {
+        Some(last_run) => println!(
+            "Last successful run: {}",
+            last_run.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        ),
+        None => println!("Last successful run: never"),
+    }
+
+    match next_run {
+        Some(next_run) => println!("Next scheduled run: {}", next_run),
+        None => println!("Next scheduled run: not scheduled"),
+    }
+
+    if bandwidth.is_known() {
+        println!(
+            "Bandwidth: about {:.1} MB/s, {:.1} MB per photo",
+            bandwidth.bytes_per_second / 1_000_000.0,
+            bandwidth.bytes_per_photo / 1_000_000.0
+        );
+        if let (Some(affordable), Some(minutes)) = (affordable, config.limits.max_duration_minutes)
+        {
+            println!(
+                "The next run can afford about {} photos within its {} minute limit",
+                affordable, minutes
+            );
+        }
+    } else {
+        println!("Bandwidth: unknown until the first download");
+    }
+
+    Ok(())
+}
+
+pub fn print_stats(
+    config: &Config,
+    stats: &Stats,
+    tracks: &TrackQueue,
+    summary: bool,
+    pool: Option<&PoolHistograms>,
+    sparklines: bool,
+) {
+    const SUMMARY_AUTHORS: usize = 5;
+
+    let counters = stats.counters();
+    let authors = stats.favorite_authors();
+    let shown = if summary {
+        SUMMARY_AUTHORS
+    } else {
+        authors.len()
+    };
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "photos_fetched": counters.photos_fetched,
+                "bytes_downloaded": counters.bytes_downloaded,
+                "wallpapers_set": counters.wallpapers_set,
+                "pending_pings": tracks.len(),
+                "favorite_authors": authors
+                    .into_iter()
+                    .take(shown)
+                    .map(|(username, count)| serde_json::json!({
+                        "username": username,
+                        "count": count,
+                    }))
+                    .collect::<Vec<_>>(),
+                "pool": pool,
+            })
+        );
+
+        return;
+    }
+
+    println!("Photos fetched: {}", counters.photos_fetched);
+    println!(
+        "Data downloaded: {:.1} MB",
+        counters.bytes_downloaded as f64 / 1_000_000.0
+    );
+    println!("Wallpapers set: {}", counters.wallpapers_set);
+    if !tracks.is_empty() {
+        println!("Download pings waiting to be sent: {}", tracks.len());
+    }
+
+    if !authors.is_empty() {
+        println!("Favorite photographers:");
+        for (username, count) in authors.into_iter().take(shown) {
+            println!("  @{}  {}", username, count);
+        }
+    }
+
+    if let Some(pool) = pool {
+        for (title, histogram) in [
+            ("Resolutions", &pool.resolutions),
+            ("Aspect ratios", &pool.aspect_ratios),
+            ("Dominant hues", &pool.hues),
+            ("Photographers in the pool", &pool.photographers),
+        ] {
+            print_histogram(title, histogram, sparklines);
+        }
+    }
+}
+
+fn print_histogram(title: &str, histogram: &Histogram, sparkline: bool) {
+    if histogram.is_empty() {
+        println!("{}: unknown", title);
+
+        return;
+    }
+
+    if sparkline {
+        let legend: Vec<_> = histogram
+            .buckets
+            .iter()
+            .map(|(label, count)| format!("{} {}", label, count))
+            .collect();
+        println!(
+            "{}: {}  ({})",
+            title,
+            histogram.sparkline(),
+            legend.join(", ")
+        );
+
+        return;
+    }
+
+    let total = histogram.total();
+    let width = histogram
+        .buckets
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    println!("{}:", title);
+    for (label, count) in histogram.buckets.iter().filter(|(_, count)| *count > 0) {
+        println!(
+            "  {:width$}  {:>4}  {:>3.0}%",
+            label,
+            count,
+            *count as f64 * 100.0 / total as f64,
+        );
+    }
+}
+
+pub fn print_features(config: &Config) {
+    if config.json {
+        let features: Vec<_> = Feature::ALL
+            .iter()
+            .map(|&feature| {
+                serde_json::json!({
+                    "name": feature,
+                    "description": feature.description(),
+                    "available": feature.is_available(),
+                    "enabled": config.experimental.is_enabled(feature),
+                    "configured": config.experimental.is_configured(feature),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(features));
+
+        return;
+    }
+
+    for &feature in Feature::ALL {
+        let state = if !feature.is_available() {
+            "not in this build"
+        } else if config.experimental.is_enabled(feature) {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        let source = if !feature.is_available() || config.experimental.is_configured(feature) {
+            ""
+        } else {
+            " (default)"
+        };
+
+        println!(
+            "{:<16} {:<18} {}",
+            feature.to_string(),
+            format!("{}{}", state, source),
+            feature.description()
+        );
+    }
+}
+
+pub async fn doctor<P: AsRef<Path>>(config: &Config, topics_path: P) -> Result<()> {
+    let client = client(config)?;
+    let topics = cached_topics(&client, topics_path).await?;
+
+    let fetches = [("fetch".to_owned(), &config.fetch)]
+        .into_iter()
+        .chain(
+            config
+                .queries
+                .iter()
+                .map(|(name, fetch)| (format!("queries.{}", name), fetch)),
+        )
+        .chain(
+            config
+                .docked
+                .iter()
+                .filter_map(|docked| docked.fetch.as_ref())
+                .map(|fetch| ("docked.fetch".to_owned(), fetch)),
+        );
+
+    let mut problems = 0;
+    for (context, fetch) in fetches {
+        if fetch.color.is_some() && matches!(fetch.mode, Mode::Random) {
+            println!(
+                "{}: color is ignored for random photos, use search mode",
+                context
+            );
+            problems += 1;
+        }
+
+        for weighted in &fetch.queries {
+            let ids_or_slugs = match &weighted.query {
+                Query::Topic(id_or_slug) => slice::from_ref(id_or_slug),
+                Query::Topics(ids_or_slugs) => ids_or_slugs.as_slice(),
+                Query::Text(_) => continue,
+            };
+
+            for id_or_slug in ids_or_slugs {
+                if topics.iter().any(|topic| topic.matches(id_or_slug)) {
+                    continue;
+                }
+
+                match client.find_topic(id_or_slug).await {
+                    Ok(_) => {}
+
+                    Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                        println!("{}: unknown topic `{}`", context, id_or_slug);
+                        problems += 1;
+                    }
+
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+
+    if problems == 0 {
+        println!("No problems found");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use reqwest::StatusCode;
+
+use super::{changelog, client, hidden_photo_ids, Error, Result};
+use crate::{
+    bundle,
+    changelog::Change,
+    config::Config,
+    history::{self, History},
+    manifest::{self, Manifest},
+    spotlight, Workflow,
+};
+
+pub async fn refetch<P: AsRef<Path>>(config: &Config, manifest_path: P) -> Result<()> {
+    let manifest = Manifest::open(manifest_path)?;
+    let client = client(config)?;
+
+    fs::create_dir_all(&config.folder)?;
+    for entry in &manifest.entries {
+        let data = client
+            .download_photo(&entry.photo, &manifest.download)
+            .await?;
+
+        if manifest::sha256_hex(&data) != entry.sha256 {
+            println!("{}: content differs from the manifest", entry.photo.id());
+        }
+
+        fs::write(config.folder.join(&entry.file_name), data)?;
+    }
+
+    Ok(())
+}
+
+pub async fn create_bundle<P: AsRef<Path>, Q: AsRef<Path>>(
+    config: &Config,
+    history: &History,
+    staging_folder: P,
+    output: Q,
+) -> Result<()> {
+    let staging_folder = staging_folder.as_ref();
+    let client = client(config)?;
+
+    let hidden = hidden_photo_ids(history);
+    let blocklist = config.blocklist.clone();
+
+    let saved = Workflow::new(client, config.fetch.clone(), config.download.clone())
+        .with_max_concurrent_downloads(config.max_concurrent_downloads)
+        .with_filter(move |photo| !hidden.contains(photo.id()) && !blocklist.blocks(photo))
+        .run(staging_folder, history)
+        .await
+        .and_then(|(report, _)| report.into_result());
+
+    let result = saved.map_err(Error::from).and_then(|saved| {
+        let photos: Vec<_> = saved
+            .into_iter()
+            .map(|saved| (saved.photo, saved.path))
+            .collect();
+
+        bundle::create(output, &photos)?;
+        println!("Bundled {} photos", photos.len());
+
+        Ok(())
+    });
+
+    if staging_folder.exists() {
+        fs::remove_dir_all(staging_folder)?;
+    }
+
+    result
+}
+
+pub fn apply_bundle<P: AsRef<Path>>(
+    config: &Config,
+    history: &mut History,
+    bundle_path: P,
+) -> Result<()> {
+    let photos = bundle::apply(bundle_path, &config.folder)?;
+
+    for (photo, path) in &photos {
+        history.record(history::Entry::new(photo, path).with_expires_at(config.photo_expiry()));
+    }
+    history.save()?;
+    changelog().append_all(photos.iter().map(|(photo, path)| Change::Added {
+        id: photo.id().to_owned(),
+        path: path.clone(),
+    }))?;
+
+    println!("Imported {} photos", photos.len());
+
+    Ok(())
+}
+
+fn legacy_photos(config: &Config, history: &History) -> io::Result<Vec<(String, PathBuf)>> {
+    if !config.folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut photos = Vec::new();
+    for file in config.folder.read_dir()? {
+        let path = file?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "png") {
+            continue;
+        }
+
+        let id = history.photo_id(&path);
+        let is_id = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if is_id && !history.contains(&id) {
+            photos.push((id, path));
+        }
+    }
+    photos.sort();
+
+    Ok(photos)
+}
+
+pub async fn migrate(config: &Config, history: &mut History) -> Result<()> {
+    let legacy = legacy_photos(config, history)?;
+    if legacy.is_empty() {
+        println!("No photos from older versions were found");
+
+        return Ok(());
+    }
+
+    let client = client(config)?;
+
+    let mut migrated = 0;
+    let mut taken = HashSet::new();
+    for (id, path) in legacy {
+        let photo = match client.fetch_photo(&id).await {
+            Ok(photo) => photo,
+
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                println!("{}: no longer on Unsplash, left in place", path.display());
+                continue;
+            }
+
+            Err(e) => {
+                history.save()?;
+
+                return Err(e.into());
+            }
+        };
+
+        let target = config
+            .download
+            .path_with_extension(&config.folder, &photo, "png", &mut taken);
+        if target != path {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&path, &target)?;
+        }
+
+        let mut entry = history::Entry::new(&photo, &target).with_expires_at(config.photo_expiry());
+        entry.shown = true;
+        history.record(entry);
+        migrated += 1;
+    }
+    history.save()?;
+
+    println!("Migrated {} photos", migrated);
+
+    Ok(())
+}
+
+pub fn import_spotlight(
+    config: &Config,
+    history: &mut History,
+    from: Option<PathBuf>,
+) -> Result<()> {
+    let Some(source) = from
+        .or_else(spotlight::assets_folder)
+        .filter(|path| path.is_dir())
+    else {
+        println!("No Windows Spotlight images were found");
+
+        return Ok(());
+    };
+
+    let entries = spotlight::import(source, &config.folder, history)?;
+    let count = entries.len();
+    changelog().append_all(entries.iter().map(|entry| Change::Added {
+        id: entry.id.clone(),
+        path: entry.path.clone(),
+    }))?;
+    for entry in entries {
+        history.record(entry);
+    }
+    history.save()?;
+
+    println!("Imported {} Windows Spotlight images", count);
+
+    Ok(())
+}
+
+#[cfg(feature = "frame")]
+pub async fn push_to_frame(
+    config: &Config,
+    history: &History,
+) -> Result<Option<crate::frame::SyncReport>> {
+    use reqwest::Url;
+
+    use super::FRAME_STATE_FILE;
+    use crate::{cleanup, config, frame::Frame};
+
+    let Some(settings) = &config.frame else {
+        return Ok(None);
+    };
+
+    let mut frame = Frame::new(Url::parse(&settings.url).map_err(io::Error::other)?);
+    if let Some(token) = &settings.token {
+        frame = frame.with_token(token.clone());
+    }
+    if let Some(max_size) = settings.max_size {
+        frame = frame.with_max_size(max_size);
+    }
+
+    let candidates = cleanup::scan(&config.folder, history)?;
+    let report = frame
+        .sync(
+            &config.folder,
+            &candidates,
+            config::folder().join(FRAME_STATE_FILE),
+        )
+        .await?;
+
+    tracing::info!(
+        uploaded = report.uploaded.len(),
+        removed = report.removed.len(),
+        unchanged = report.unchanged,
+        over_budget = report.over_budget.len(),
+        "pushed photos to the frame"
+    );
+
+    Ok(Some(report))
+}
+
+#[cfg(feature = "frame")]
+pub fn print_push(config: &Config, report: Option<crate::frame::SyncReport>) {
+    let Some(report) = report else {
+        config.print("No photo frame is configured");
+
+        return;
+    };
+
+    if config.json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "uploaded": report.uploaded,
+                "removed": report.removed,
+                "unchanged": report.unchanged,
+                "over_budget": report.over_budget,
+            })
+        );
+    } else {
+        config.print(format_args!(
+            "Pushed {} photos to the frame, removed {} and left {} unchanged",
+            report.uploaded.len(),
+            report.removed.len(),
+            report.unchanged
+        ));
+        if !report.over_budget.is_empty() {
+            config.print(format_args!(
+                "{} photos did not fit in the frame's size budget",
+                report.over_budget.len()
+            ));
+        }
+    }
+}
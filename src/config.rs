@@ -0,0 +1,1748 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::{self, Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, Utc, Weekday};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::{
+    accessibility,
+    blocklist::Blocklist,
+    calendar::{self, QuietHours},
+    cleanup::RetentionPolicy,
+    cloud::{self, SyncClient},
+    commands::{random, unseen_photos},
+    cron::Cron,
+    daily, display,
+    experimental::{Experimental, Feature},
+    history::History,
+    redact::Redacted,
+    selection::Selection,
+    stats::Stats,
+    theme, unsplash, wallpaper, Download, Fetch, Format, Limits, Mode, Network, Resolution,
+    RetryPolicy,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error(
+        "A default configuration file has been created, please review it or run `backdrop init` before proceeding"
+    )]
+    RequiresConfigure,
+
+    #[error("{0}")]
+    Query(#[from] unsplash::ParseQueryError),
+
+    #[error("{0}")]
+    TomlDe(#[from] toml_edit::de::Error),
+
+    #[error("{0}")]
+    TomlSer(#[from] toml_edit::ser::Error),
+
+    #[error("{0}")]
+    TomlEdit(#[from] toml_edit::TomlError),
+
+    #[error("No query named `{0}` is configured")]
+    UnknownQueryName(String),
+
+    #[error("Failed to parse {}: {message}", path.display())]
+    ParseConfig { path: PathBuf, message: String },
+
+    #[error("The configuration is invalid:\n{}", .0.join("\n"))]
+    InvalidConfig(Vec<String>),
+
+    #[error("No profile named `{0}` is configured")]
+    UnknownProfile(String),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub const CONFIG_FILE: &str = "config.toml";
+pub const ENV_FILE: &str = ".env";
+const ENV_TEMPLATE: &str = include_str!("../.env.example");
+pub const LEGACY_CONFIG_FILE: &str = "config.json";
+
+const CONFIG_COMMENTS: &[(&str, &str)] = &[
+    ("folder", "Folder the downloaded photos are saved to"),
+    (
+        "max_size",
+        "Maximum total size of the folder in bytes, oldest photos are deleted first",
+    ),
+    (
+        "delete_unshown",
+        "Allow cleanup to delete photos that have not been set as the wallpaper yet",
+    ),
+    (
+        "photo_expiry_days",
+        "Delete photos this many days after they were downloaded, e.g. when the provider's terms require it, even when pinned or within the size limits",
+    ),
+    (
+        "quotas",
+        "Separate max_size, max_count, max_age_days and delete_unshown limits for subfolders, e.g. [quotas.mobile]",
+    ),
+    (
+        "shared_folder",
+        "Set when several users share the folder, their runs then take turns while history and wallpaper stay per user",
+    ),
+    (
+        "shared_cache",
+        "Read-only folder of <id>.<format> photos, e.g. on a network share, copied from before downloading",
+    ),
+    (
+        "accent",
+        "Write the dominant color of the wallpaper to the accent file and a blurred preview to placeholder.bmp in the configuration folder",
+    ),
+    (
+        "provider",
+        "Where photos come from: unsplash, or bing and apod which need no API key",
+    ),
+    (
+        "fetch",
+        "Which photos to fetch from Unsplash and how many per run",
+    ),
+    (
+        "queries",
+        "Named fetch settings, selected with --query-name or weekday_queries",
+    ),
+    (
+        "weekday_queries",
+        "Named query to use on each weekday, e.g. mon = \"work\"",
+    ),
+    (
+        "profiles",
+        "Named folder, fetch and download settings, selected with --profile",
+    ),
+    (
+        "download",
+        "Image format, resolution and filename template of the downloaded photos, e.g. \"{date}-{user}-{id}.{ext}\", subfolders saves each query into its own folder and slow_fallback switches to a lower resolution (regular by default) once downloads exceed time_budget_secs and min_bytes rejects smaller downloads as broken",
+    ),
+    (
+        "review",
+        "Ask for feedback on the current wallpaper before each run",
+    ),
+    (
+        "require_approval",
+        "Download into a pending folder and wait for `backdrop approve`",
+    ),
+    (
+        "motion",
+        "Reduced motion preference, `freeze_rotation` stops rotating when it is on",
+    ),
+    (
+        "wallpaper",
+        "Wallpaper backend on Linux: auto, feh or swaybg",
+    ),
+    ("apply_wallpaper", "Set the wallpaper after downloading"),
+    (
+        "manual_change",
+        "When the wallpaper was changed outside Backdrop, reassert replaces it on the next run and pause leaves it until Backdrop sets one again",
+    ),
+    (
+        "apply_to",
+        "Where the wallpaper is set: desktop and, on Windows, lockscreen",
+    ),
+    ("retry", "Retries for failed Unsplash requests"),
+    (
+        "network",
+        "Timeouts in seconds, an optional proxy URL (HTTPS_PROXY is used otherwise), extra PEM root certificates (pin_root_certificates trusts only those for Unsplash), ip_version (any, v4, v6, prefer_v4 or prefer_v6), dns_overrides of host names to addresses and a dns_over_https JSON endpoint",
+    ),
+    (
+        "limits",
+        "Caps on requests, downloaded bytes and minutes per run, unlimited when unset",
+    ),
+    (
+        "request_budget",
+        "Maximum Unsplash API requests per hour across all runs, e.g. 50 for a demo access key, runs are skipped once it is spent",
+    ),
+    (
+        "run_log",
+        "Append a summary of every run to runs.jsonl in the config folder",
+    ),
+    (
+        "selection",
+        "How rotation picks the next photo: random, random_no_repeat, least_shown, newest or rating_weighted",
+    ),
+    (
+        "learning",
+        "Learn from blocked, unpinned and skipped photos and skip similar ones scoring below min_score, set enabled = false to opt out",
+    ),
+    (
+        "auto_count",
+        "Adjust the number of photos per run to keep buffer_days of unseen photos, between min and max",
+    ),
+    (
+        "refresh_metadata",
+        "Refresh the likes and availability of up to per_run downloaded photos older than every_days on each run",
+    ),
+    (
+        "max_concurrent_downloads",
+        "Number of photos downloaded at the same time",
+    ),
+    (
+        "blocklist",
+        "Photo ids, usernames and keywords that are never downloaded",
+    ),
+    (
+        "attribution_sidecars",
+        "Write a JSON file with the photographer's details next to each photo",
+    ),
+    (
+        "schedule",
+        "Minimum minutes between runs, a random delay in seconds before each run, local times to run at (at = [\"07:00\"] or cron = \"0 */4 * * *\") and quiet_hours without runs, all in local time. `backdrop serve` also runs on_login and then every_minutes",
+    ),
+    ("per_monitor", "Download a separate photo for each monitor"),
+    (
+        "log_level",
+        "Level of the log file in the logs folder: off, error, warn, info, debug or trace",
+    ),
+    (
+        "offline_fallback",
+        "Rotate through downloaded photos when Unsplash is unreachable",
+    ),
+    (
+        "fallbacks",
+        "Tried in order when no new photos are found, e.g. [{ query = \"nature\" }, \"editorial\", \"local_pool\"]",
+    ),
+    (
+        "embed_metadata",
+        "Write the attribution into the photo's EXIF metadata",
+    ),
+    (
+        "notification_template",
+        "Text of the new wallpaper notification, {author}, {username}, {description}, {id}, {url} and {profile} are replaced with the photo's details and empty lines are left out",
+    ),
+    (
+        "processing",
+        "Crop, blur, darken, grayscale and attribution overlay applied to each photo",
+    ),
+    (
+        "s3",
+        "Upload downloaded photos to an S3-compatible bucket, the secret key is read from the credential store or S3_SECRET_ACCESS_KEY, retention overrides the limits for the bucket",
+    ),
+    (
+        "webdav",
+        "Upload downloaded photos to a WebDAV folder such as Nextcloud, the password is read from the credential store or WEBDAV_PASSWORD, retention overrides the limits for the folder",
+    ),
+    (
+        "mirror",
+        "Copy downloaded photos into another folder, e.g. one synced to a phone, relative to the OneDrive or Dropbox folder when sync_client is set, retention overrides the limits for the folder",
+    ),
+    (
+        "experimental",
+        "Turn larger subsystems on or off, e.g. web_ui = false, `backdrop features list` shows what this build includes",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotionPreference {
+    #[default]
+    System,
+    Reduced,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    #[default]
+    Unsplash,
+    Bing,
+    Apod,
+}
+
+impl Provider {
+    pub fn daily_source(self) -> Option<daily::Source> {
+        match self {
+            Provider::Unsplash => None,
+            Provider::Bing => Some(daily::Source::Bing),
+            Provider::Apod => Some(daily::Source::Apod),
+        }
+    }
+}
+
+#[cfg(feature = "span")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Span {
+    Stitch,
+    Crop,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManualChange {
+    #[default]
+    Reassert,
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback {
+    Query(String),
+    Editorial,
+    LocalPool,
+}
+
+impl Fallback {
+    pub fn fetch(
+        &self,
+        fetch: &Fetch,
+    ) -> std::result::Result<Option<Fetch>, unsplash::ParseQueryError> {
+        let mut fetch = fetch.clone();
+        fetch.queries.clear();
+
+        match self {
+            Fallback::Query(query) => fetch.apply_query(query)?,
+            Fallback::Editorial => fetch.mode = Mode::Search(Default::default()),
+            Fallback::LocalPool => return Ok(None),
+        }
+
+        Ok(Some(fetch))
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Fallback::Query(query) => format!("the query \"{}\"", query),
+            Fallback::Editorial => "the editorial feed".to_owned(),
+            Fallback::LocalPool => "the downloaded photos".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "component", rename_all = "snake_case")]
+pub enum Component {
+    SkipExisting,
+    SkipHidden,
+    Blocklist,
+    MinLikes {
+        likes: u32,
+    },
+    RequireTags {
+        tags: Vec<String>,
+    },
+    #[cfg(feature = "processing")]
+    Process,
+    #[cfg(feature = "metadata")]
+    EmbedMetadata,
+    Sidecar,
+    Manifest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ComponentKind {
+    Filter,
+    #[cfg(any(feature = "metadata", feature = "processing"))]
+    Processor,
+    Sink,
+}
+
+impl Component {
+    pub fn kind(&self) -> ComponentKind {
+        match self {
+            Component::SkipExisting
+            | Component::SkipHidden
+            | Component::Blocklist
+            | Component::MinLikes { .. }
+            | Component::RequireTags { .. } => ComponentKind::Filter,
+            #[cfg(feature = "processing")]
+            Component::Process => ComponentKind::Processor,
+            #[cfg(feature = "metadata")]
+            Component::EmbedMetadata => ComponentKind::Processor,
+            Component::Sidecar | Component::Manifest => ComponentKind::Sink,
+        }
+    }
+
+    pub fn filter_description(&self) -> Option<String> {
+        match self {
+            Component::SkipExisting => Some("not downloaded before".to_owned()),
+            Component::SkipHidden => Some("not hidden".to_owned()),
+            Component::Blocklist => Some("not blocklisted".to_owned()),
+            Component::MinLikes { likes } => Some(format!("at least {} likes", likes)),
+            Component::RequireTags { tags } => Some(format!("tagged {}", tags.join(", "))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Motion {
+    #[serde(default)]
+    pub preference: MotionPreference,
+    #[serde(default)]
+    pub freeze_rotation: bool,
+}
+
+impl Motion {
+    pub fn is_reduced(&self) -> bool {
+        match self.preference {
+            MotionPreference::System => accessibility::prefers_reduced_motion(),
+            MotionPreference::Reduced => true,
+            MotionPreference::Full => false,
+        }
+    }
+
+    pub fn is_rotation_frozen(&self) -> bool {
+        self.freeze_rotation && self.is_reduced()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Docked {
+    #[serde(default = "Docked::default_min_monitors")]
+    pub min_monitors: u32,
+    #[serde(default)]
+    pub fetch: Option<Fetch>,
+    #[serde(default)]
+    pub download: Option<Download>,
+}
+
+impl Docked {
+    pub fn default_min_monitors() -> u32 {
+        2
+    }
+
+    pub fn is_active(&self) -> bool {
+        display::monitor_count().is_some_and(|count| count >= self.min_monitors)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub folder: Option<PathBuf>,
+    #[serde(default)]
+    pub fetch: Option<Fetch>,
+    #[serde(default)]
+    pub download: Option<Download>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub min_interval_minutes: Option<u64>,
+    #[serde(default)]
+    pub jitter: Option<u64>,
+    #[serde(default)]
+    pub at: Vec<NaiveTime>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    #[serde(default)]
+    pub cron: Option<Cron>,
+    #[serde(default)]
+    pub on_login: Option<bool>,
+    #[serde(default)]
+    pub every_minutes: Option<u64>,
+}
+
+impl Schedule {
+    pub fn is_due(&self, last_run: Option<DateTime<Utc>>) -> bool {
+        if let Some(last_run) = last_run
+            && (!self.at.is_empty() || self.cron.is_some())
+            && self
+                .previous_slot(&Local::now())
+                .is_none_or(|previous| last_run >= previous)
+        {
+            return false;
+        }
+
+        !self.is_too_recent(last_run)
+    }
+
+    pub fn is_too_recent(&self, last_run: Option<DateTime<Utc>>) -> bool {
+        match (self.min_interval_minutes, last_run) {
+            (Some(minutes), Some(last_run)) => {
+                Utc::now() - last_run < TimeDelta::minutes(minutes as i64)
+            }
+
+            _ => false,
+        }
+    }
+
+    pub fn every(&self, web: &Web) -> Duration {
+        Duration::from_secs(self.every_minutes.unwrap_or(web.interval_minutes).max(1) * 60)
+    }
+
+    pub fn previous_slot(&self, now: &DateTime<Local>) -> Option<DateTime<Local>> {
+        let at = calendar::previous(now, &self.at);
+        let cron = self.cron.as_ref().and_then(|cron| cron.previous(now));
+
+        at.max(cron)
+    }
+
+    pub fn next_slot(&self, now: &DateTime<Local>) -> Option<DateTime<Local>> {
+        let at = calendar::next(now, &self.at);
+        let cron = self.cron.as_ref().and_then(|cron| cron.next(now));
+
+        match (at, cron) {
+            (Some(at), Some(cron)) => Some(at.min(cron)),
+            (at, cron) => at.or(cron),
+        }
+    }
+
+    pub fn is_quiet(&self, at: &DateTime<Local>) -> bool {
+        self.quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.contains(at))
+    }
+
+    pub fn next_cycle(&self, now: DateTime<Local>, every: Duration) -> DateTime<Local> {
+        let every = TimeDelta::from_std(every).unwrap_or(TimeDelta::hours(1));
+        let next = self.next_slot(&now).unwrap_or(now + every);
+
+        match self.quiet_hours {
+            Some(quiet_hours) if quiet_hours.contains(&next) => {
+                quiet_hours.end_after(&next).unwrap_or(next)
+            }
+            _ => next,
+        }
+    }
+
+    pub fn jitter_delay(&self) -> Duration {
+        let Some(jitter) = self.jitter.filter(|&jitter| jitter > 0) else {
+            return Duration::ZERO;
+        };
+
+        Duration::from_secs(random() % (jitter + 1))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshMetadata {
+    #[serde(default = "RefreshMetadata::default_every_days")]
+    pub every_days: u32,
+    #[serde(default = "RefreshMetadata::default_per_run")]
+    pub per_run: usize,
+}
+
+impl RefreshMetadata {
+    pub fn default_every_days() -> u32 {
+        30
+    }
+
+    pub fn default_per_run() -> usize {
+        10
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Learning {
+    #[serde(default = "Learning::default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "Learning::default_min_score")]
+    pub min_score: f64,
+}
+
+impl Learning {
+    pub fn default_enabled() -> bool {
+        true
+    }
+
+    pub fn default_min_score() -> f64 {
+        -1.0
+    }
+}
+
+impl Default for Learning {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            min_score: Self::default_min_score(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoCount {
+    #[serde(default = "AutoCount::default_min")]
+    pub min: u32,
+    #[serde(default = "AutoCount::default_max")]
+    pub max: u32,
+    #[serde(default = "AutoCount::default_buffer_days")]
+    pub buffer_days: f64,
+}
+
+impl AutoCount {
+    pub const WINDOW_DAYS: i64 = 14;
+
+    pub fn default_min() -> u32 {
+        1
+    }
+
+    pub fn default_max() -> u32 {
+        30
+    }
+
+    pub fn default_buffer_days() -> f64 {
+        1.0
+    }
+
+    pub fn count(&self, changes_per_day: Option<f64>, unseen: usize, configured: u32) -> u32 {
+        let Some(changes_per_day) = changes_per_day else {
+            return configured.clamp(self.min, self.max);
+        };
+
+        let target = (changes_per_day * self.buffer_days).ceil() as usize;
+        let needed = target.saturating_sub(unseen);
+
+        (needed.min(u32::MAX as usize) as u32).clamp(self.min, self.max)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequence {
+    pub collection: String,
+}
+
+#[cfg(feature = "parallax")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parallax {
+    pub model: PathBuf,
+    pub folder: PathBuf,
+    #[serde(default = "Parallax::default_layers")]
+    pub layers: u32,
+}
+
+#[cfg(feature = "parallax")]
+impl Parallax {
+    pub fn default_layers() -> u32 {
+        3
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Web {
+    #[serde(default = "Web::default_address")]
+    pub address: std::net::SocketAddr,
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    #[cfg_attr(not(feature = "web"), allow(dead_code))]
+    pub token: Option<Redacted<String>>,
+    #[serde(default = "Web::default_interval_minutes")]
+    pub interval_minutes: u64,
+}
+
+impl Web {
+    pub fn default_address() -> std::net::SocketAddr {
+        ([127, 0, 0, 1], 8427).into()
+    }
+
+    pub fn default_interval_minutes() -> u64 {
+        60
+    }
+}
+
+impl Default for Web {
+    fn default() -> Self {
+        Self {
+            address: Self::default_address(),
+            token: None,
+            interval_minutes: Self::default_interval_minutes(),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3 {
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "S3::default_region")]
+    pub region: String,
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_id: String,
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    pub secret_access_key: Option<Redacted<String>>,
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+}
+
+#[cfg(feature = "s3")]
+impl S3 {
+    pub fn default_region() -> String {
+        "auto".to_owned()
+    }
+}
+
+#[cfg(feature = "webdav")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebDav {
+    pub url: String,
+    pub username: String,
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    pub password: Option<Redacted<String>>,
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+}
+
+#[cfg(feature = "frame")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotoFrame {
+    pub url: String,
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    pub token: Option<Redacted<String>>,
+    #[serde(default)]
+    pub max_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mirror {
+    #[serde(default)]
+    pub sync_client: Option<SyncClient>,
+    pub folder: PathBuf,
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+}
+
+impl Mirror {
+    pub fn folder(&self) -> Option<PathBuf> {
+        match self.sync_client {
+            Some(client) => Some(client.folder()?.join(&self.folder)),
+            None => Some(self.folder.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub folder: PathBuf,
+    pub max_size: u64,
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    #[serde(default)]
+    pub delete_unshown: bool,
+    #[serde(default)]
+    pub photo_expiry_days: Option<u64>,
+    #[serde(default)]
+    pub quotas: BTreeMap<PathBuf, RetentionPolicy>,
+    #[serde(default)]
+    pub shared_folder: bool,
+    #[serde(default)]
+    pub shared_cache: Option<PathBuf>,
+    #[serde(default)]
+    pub accent: bool,
+    #[serde(default)]
+    pub provider: Provider,
+    pub fetch: Fetch,
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub queries: BTreeMap<String, Fetch>,
+    #[serde(default)]
+    pub weekday_queries: HashMap<Weekday, String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    pub download: Download,
+    #[serde(default)]
+    pub review: bool,
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default)]
+    pub motion: Motion,
+    #[serde(default)]
+    pub wallpaper: wallpaper::Backend,
+    #[serde(default = "Config::default_apply_to")]
+    pub apply_to: Vec<wallpaper::Target>,
+    #[serde(default = "Config::default_apply_wallpaper")]
+    pub apply_wallpaper: bool,
+    #[serde(default)]
+    pub manual_change: ManualChange,
+    #[serde(default)]
+    pub docked: Option<Docked>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub request_budget: Option<u32>,
+    #[serde(default)]
+    pub run_log: bool,
+    #[serde(default)]
+    pub api_url: Option<String>,
+    #[serde(default)]
+    pub network: Network,
+    #[serde(default)]
+    pub sequence: Option<Sequence>,
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub learning: Learning,
+    #[serde(default)]
+    pub auto_count: Option<AutoCount>,
+    #[serde(default)]
+    pub refresh_metadata: Option<RefreshMetadata>,
+    #[serde(default = "Config::default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: usize,
+    #[serde(default)]
+    pub max_processing_workers: Option<usize>,
+    #[serde(default)]
+    pub keep_folder: Option<PathBuf>,
+    #[serde(default)]
+    pub blocklist: Blocklist,
+    #[serde(default)]
+    pub attribution_sidecars: bool,
+    #[serde(default)]
+    pub schedule: Schedule,
+    #[serde(default)]
+    pub per_monitor: bool,
+    #[serde(default)]
+    pub fetch_dark: Option<Fetch>,
+    #[serde(default = "Config::default_offline_fallback")]
+    pub offline_fallback: bool,
+    #[serde(default)]
+    pub fallbacks: Vec<Fallback>,
+    #[serde(default)]
+    pub pipeline: Option<Vec<Component>>,
+    #[serde(default)]
+    pub log_level: LogLevel,
+    #[cfg(feature = "metadata")]
+    #[serde(default)]
+    pub embed_metadata: bool,
+    #[cfg(feature = "notifications")]
+    #[serde(default)]
+    pub notifications: bool,
+    #[cfg(feature = "notifications")]
+    #[serde(default)]
+    pub notification_template: crate::notification::MessageTemplate,
+    #[cfg(feature = "sandbox")]
+    #[serde(default)]
+    pub sandbox: bool,
+    #[cfg(feature = "processing")]
+    #[serde(default)]
+    pub processing: crate::processing::Processing,
+    #[cfg(feature = "parallax")]
+    #[serde(default)]
+    pub parallax: Option<Parallax>,
+    #[cfg(feature = "span")]
+    #[serde(default)]
+    pub span: Option<Span>,
+    #[serde(default)]
+    pub web: Web,
+    #[cfg(feature = "s3")]
+    #[serde(default)]
+    pub s3: Option<S3>,
+    #[cfg(feature = "webdav")]
+    #[serde(default)]
+    pub webdav: Option<WebDav>,
+    #[serde(default)]
+    pub mirror: Option<Mirror>,
+    #[cfg(feature = "frame")]
+    #[serde(default)]
+    pub frame: Option<PhotoFrame>,
+    #[serde(default)]
+    pub experimental: Experimental,
+    #[serde(skip)]
+    pub json: bool,
+}
+
+impl Config {
+    pub fn print(&self, message: impl fmt::Display) {
+        if self.json {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    pub fn default_apply_wallpaper() -> bool {
+        !cfg!(windows)
+    }
+
+    pub fn default_apply_to() -> Vec<wallpaper::Target> {
+        vec![wallpaper::Target::Desktop]
+    }
+
+    pub fn default_max_concurrent_downloads() -> usize {
+        4
+    }
+
+    pub fn default_offline_fallback() -> bool {
+        true
+    }
+
+    pub fn pipeline(&self) -> Vec<Component> {
+        if let Some(pipeline) = &self.pipeline {
+            return pipeline.clone();
+        }
+
+        let mut pipeline = vec![
+            Component::SkipExisting,
+            Component::SkipHidden,
+            Component::Blocklist,
+        ];
+        #[cfg(feature = "processing")]
+        pipeline.push(Component::Process);
+        #[cfg(feature = "metadata")]
+        if self.embed_metadata {
+            pipeline.push(Component::EmbedMetadata);
+        }
+        if self.attribution_sidecars {
+            pipeline.push(Component::Sidecar);
+        }
+        pipeline.push(Component::Manifest);
+
+        pipeline
+    }
+
+    pub fn processing_workers(&self) -> usize {
+        self.max_processing_workers.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, |workers| workers.get())
+        })
+    }
+
+    pub fn photo_expiry(&self) -> Option<DateTime<Utc>> {
+        self.photo_expiry_days
+            .map(|days| Utc::now() + TimeDelta::days(days as i64))
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_size: Some(self.max_size),
+            max_count: self.max_count,
+            max_age_days: self.max_age_days,
+            delete_unshown: self.delete_unshown,
+        }
+    }
+
+    pub fn remote_retention_policy(&self) -> RetentionPolicy {
+        #[cfg(feature = "s3")]
+        if let Some(retention) = self.s3.as_ref().and_then(|s3| s3.retention.clone()) {
+            return retention;
+        }
+
+        #[cfg(feature = "webdav")]
+        if let Some(retention) = self
+            .webdav
+            .as_ref()
+            .and_then(|webdav| webdav.retention.clone())
+        {
+            return retention;
+        }
+
+        if let Some(retention) = self
+            .mirror
+            .as_ref()
+            .and_then(|mirror| mirror.retention.clone())
+        {
+            return retention;
+        }
+
+        self.retention_policy()
+    }
+
+    pub fn apply_named_query(&mut self, name: &str) -> Result<()> {
+        self.fetch = self
+            .queries
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownQueryName(name.to_owned()))?;
+
+        Ok(())
+    }
+
+    pub fn apply_queries(
+        &mut self,
+        weekday: Weekday,
+        query_name: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<()> {
+        if let Some(name) = self.weekday_queries.get(&weekday).cloned() {
+            self.apply_named_query(&name)?;
+        }
+        if let Some(query) = self.query.clone() {
+            self.fetch.apply_query(&query)?;
+        }
+
+        if let Some(name) = query_name {
+            self.apply_named_query(name)?;
+        }
+        if let Some(query) = query {
+            self.fetch.apply_query(query)?;
+        }
+
+        let mut problems = Vec::new();
+        validate_fetch("fetch", &self.fetch, &mut problems);
+        if !problems.is_empty() {
+            return Err(Error::InvalidConfig(problems));
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownProfile(name.to_owned()))?;
+
+        if let Some(folder) = profile.folder {
+            self.folder = folder;
+        }
+
+        if let Some(fetch) = profile.fetch {
+            self.fetch = fetch;
+        }
+
+        if let Some(download) = profile.download {
+            self.download = download;
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_theme(&mut self) {
+        if let Some(fetch) = self.fetch_dark.as_ref().filter(|_| theme::is_dark()) {
+            self.fetch = fetch.clone();
+        }
+    }
+
+    pub fn apply_auto_count(&mut self, history: &History, stats: &Stats) -> io::Result<()> {
+        let Some(auto_count) = &self.auto_count else {
+            return Ok(());
+        };
+
+        let unseen = unseen_photos(&self.folder, history, stats)?;
+
+        let changes_per_day = stats.changes_per_day(TimeDelta::days(AutoCount::WINDOW_DAYS));
+        let count = auto_count.count(changes_per_day, unseen, self.fetch.count);
+        tracing::info!(changes_per_day, unseen, count, "tuned the fetch count");
+
+        self.fetch.count = count;
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.max_size == 0 {
+            problems.push("max_size: must be greater than 0".to_owned());
+        }
+
+        if self.max_count == Some(0) {
+            problems.push("max_count: must be greater than 0".to_owned());
+        }
+
+        if self.provider.daily_source().is_some()
+            && !self.experimental.is_enabled(Feature::DailyProviders)
+        {
+            problems.push(
+                "provider: image of the day providers are turned off in experimental.daily_providers"
+                    .to_owned(),
+            );
+        }
+
+        for &feature in Feature::ALL {
+            if self.experimental.is_configured(feature)
+                && self.experimental.is_requested(feature)
+                && !feature.is_available()
+            {
+                problems.push(format!(
+                    "experimental.{}: this build of Backdrop does not include it",
+                    feature
+                ));
+            }
+        }
+
+        if self.photo_expiry_days == Some(0) {
+            problems.push("photo_expiry_days: must be at least 1".to_owned());
+        }
+
+        if self.apply_to.is_empty() {
+            problems.push("apply_to: must contain desktop or lockscreen".to_owned());
+        }
+
+        if !cfg!(windows) && self.apply_to.contains(&wallpaper::Target::LockScreen) {
+            problems.push("apply_to: the lock screen can only be set on Windows".to_owned());
+        }
+
+        if self.max_concurrent_downloads == 0 {
+            problems.push("max_concurrent_downloads: must be greater than 0".to_owned());
+        }
+
+        if self.max_processing_workers == Some(0) {
+            problems.push("max_processing_workers: must be greater than 0".to_owned());
+        }
+
+        if let Some(refresh) = &self.refresh_metadata {
+            if refresh.every_days == 0 {
+                problems.push("refresh_metadata.every_days: must be at least 1".to_owned());
+            }
+
+            if refresh.per_run == 0 {
+                problems.push("refresh_metadata.per_run: must be at least 1".to_owned());
+            }
+        }
+
+        if let Some(auto_count) = &self.auto_count {
+            if auto_count.min == 0 {
+                problems.push("auto_count.min: must be at least 1".to_owned());
+            }
+
+            if auto_count.min > auto_count.max {
+                problems.push("auto_count.max: must not be less than min".to_owned());
+            }
+
+            if auto_count.buffer_days <= 0.0 {
+                problems.push("auto_count.buffer_days: must be greater than 0".to_owned());
+            }
+        }
+
+        if let Some(api_url) = &self.api_url
+            && let Err(e) = Url::parse(api_url)
+        {
+            problems.push(format!("api_url: {}", e));
+        }
+        if self.network.pin_root_certificates && self.network.root_certificates.is_empty() {
+            problems.push(
+                "network.pin_root_certificates: requires at least one of root_certificates"
+                    .to_owned(),
+            );
+        }
+        if let Some(url) = &self.network.dns_over_https
+            && let Err(e) = Url::parse(url)
+        {
+            problems.push(format!("network.dns_over_https: {}", e));
+        }
+
+        #[cfg(feature = "s3")]
+        if let Some(s3) = &self.s3 {
+            if let Err(e) = Url::parse(&s3.endpoint) {
+                problems.push(format!("s3.endpoint: {}", e));
+            }
+
+            if s3.bucket.is_empty() {
+                problems.push("s3.bucket: must not be empty".to_owned());
+            }
+        }
+
+        #[cfg(feature = "webdav")]
+        if let Some(webdav) = &self.webdav
+            && let Err(e) = Url::parse(&webdav.url)
+        {
+            problems.push(format!("webdav.url: {}", e));
+        }
+
+        #[cfg(feature = "frame")]
+        if let Some(frame) = &self.frame {
+            if let Err(e) = Url::parse(&frame.url) {
+                problems.push(format!("frame.url: {}", e));
+            }
+            if frame.max_size == Some(0) {
+                problems.push("frame.max_size: must be greater than 0".to_owned());
+            }
+        }
+
+        if let Some(mirror) = &self.mirror {
+            match (mirror.sync_client, mirror.folder()) {
+                (Some(client), None) => problems.push(format!(
+                    "mirror.sync_client: no {} folder was found",
+                    client
+                )),
+                (None, _) if mirror.folder.is_relative() => problems.push(format!(
+                    "mirror.folder: {} must be absolute without a sync_client",
+                    mirror.folder.display()
+                )),
+                (_, Some(folder)) => validate_folder("mirror.folder", &folder, &mut problems),
+                _ => {}
+            }
+        }
+
+        validate_folder("folder", &self.folder, &mut problems);
+        for subfolder in self.quotas.keys() {
+            if !subfolder
+                .components()
+                .all(|component| matches!(component, path::Component::Normal(_)))
+            {
+                problems.push(format!(
+                    "quotas.{}: must be a subfolder of the folder",
+                    subfolder.display()
+                ));
+            }
+        }
+        if self.request_budget == Some(0) {
+            problems.push("request_budget: must be at least 1".to_owned());
+        }
+        if let Some(quiet_hours) = &self.schedule.quiet_hours
+            && quiet_hours.start == quiet_hours.end
+        {
+            problems.push("schedule.quiet_hours: start and end must differ".to_owned());
+        }
+        if self.schedule.every_minutes == Some(0) {
+            problems.push("schedule.every_minutes: must be at least 1".to_owned());
+        }
+        validate_fetch("fetch", &self.fetch, &mut problems);
+        validate_download("download", &self.download, &mut problems);
+
+        if let Some(fetch) = &self.fetch_dark {
+            validate_fetch("fetch_dark", fetch, &mut problems);
+        }
+
+        for (name, fetch) in &self.queries {
+            validate_fetch(&format!("queries.{}", name), fetch, &mut problems);
+        }
+
+        for (name, profile) in &self.profiles {
+            if let Some(folder) = &profile.folder {
+                validate_folder(&format!("profiles.{}.folder", name), folder, &mut problems);
+            }
+
+            if let Some(fetch) = &profile.fetch {
+                validate_fetch(&format!("profiles.{}.fetch", name), fetch, &mut problems);
+            }
+
+            if let Some(download) = &profile.download {
+                validate_download(
+                    &format!("profiles.{}.download", name),
+                    download,
+                    &mut problems,
+                );
+            }
+        }
+
+        if let Some(pipeline) = &self.pipeline {
+            for (index, pair) in pipeline.windows(2).enumerate() {
+                if pair[0].kind() > pair[1].kind() {
+                    problems.push(format!(
+                        "pipeline.{}: filters must come before processors, and processors before sinks",
+                        index + 1
+                    ));
+                }
+            }
+        }
+
+        for (index, fallback) in self.fallbacks.iter().enumerate() {
+            if let Err(e) = fallback.fetch(&self.fetch) {
+                problems.push(format!("fallbacks.{}: {}", index, e));
+            }
+        }
+
+        if let Some(docked) = &self.docked {
+            if let Some(fetch) = &docked.fetch {
+                validate_fetch("docked.fetch", fetch, &mut problems);
+            }
+
+            if let Some(download) = &docked.download {
+                validate_download("docked.download", download, &mut problems);
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidConfig(problems))
+        }
+    }
+
+    pub fn apply_docking_profile(&mut self) {
+        let Some(docked) = self.docked.as_ref().filter(|docked| docked.is_active()) else {
+            return;
+        };
+
+        if let Some(fetch) = &docked.fetch {
+            self.fetch = fetch.clone();
+        }
+
+        if let Some(download) = &docked.download {
+            self.download = download.clone();
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let folder = dirs::picture_dir()
+            .or_else(dirs::home_dir)
+            .unwrap()
+            .join("Backdrop");
+
+        Self {
+            folder,
+            max_size: 100_000_000,
+            max_count: None,
+            max_age_days: None,
+            delete_unshown: false,
+            photo_expiry_days: None,
+            quotas: BTreeMap::new(),
+            shared_folder: false,
+            shared_cache: None,
+            accent: false,
+            provider: Provider::Unsplash,
+            fetch: Default::default(),
+            query: None,
+            queries: BTreeMap::new(),
+            weekday_queries: HashMap::new(),
+            profiles: BTreeMap::new(),
+            download: Default::default(),
+            review: false,
+            require_approval: false,
+            motion: Default::default(),
+            wallpaper: Default::default(),
+            apply_to: Config::default_apply_to(),
+            apply_wallpaper: Self::default_apply_wallpaper(),
+            manual_change: ManualChange::default(),
+            docked: None,
+            retry: Default::default(),
+            limits: Default::default(),
+            request_budget: None,
+            run_log: false,
+            api_url: None,
+            network: Default::default(),
+            sequence: None,
+            selection: Default::default(),
+            learning: Default::default(),
+            auto_count: None,
+            refresh_metadata: None,
+            max_concurrent_downloads: Self::default_max_concurrent_downloads(),
+            max_processing_workers: None,
+            keep_folder: None,
+            blocklist: Default::default(),
+            attribution_sidecars: false,
+            schedule: Default::default(),
+            per_monitor: false,
+            fetch_dark: None,
+            offline_fallback: Self::default_offline_fallback(),
+            fallbacks: Vec::new(),
+            pipeline: None,
+            log_level: Default::default(),
+            #[cfg(feature = "metadata")]
+            embed_metadata: false,
+            #[cfg(feature = "notifications")]
+            notifications: false,
+            #[cfg(feature = "notifications")]
+            notification_template: Default::default(),
+            #[cfg(feature = "sandbox")]
+            sandbox: false,
+            #[cfg(feature = "processing")]
+            processing: Default::default(),
+            #[cfg(feature = "parallax")]
+            parallax: None,
+            #[cfg(feature = "span")]
+            span: None,
+            web: Default::default(),
+            #[cfg(feature = "s3")]
+            s3: None,
+            #[cfg(feature = "webdav")]
+            webdav: None,
+            mirror: None,
+            #[cfg(feature = "frame")]
+            frame: None,
+            experimental: Experimental::default(),
+            json: false,
+        }
+    }
+}
+
+pub fn folder() -> PathBuf {
+    dirs::config_dir().unwrap().join("Backdrop")
+}
+
+pub fn path<P: AsRef<Path>>(config_folder: P) -> PathBuf {
+    let config_folder = config_folder.as_ref();
+
+    let legacy_path = config_folder.join(LEGACY_CONFIG_FILE);
+    let path = config_folder.join(CONFIG_FILE);
+
+    if !path.exists() && legacy_path.exists() {
+        legacy_path
+    } else {
+        path
+    }
+}
+
+pub fn validate_fetch(path: &str, fetch: &Fetch, problems: &mut Vec<String>) {
+    if fetch.count == 0 {
+        problems.push(format!("{}.count: must be at least 1", path));
+    }
+
+    if let Mode::Search(search) = &fetch.mode
+        && search.page == 0
+    {
+        problems.push(format!("{}.mode.page: must be at least 1", path));
+    }
+
+    if !fetch.queries.is_empty() && fetch.queries.iter().all(|query| query.weight == 0) {
+        problems.push(format!(
+            "{}.queries: at least one weight must be greater than 0",
+            path
+        ));
+    }
+
+    // The orientation always has a value, so ignoring it is only warned about while fetching.
+    for endpoint in fetch.endpoints() {
+        for filter in fetch.ignored_filters(endpoint) {
+            if filter != "orientation" {
+                problems.push(format!(
+                    "{}.{}: not supported by {}",
+                    path, filter, endpoint
+                ));
+            }
+        }
+    }
+}
+
+fn validate_download(path: &str, download: &Download, problems: &mut Vec<String>) {
+    let quality = match download.format {
+        Format::Png => None,
+        Format::Jpeg { quality } | Format::Webp { quality } | Format::Avif { quality } => {
+            Some(quality)
+        }
+    };
+
+    if let Some(quality) = quality.filter(|quality| !(1..=100).contains(quality)) {
+        problems.push(format!(
+            "{}.format.quality: must be between 1 and 100, got {}",
+            path, quality
+        ));
+    }
+
+    if let Resolution::Custom { width, height } = download.resolution
+        && (width == 0 || height == 0)
+    {
+        problems.push(format!(
+            "{}.resolution: width and height must be greater than 0",
+            path
+        ));
+    }
+
+    if let Some(fallback) = &download.slow_fallback {
+        if fallback.time_budget_secs == 0 {
+            problems.push(format!(
+                "{}.slow_fallback.time_budget_secs: must be greater than 0",
+                path
+            ));
+        }
+        if let Resolution::Custom { width, height } = fallback.resolution
+            && (width == 0 || height == 0)
+        {
+            problems.push(format!(
+                "{}.slow_fallback.resolution: width and height must be greater than 0",
+                path
+            ));
+        }
+    }
+
+    if download.naming.max_length == Some(0) {
+        problems.push(format!(
+            "{}.naming.max_length: must be greater than 0",
+            path
+        ));
+    }
+}
+
+fn validate_folder(path: &str, folder: &Path, problems: &mut Vec<String>) {
+    let Some(existing) = folder.ancestors().find(|ancestor| ancestor.exists()) else {
+        problems.push(format!(
+            "{}: {} is not a valid path",
+            path,
+            folder.display()
+        ));
+        return;
+    };
+
+    if !existing.is_dir() {
+        problems.push(format!("{}: {} is not a folder", path, existing.display()));
+        return;
+    }
+
+    let probe = existing.join(".backdrop-write-test");
+    match fs::write(&probe, []) {
+        Ok(()) => {
+            let _ = fs::remove_file(probe);
+        }
+
+        Err(err) => problems.push(format!(
+            "{}: {} is not writable ({})",
+            path,
+            existing.display(),
+            err
+        )),
+    }
+}
+
+fn is_toml<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+}
+
+fn read<P: AsRef<Path>>(config_path: P) -> Result<Config> {
+    let config_path = config_path.as_ref();
+    let content = cloud::read_to_string(config_path)?;
+
+    let config = if is_toml(config_path) {
+        toml_edit::de::from_str(&content).map_err(|err| err.to_string())
+    } else {
+        serde_json::from_str(&content).map_err(|err| err.to_string())
+    };
+
+    config.map_err(|message| Error::ParseConfig {
+        path: config_path.to_owned(),
+        message,
+    })
+}
+
+pub fn to_toml(config: &Config) -> Result<String> {
+    let mut document = toml_edit::ser::to_document(config)?;
+
+    for (_, item) in document.iter_mut() {
+        if let Some(table) = item.as_inline_table().filter(|table| !table.is_empty()) {
+            *item = toml_edit::Item::Table(table.clone().into_table());
+        }
+    }
+
+    for (key, comment) in CONFIG_COMMENTS {
+        let prefix = format!("\n# {}\n", comment);
+
+        match document.get_mut(key) {
+            Some(toml_edit::Item::Table(table)) => table.decor_mut().set_prefix(prefix),
+
+            Some(_) => {
+                if let Some(mut key) = document.key_mut(key) {
+                    key.leaf_decor_mut().set_prefix(prefix);
+                }
+            }
+
+            None => {}
+        }
+    }
+
+    Ok(document.to_string().trim_start().to_owned())
+}
+
+pub fn update<P, F>(config_path: P, update: F) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut serde_json::Value),
+{
+    let config_path = config_path.as_ref();
+    let content = fs::read_to_string(config_path)?;
+
+    if !is_toml(config_path) {
+        let mut value: serde_json::Value =
+            serde_json::from_str(&content).map_err(Into::<io::Error>::into)?;
+
+        update(&mut value);
+
+        let content = serde_json::to_string_pretty(&value).map_err(Into::<io::Error>::into)?;
+        fs::write(config_path, &content)?;
+
+        return Ok(());
+    }
+
+    let mut document: toml_edit::DocumentMut = content.parse()?;
+    let original: serde_json::Value = toml_edit::de::from_document(document.clone())?;
+
+    let mut value = original.clone();
+    update(&mut value);
+
+    let updated = toml_edit::ser::to_document(&value)?;
+    for (key, item) in updated.iter() {
+        if original.get(key) == value.get(key) {
+            continue;
+        }
+
+        match (document.get_mut(key), item.clone()) {
+            (
+                Some(toml_edit::Item::Table(table)),
+                toml_edit::Item::Value(toml_edit::Value::InlineTable(inline)),
+            ) => {
+                let decor = table.decor().clone();
+                *table = inline.into_table();
+                *table.decor_mut() = decor;
+            }
+
+            (Some(toml_edit::Item::Value(existing)), toml_edit::Item::Value(mut value)) => {
+                *value.decor_mut() = existing.decor().clone();
+                *existing = value;
+            }
+
+            (_, item) => {
+                document.insert(key, item);
+            }
+        }
+    }
+
+    fs::write(config_path, document.to_string())?;
+
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(config_folder: P) -> Result<Config> {
+    let config_folder = config_folder.as_ref();
+
+    if !config_folder.exists() {
+        fs::create_dir_all(config_folder)?;
+    }
+
+    let env_path = config_folder.join(ENV_FILE);
+    let config_path = path(config_folder);
+    let requires_config = !cloud::exists(&env_path) || !cloud::exists(&config_path);
+
+    if requires_config {
+        if !cloud::exists(&env_path) {
+            fs::write(&env_path, ENV_TEMPLATE)?;
+        }
+
+        if !cloud::exists(&config_path) {
+            fs::write(&config_path, to_toml(&Config::default())?)?;
+        }
+
+        return Err(Error::RequiresConfigure);
+    }
+
+    dotenvy::from_read(cloud::read_to_string(env_path)?.as_bytes()).map_err(|err| match err {
+        dotenvy::Error::Io(err) => err,
+
+        _ => unreachable!(),
+    })?;
+
+    let config = read(config_path)?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_queries() -> Config {
+        let mut config = Config {
+            query: Some("count:3".to_owned()),
+            ..Default::default()
+        };
+        config.queries.insert(
+            "night".to_owned(),
+            Fetch {
+                count: 7,
+                ..Default::default()
+            },
+        );
+        config
+            .weekday_queries
+            .insert(Weekday::Mon, "night".to_owned());
+
+        config
+    }
+
+    #[test]
+    fn configured_query_applies_over_the_weekday_query() {
+        let mut config = config_with_queries();
+
+        config.apply_queries(Weekday::Mon, None, None).unwrap();
+
+        assert_eq!(config.fetch.count, 3);
+    }
+
+    #[test]
+    fn command_line_query_name_wins_over_the_configured_query() {
+        let mut config = config_with_queries();
+
+        config
+            .apply_queries(Weekday::Tue, Some("night"), None)
+            .unwrap();
+
+        assert_eq!(config.fetch.count, 7);
+    }
+
+    #[test]
+    fn command_line_query_wins_over_everything() {
+        let mut config = config_with_queries();
+
+        config
+            .apply_queries(Weekday::Mon, Some("night"), Some("count:9"))
+            .unwrap();
+
+        assert_eq!(config.fetch.count, 9);
+    }
+
+    #[test]
+    fn filters_the_endpoint_ignores_are_rejected() {
+        let fetch: Fetch = toml_edit::de::from_str(
+            r#"
+            count = 5
+            color = "blue"
+            mode = { type = "search", order_by = "relevant" }
+            queries = [{ category = "topic", value = "nature" }]
+            "#,
+        )
+        .unwrap();
+
+        let mut problems = Vec::new();
+        validate_fetch("fetch", &fetch, &mut problems);
+
+        assert_eq!(
+            problems,
+            [
+                "fetch.color: not supported by topic listings",
+                "fetch.mode.order_by: not supported by topic listings",
+            ]
+        );
+    }
+
+    #[test]
+    fn command_line_queries_with_ignored_filters_are_rejected() {
+        let mut config = config_with_queries();
+
+        let result = config.apply_queries(Weekday::Tue, None, Some("color:blue topic:nature"));
+
+        assert!(
+            matches!(result, Err(Error::InvalidConfig(problems)) if problems == ["fetch.color: not supported by topic listings"])
+        );
+    }
+
+    #[test]
+    fn config_never_shows_the_web_token() {
+        const SECRET: &str = "hunter2";
+
+        let mut config = Config::default();
+        config.web.token = Some(Redacted::new(SECRET.to_owned()));
+
+        assert!(!format!("{:?}", config).contains(SECRET));
+        assert!(!format!("{:#?}", config).contains(SECRET));
+        assert!(!to_toml(&config).unwrap().contains(SECRET));
+    }
+}
@@ -0,0 +1,311 @@
+use std::{io, path::Path};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{mpsc, oneshot},
+};
+
+pub use native::Listener;
+
+const CURRENT_FLAG: &str = "--current";
+
+#[derive(Debug)]
+pub enum Action {
+    Next,
+    Pause,
+    Resume,
+    SetQuery(String, oneshot::Sender<Result<(), String>>),
+    Pin(Vec<String>, bool, oneshot::Sender<Result<(), String>>),
+    Block(Vec<String>, bool, oneshot::Sender<Result<(), String>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Next,
+    Pause,
+    Resume,
+    Pin(Vec<String>),
+    Unpin(Vec<String>),
+    Block { ids: Vec<String>, current: bool },
+}
+
+impl Request {
+    fn to_line(&self) -> String {
+        let (name, ids) = match self {
+            Request::Next => ("next", &[][..]),
+            Request::Pause => ("pause", &[][..]),
+            Request::Resume => ("resume", &[][..]),
+            Request::Pin(ids) => ("pin", ids.as_slice()),
+            Request::Unpin(ids) => ("unpin", ids.as_slice()),
+            Request::Block { ids, .. } => ("block", ids.as_slice()),
+        };
+
+        let mut words = vec![name];
+        if matches!(self, Request::Block { current: true, .. }) {
+            words.push(CURRENT_FLAG);
+        }
+        words.extend(ids.iter().map(String::as_str));
+
+        words.join(" ")
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let name = words.next()?;
+        let ids = || words.clone().map(ToOwned::to_owned).collect::<Vec<_>>();
+
+        match name {
+            "next" => Some(Request::Next),
+            "pause" => Some(Request::Pause),
+            "resume" => Some(Request::Resume),
+            "pin" => Some(Request::Pin(ids())),
+            "unpin" => Some(Request::Unpin(ids())),
+            "block" => {
+                let ids = ids();
+                let current = ids.iter().any(|id| id == CURRENT_FLAG);
+
+                Some(Request::Block {
+                    ids: ids.into_iter().filter(|id| id != CURRENT_FLAG).collect(),
+                    current,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn into_action(self, reply: oneshot::Sender<Result<(), String>>) -> Action {
+        match self {
+            Request::Next => Action::Next,
+            Request::Pause => Action::Pause,
+            Request::Resume => Action::Resume,
+            Request::Pin(ids) => Action::Pin(ids, true, reply),
+            Request::Unpin(ids) => Action::Pin(ids, false, reply),
+            Request::Block { ids, current } => Action::Block(ids, current, reply),
+        }
+    }
+}
+
+pub async fn send<P: AsRef<Path>>(config_folder: P, request: Request) -> io::Result<()> {
+    let stream = native::connect(config_folder.as_ref()).await?;
+    let (reader, mut writer) = tokio::io::split(stream);
+
+    writer
+        .write_all(format!("{}\n", request.to_line()).as_bytes())
+        .await?;
+
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+
+    match response.trim_end() {
+        "ok" => Ok(()),
+        message => Err(io::Error::other(message.to_owned())),
+    }
+}
+
+async fn handle<S>(stream: S, actions: mpsc::Sender<Action>) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match Request::parse(line.trim()) {
+        Some(request) => {
+            let (tx, rx) = oneshot::channel();
+
+            match actions.send(request.into_action(tx)).await {
+                Ok(()) => match rx.await {
+                    Ok(Err(message)) => message,
+                    _ => "ok".to_owned(),
+                },
+                Err(_) => "Backdrop is shutting down".to_owned(),
+            }
+        }
+
+        None => "Unknown request".to_owned(),
+    };
+
+    writer.write_all(format!("{}\n", response).as_bytes()).await
+}
+
+#[cfg(unix)]
+mod native {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+    };
+
+    use tokio::{
+        net::{UnixListener, UnixStream},
+        sync::mpsc,
+    };
+
+    use super::Action;
+
+    const SOCKET_FILE: &str = "backdrop.sock";
+
+    #[derive(Debug)]
+    pub struct Listener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        pub fn bind<P: AsRef<Path>>(config_folder: P) -> io::Result<Self> {
+            let path = config_folder.as_ref().join(SOCKET_FILE);
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another Backdrop instance is already running",
+                ));
+            }
+
+            match fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+
+            Ok(Self {
+                listener: UnixListener::bind(&path)?,
+                path,
+            })
+        }
+
+        pub async fn serve(self, actions: mpsc::Sender<Action>) -> io::Result<()> {
+            loop {
+                let (stream, _) = self.listener.accept().await?;
+
+                let actions = actions.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = super::handle(stream, actions).await {
+                        tracing::warn!(error = %err, "control request failed");
+                    }
+                });
+            }
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    pub async fn connect(config_folder: &Path) -> io::Result<UnixStream> {
+        UnixStream::connect(config_folder.join(SOCKET_FILE)).await
+    }
+}
+
+#[cfg(windows)]
+mod native {
+    use std::{io, path::Path};
+
+    use sha2::{Digest, Sha256};
+    use tokio::{
+        net::windows::named_pipe::{
+            ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions,
+        },
+        sync::mpsc,
+    };
+
+    use super::Action;
+
+    #[derive(Debug)]
+    pub struct Listener {
+        name: String,
+        server: NamedPipeServer,
+    }
+
+    impl Listener {
+        pub fn bind<P: AsRef<Path>>(config_folder: P) -> io::Result<Self> {
+            let name = pipe_name(config_folder.as_ref());
+            let server = ServerOptions::new()
+                .first_pipe_instance(true)
+                .create(&name)
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::PermissionDenied => io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        "another Backdrop instance is already running",
+                    ),
+                    _ => err,
+                })?;
+
+            Ok(Self { name, server })
+        }
+
+        pub async fn serve(mut self, actions: mpsc::Sender<Action>) -> io::Result<()> {
+            loop {
+                self.server.connect().await?;
+
+                let next = ServerOptions::new().create(&self.name)?;
+                let stream = std::mem::replace(&mut self.server, next);
+
+                let actions = actions.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = super::handle(stream, actions).await {
+                        tracing::warn!(error = %err, "control request failed");
+                    }
+                });
+            }
+        }
+    }
+
+    pub async fn connect(config_folder: &Path) -> io::Result<NamedPipeClient> {
+        ClientOptions::new().open(pipe_name(config_folder))
+    }
+
+    fn pipe_name(config_folder: &Path) -> String {
+        let hash = Sha256::digest(config_folder.to_string_lossy().as_bytes());
+
+        format!(r"\\.\pipe\backdrop-{:x}", hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, AsyncReadExt};
+
+    use super::*;
+
+    #[test]
+    fn requests_survive_the_line_protocol() {
+        let requests = [
+            Request::Next,
+            Request::Pause,
+            Request::Resume,
+            Request::Pin(vec!["a".to_owned(), "b".to_owned()]),
+            Request::Unpin(vec!["a".to_owned()]),
+            Request::Block {
+                ids: Vec::new(),
+                current: true,
+            },
+        ];
+
+        for request in requests {
+            assert_eq!(Request::parse(&request.to_line()), Some(request));
+        }
+    }
+
+    #[tokio::test]
+    async fn handled_requests_become_actions() {
+        let (client, server) = duplex(64);
+        let (tx, mut rx) = mpsc::channel(1);
+        let server = tokio::spawn(handle(server, tx));
+
+        let (mut reader, mut writer) = tokio::io::split(client);
+        writer.write_all(b"pin a b\n").await.unwrap();
+
+        let Some(Action::Pin(ids, true, reply)) = rx.recv().await else {
+            panic!("expected a pin action");
+        };
+        assert_eq!(ids, ["a", "b"]);
+        reply.send(Err("not found".to_owned())).unwrap();
+
+        server.await.unwrap().unwrap();
+        let mut response = String::new();
+        reader.read_to_string(&mut response).await.unwrap();
+        assert_eq!(response, "not found\n");
+    }
+}
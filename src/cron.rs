@@ -0,0 +1,275 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::calendar;
+
+const SEARCH_DAYS: i64 = 4 * 366;
+
+#[derive(Debug, Error)]
+pub enum ParseCronError {
+    #[error("A cron expression needs 5 fields (minute hour day month weekday), got {0}")]
+    FieldCount(usize),
+
+    #[error("Invalid {field} field `{value}` in cron expression")]
+    InvalidField { field: &'static str, value: String },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+}
+
+const MINUTE: Field = Field {
+    name: "minute",
+    min: 0,
+    max: 59,
+};
+const HOUR: Field = Field {
+    name: "hour",
+    min: 0,
+    max: 23,
+};
+const DAY: Field = Field {
+    name: "day",
+    min: 1,
+    max: 31,
+};
+const MONTH: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+};
+const WEEKDAY: Field = Field {
+    name: "weekday",
+    min: 0,
+    max: 7,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, ParseCronError> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(ParseCronError::FieldCount(fields.len()));
+        };
+
+        let mut weekdays = parse_field(weekday, WEEKDAY)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field(minute, MINUTE)?,
+            hours: parse_field(hour, HOUR)?,
+            days: parse_field(day, DAY)?,
+            months: parse_field(month, MONTH)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    pub fn next<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let today = after.date_naive();
+
+        (0..=SEARCH_DAYS)
+            .filter_map(|days| today.checked_add_signed(TimeDelta::days(days)))
+            .filter(|date| self.matches_date(*date))
+            .find_map(|date| {
+                self.times()
+                    .filter_map(|time| calendar::resolve(&after.timezone(), date, time))
+                    .filter(|at| at > after)
+                    .min()
+            })
+    }
+
+    pub fn previous<Tz: TimeZone>(&self, before: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let today = before.date_naive();
+
+        (0..=SEARCH_DAYS)
+            .filter_map(|days| today.checked_sub_signed(TimeDelta::days(days)))
+            .filter(|date| self.matches_date(*date))
+            .find_map(|date| {
+                self.times()
+                    .filter_map(|time| calendar::resolve(&before.timezone(), date, time))
+                    .filter(|at| at <= before)
+                    .max()
+            })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !contains(self.months, date.month()) {
+            return false;
+        }
+
+        let day = contains(self.days, date.day());
+        let weekday = contains(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        }
+    }
+
+    fn times(&self) -> impl Iterator<Item = NaiveTime> + '_ {
+        (HOUR.min..=HOUR.max)
+            .filter(|&hour| contains(self.hours, hour))
+            .flat_map(move |hour| {
+                (MINUTE.min..=MINUTE.max)
+                    .filter(|&minute| contains(self.minutes, minute))
+                    .filter_map(move |minute| NaiveTime::from_hms_opt(hour, minute, 0))
+            })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = ParseCronError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Self::parse(expression)
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = ParseCronError;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        Self::parse(&expression)
+    }
+}
+
+impl From<Cron> for String {
+    fn from(cron: Cron) -> Self {
+        cron.expression
+    }
+}
+
+fn parse_field(text: &str, field: Field) -> Result<u64, ParseCronError> {
+    let invalid = || ParseCronError::InvalidField {
+        field: field.name,
+        value: text.to_owned(),
+    };
+
+    let mut set = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (field.min, field.max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| invalid())?,
+                    end.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    let end = if part.contains('/') { field.max } else { value };
+                    (value, end)
+                }
+            },
+        };
+
+        if step == 0 || start < field.min || end > field.max || start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::tests::{assert_fires_once_a_day, local, Dst};
+
+    fn fires(cron: &Cron, mut at: DateTime<Dst>) -> impl Iterator<Item = DateTime<Dst>> + '_ {
+        std::iter::from_fn(move || {
+            at = cron.next(&at)?;
+            Some(at)
+        })
+    }
+
+    #[test]
+    fn daily_run_fires_once_across_spring_forward() {
+        let cron = Cron::parse("30 2 * * *").unwrap();
+
+        assert_fires_once_a_day(fires(&cron, local(3, 27, 12, 0)).take(5));
+    }
+
+    #[test]
+    fn daily_run_fires_once_across_fall_back() {
+        let cron = Cron::parse("30 2 * * *").unwrap();
+
+        assert_fires_once_a_day(fires(&cron, local(10, 23, 12, 0)).take(5));
+    }
+
+    #[test]
+    fn skipped_slots_fire_once_after_the_gap() {
+        let cron = Cron::parse("0,30 2,3 * * *").unwrap();
+
+        let runs: Vec<_> = fires(&cron, local(3, 29, 1, 0))
+            .take(3)
+            .map(|at| at.naive_local().time())
+            .collect();
+
+        assert_eq!(
+            runs,
+            [
+                NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(3, 30, 0).unwrap(),
+                NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_hour_fires_once() {
+        let cron = Cron::parse("30 * * * *").unwrap();
+
+        let runs: Vec<_> = fires(&cron, local(10, 25, 1, 0)).take(3).collect();
+
+        assert!(runs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            runs.iter()
+                .map(|at| at.naive_local().time().format("%H:%M").to_string())
+                .collect::<Vec<_>>(),
+            ["01:30", "02:30", "03:30"]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    env, fmt, io,
+    path::{Path, PathBuf},
+};
+
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::PART_EXTENSION;
+
+const BING_URL: &str = "https://www.bing.com";
+const APOD_URL: &str = "https://api.nasa.gov/planetary/apod";
+const APOD_DEMO_KEY: &str = "DEMO_KEY";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("{0} did not return an image today")]
+    NoImage(Source),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Bing,
+    Apod,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Bing => "Bing",
+            Source::Apod => "NASA APOD",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub id: String,
+    pub title: String,
+    pub copyright: Option<String>,
+    pub url: String,
+    pub page_url: Option<String>,
+}
+
+impl Image {
+    pub fn extension(&self) -> &str {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| {
+                let path = url.path().to_lowercase();
+                ["jpg", "jpeg", "png", "webp"]
+                    .into_iter()
+                    .find(|extension| path.ends_with(&format!(".{}", extension)))
+            })
+            .unwrap_or("jpg")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BingArchive {
+    images: Vec<BingImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BingImage {
+    startdate: String,
+    urlbase: String,
+    title: String,
+    #[serde(default)]
+    copyright: Option<String>,
+    #[serde(default)]
+    copyrightlink: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Apod {
+    date: String,
+    title: String,
+    url: String,
+    #[serde(default)]
+    hdurl: Option<String>,
+    media_type: String,
+    #[serde(default)]
+    copyright: Option<String>,
+}
+
+pub async fn fetch(http: &Client, source: Source) -> Result<Image> {
+    match source {
+        Source::Bing => fetch_bing(http).await,
+        Source::Apod => fetch_apod(http).await,
+    }
+}
+
+async fn fetch_bing(http: &Client) -> Result<Image> {
+    let archive: BingArchive = http
+        .get(format!("{}/HPImageArchive.aspx", BING_URL))
+        .query(&[("format", "js"), ("idx", "0"), ("n", "1")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let image = archive
+        .images
+        .into_iter()
+        .next()
+        .ok_or(Error::NoImage(Source::Bing))?;
+
+    Ok(Image {
+        id: format!("bing-{}", image.startdate),
+        title: image.title,
+        copyright: image.copyright,
+        url: format!("{}{}_UHD.jpg", BING_URL, image.urlbase),
+        page_url: image.copyrightlink,
+    })
+}
+
+async fn fetch_apod(http: &Client) -> Result<Image> {
+    let api_key = env::var("NASA_API_KEY").unwrap_or_else(|_| APOD_DEMO_KEY.to_owned());
+
+    let apod: Apod = http
+        .get(APOD_URL)
+        .query(&[("api_key", api_key.as_str())])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if apod.media_type != "image" {
+        return Err(Error::NoImage(Source::Apod));
+    }
+
+    Ok(Image {
+        page_url: Some(format!(
+            "https://apod.nasa.gov/apod/ap{}.html",
+            apod.date.get(2..).unwrap_or_default().replace('-', "")
+        )),
+        id: format!("apod-{}", apod.date),
+        title: apod.title,
+        copyright: apod.copyright.map(|copyright| copyright.trim().to_owned()),
+        url: apod.hdurl.unwrap_or(apod.url),
+    })
+}
+
+pub async fn download<P: AsRef<Path>>(http: &Client, image: &Image, folder: P) -> Result<PathBuf> {
+    let folder = folder.as_ref();
+    fs::create_dir_all(folder).await?;
+
+    let path = folder.join(format!("{}.{}", image.id, image.extension()));
+    if fs::try_exists(&path).await? {
+        return Ok(path);
+    }
+
+    let bytes = http
+        .get(&image.url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let temp_path = path.with_extension(format!("{}.{}", image.extension(), PART_EXTENSION));
+    let mut file = fs::File::create(&temp_path).await?;
+    file.write_all(&bytes).await?;
+    file.sync_all().await?;
+    drop(file);
+    fs::rename(&temp_path, &path).await?;
+
+    Ok(path)
+}
@@ -0,0 +1,168 @@
+use crate::{Orientation, Resolution};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Monitor {
+    pub fn orientation(&self) -> Orientation {
+        if self.height > self.width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        Resolution::Custom {
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+pub fn bounds(monitors: &[Monitor]) -> Option<(i32, i32, u32, u32)> {
+    let left = monitors.iter().map(|monitor| monitor.x).min()?;
+    let top = monitors.iter().map(|monitor| monitor.y).min()?;
+    let right = monitors
+        .iter()
+        .map(|monitor| monitor.x + monitor.width as i32)
+        .max()?;
+    let bottom = monitors
+        .iter()
+        .map(|monitor| monitor.y + monitor.height as i32)
+        .max()?;
+
+    Some((left, top, (right - left) as u32, (bottom - top) as u32))
+}
+
+#[cfg(windows)]
+pub fn primary_resolution() -> Option<(u32, u32)> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    Some((width as u32, height as u32))
+}
+
+#[cfg(not(windows))]
+pub fn primary_resolution() -> Option<(u32, u32)> {
+    None
+}
+
+#[cfg(windows)]
+pub fn monitor_count() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CMONITORS};
+
+    let count = unsafe { GetSystemMetrics(SM_CMONITORS) };
+    if count <= 0 {
+        return None;
+    }
+
+    Some(count as u32)
+}
+
+#[cfg(not(windows))]
+pub fn monitor_count() -> Option<u32> {
+    use std::process::Command;
+
+    let output = Command::new("xrandr").arg("--listmonitors").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .strip_prefix("Monitors:")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(windows)]
+pub fn monitors() -> Vec<Monitor> {
+    use windows::{core::PCWSTR, Win32::System::Com::CoTaskMemFree};
+
+    let Some(desktop_wallpaper) = desktop_wallpaper() else {
+        return Vec::new();
+    };
+
+    let count = unsafe { desktop_wallpaper.GetMonitorDevicePathCount() }.unwrap_or(0);
+
+    (0..count)
+        .filter_map(|index| unsafe {
+            let path = desktop_wallpaper.GetMonitorDevicePathAt(index).ok()?;
+            let id = path.to_string();
+            let rect = desktop_wallpaper.GetMonitorRECT(PCWSTR(path.0));
+            CoTaskMemFree(Some(path.0 as *const _));
+
+            let rect = rect.ok()?;
+            Some(Monitor {
+                id: id.ok()?,
+                x: rect.left,
+                y: rect.top,
+                width: (rect.right - rect.left) as u32,
+                height: (rect.bottom - rect.top) as u32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+pub fn monitors() -> Vec<Monitor> {
+    use std::process::Command;
+
+    let Ok(output) = Command::new("xrandr").arg("--listmonitors").output() else {
+        return Vec::new();
+    };
+
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let geometry = fields.nth(2)?;
+            let id = fields.last()?;
+
+            let (width, rest) = geometry.split_once('/')?;
+            let (_, rest) = rest.split_once('x')?;
+            let (height, rest) = rest.split_once('/')?;
+            let mut offsets = rest.split('+').skip(1);
+
+            Some(Monitor {
+                id: id.to_owned(),
+                x: offsets.next()?.parse().ok()?,
+                y: offsets.next()?.parse().ok()?,
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+pub(crate) fn desktop_wallpaper() -> Option<windows::Win32::UI::Shell::IDesktopWallpaper> {
+    use windows::Win32::{
+        System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED},
+        UI::Shell::DesktopWallpaper,
+    };
+
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        CoCreateInstance(&DesktopWallpaper, None, CLSCTX_ALL).ok()
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use tokio::sync::broadcast;
+
+use crate::Photo;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    PhotoFetched(Photo),
+    PhotoDownloaded { photo: Photo, path: PathBuf },
+    WallpaperSet(PathBuf),
+    CleanupDeleted(PathBuf),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    pub fn emit(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
@@ -0,0 +1,68 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    WebUi,
+    DailyProviders,
+    Span,
+}
+
+impl Feature {
+    pub const ALL: &[Feature] = &[Feature::WebUi, Feature::DailyProviders, Feature::Span];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::WebUi => "Web UI served by `backdrop serve`",
+            Feature::DailyProviders => "Bing and NASA APOD image of the day providers",
+            Feature::Span => "Wallpapers spanned across all monitors",
+        }
+    }
+
+    pub fn is_available(self) -> bool {
+        match self {
+            Feature::WebUi => cfg!(feature = "web"),
+            Feature::DailyProviders => true,
+            Feature::Span => cfg!(feature = "span"),
+        }
+    }
+
+    pub fn is_enabled_by_default(self) -> bool {
+        match self {
+            Feature::WebUi | Feature::DailyProviders | Feature::Span => true,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::WebUi => "web_ui",
+            Feature::DailyProviders => "daily_providers",
+            Feature::Span => "span",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Experimental(BTreeMap<Feature, bool>);
+
+impl Experimental {
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        feature.is_available() && self.is_requested(feature)
+    }
+
+    pub fn is_requested(&self, feature: Feature) -> bool {
+        self.0
+            .get(&feature)
+            .copied()
+            .unwrap_or_else(|| feature.is_enabled_by_default())
+    }
+
+    pub fn is_configured(&self, feature: Feature) -> bool {
+        self.0.contains_key(&feature)
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{selection::Selection, unsplash::Query};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Download,
+    Review,
+    Rotation,
+    Sequence,
+    Daily,
+    PerMonitor,
+    Span,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Download => "newest download",
+            Source::Review => "picked during review",
+            Source::Rotation => "rotation through downloaded photos",
+            Source::Sequence => "sequence slot",
+            Source::Daily => "image of the day",
+            Source::PerMonitor => "newest download for the monitor",
+            Source::Span => "spanned across monitors",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Context {
+    pub source: Source,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<Query>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Selection>,
+    pub candidates: usize,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scores: BTreeMap<String, f64>,
+}
+
+impl Context {
+    pub fn new(source: Source, candidates: usize) -> Self {
+        Self {
+            source,
+            query: None,
+            filters: Vec::new(),
+            strategy: None,
+            candidates,
+            scores: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_score(mut self, name: &str, value: f64) -> Self {
+        self.scores.insert(name.to_owned(), value);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Explanation {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub context: Context,
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanations {
+    path: PathBuf,
+}
+
+impl Explanations {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn append(&self, id: &str, context: Context) -> io::Result<()> {
+        let explanation = Explanation {
+            id: id.to_owned(),
+            at: Utc::now(),
+            context,
+        };
+
+        let mut line = serde_json::to_string(&explanation)?;
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    pub fn for_photo(&self, id: &str) -> io::Result<Vec<Explanation>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Explanation>(line).ok())
+            .filter(|explanation| explanation.id == id)
+            .collect())
+    }
+}
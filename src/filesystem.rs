@@ -0,0 +1,138 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone)]
+pub struct File {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+pub trait Filesystem {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>>;
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl Filesystem for RealFs {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>> {
+        if !folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for file in folder.read_dir()? {
+            let file = file?;
+
+            let metadata = file.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            files.push(File {
+                path: file.path(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            });
+        }
+
+        Ok(files)
+    }
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        if !folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut folders = Vec::new();
+        for entry in folder.read_dir()? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                folders.push(entry.path());
+            }
+        }
+
+        Ok(folders)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[derive(Debug, Default)]
+pub struct FakeFs {
+    files: std::sync::Mutex<std::collections::BTreeMap<PathBuf, File>>,
+}
+
+#[cfg(feature = "test-util")]
+impl FakeFs {
+    pub fn add_file<P: AsRef<Path>>(&self, path: P, size: u64, modified: SystemTime) {
+        let path = path.as_ref().to_owned();
+        self.files.lock().unwrap().insert(
+            path.clone(),
+            File {
+                path,
+                size,
+                modified: Some(modified),
+            },
+        );
+    }
+
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.files.lock().unwrap().contains_key(path.as_ref())
+    }
+
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl Filesystem for FakeFs {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>> {
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|file| file.path.parent() == Some(folder))
+            .cloned()
+            .collect())
+    }
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        let folders: std::collections::BTreeSet<_> = self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|path| {
+                path.ancestors()
+                    .skip(1)
+                    .find(|ancestor| ancestor.parent() == Some(folder))
+            })
+            .map(ToOwned::to_owned)
+            .collect();
+
+        Ok(folders.into_iter().collect())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .remove(path)
+            .map(drop)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use reqwest::{header, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cleanup::Candidate,
+    redact::Redacted,
+    remote::percent_encode,
+    state::{self, Migration},
+};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub uploaded: Vec<PathBuf>,
+    pub unchanged: usize,
+    pub removed: Vec<String>,
+    pub over_budget: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct Frame {
+    http: reqwest::Client,
+    url: Url,
+    token: Option<Redacted<String>>,
+    max_size: Option<u64>,
+}
+
+impl Frame {
+    pub fn new(mut url: Url) -> Self {
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Self {
+            http: reqwest::Client::new(),
+            url,
+            token: None,
+            max_size: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: Redacted<String>) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    pub async fn sync<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        folder: P,
+        candidates: &[Candidate],
+        state_path: Q,
+    ) -> io::Result<SyncReport> {
+        let folder = folder.as_ref();
+        let state_path = state_path.as_ref();
+
+        let mut pushed: BTreeMap<String, String> = state::load(state_path, MIGRATIONS)?;
+        let mut report = SyncReport::default();
+
+        let mut candidates: Vec<_> = candidates.iter().collect();
+        candidates.sort_by(|a, b| {
+            b.protected
+                .cmp(&a.protected)
+                .then(b.downloaded_at.cmp(&a.downloaded_at))
+        });
+
+        let mut total = 0;
+        let mut selected = BTreeMap::new();
+        for candidate in candidates {
+            if self
+                .max_size
+                .is_some_and(|max_size| total + candidate.size > max_size)
+            {
+                report.over_budget.push(candidate.path.clone());
+                continue;
+            }
+
+            total += candidate.size;
+            selected.insert(name(folder, &candidate.path)?, &candidate.path);
+        }
+
+        let stale: Vec<_> = pushed
+            .keys()
+            .filter(|name| !selected.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in stale {
+            match self.delete(&name).await {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+
+            pushed.remove(&name);
+            state::save(state_path, &pushed, MIGRATIONS)?;
+            report.removed.push(name);
+        }
+
+        for (name, path) in selected {
+            let content = fs::read(path)?;
+            let etag = hex(&Sha256::digest(&content));
+            if pushed.get(&name) == Some(&etag) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            if self.put(&name, &etag, content).await? {
+                report.uploaded.push(path.clone());
+            } else {
+                report.unchanged += 1;
+            }
+
+            pushed.insert(name, etag);
+            state::save(state_path, &pushed, MIGRATIONS)?;
+        }
+
+        Ok(report)
+    }
+
+    async fn put(&self, name: &str, etag: &str, content: Vec<u8>) -> io::Result<bool> {
+        let response = self
+            .request(reqwest::Method::PUT, name)?
+            .header(header::IF_NONE_MATCH, format!("\"{}\"", etag))
+            .body(content)
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::PRECONDITION_FAILED | StatusCode::NOT_MODIFIED => Ok(false),
+            status => Err(io::Error::other(format!(
+                "the frame rejected {} with {}",
+                name, status
+            ))),
+        }
+    }
+
+    async fn delete(&self, name: &str) -> io::Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, name)?
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(io::ErrorKind::NotFound.into()),
+            status => Err(io::Error::other(format!(
+                "the frame could not delete {} ({})",
+                name, status
+            ))),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, name: &str) -> io::Result<reqwest::RequestBuilder> {
+        let url = self
+            .url
+            .join(&percent_encode(name, true))
+            .map_err(io::Error::other)?;
+
+        let request = self.http.request(method, url);
+        Ok(match &self.token {
+            Some(token) => request.bearer_auth(token.expose()),
+            None => request,
+        })
+    }
+}
+
+fn name(folder: &Path, path: &Path) -> io::Result<String> {
+    let relative = path.strip_prefix(folder).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not inside the photo folder", path.display()),
+        )
+    })?;
+
+    let mut segments = Vec::new();
+    for component in relative.components() {
+        let Component::Normal(component) = component else {
+            return Err(io::ErrorKind::InvalidInput.into());
+        };
+
+        segments.push(component.to_string_lossy().into_owned());
+    }
+
+    Ok(segments.join("/"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
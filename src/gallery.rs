@@ -0,0 +1,74 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::{
+    history::{self, Entry, History},
+    recap::{escape, photo_link},
+};
+
+pub fn gallery(history: &History, photos: &[PathBuf]) -> String {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>Backdrop gallery</title>").unwrap();
+    writeln!(html, "<style>{}</style>", STYLE).unwrap();
+    writeln!(html, "</head><body>").unwrap();
+    writeln!(html, "<h1>Gallery</h1>").unwrap();
+    writeln!(
+        html,
+        "<p>{} photos. Pin one with <code>backdrop pin &lt;id&gt;</code>.</p>",
+        photos.len()
+    )
+    .unwrap();
+
+    writeln!(html, "<div class=\"photos\">").unwrap();
+    for path in photos {
+        let id = history.photo_id(path);
+        let entry = history.get(&id);
+
+        writeln!(html, "<figure>").unwrap();
+        writeln!(
+            html,
+            "<a href=\"file:///{0}\"><img src=\"file:///{0}\" alt=\"{1}\" loading=\"lazy\"></a>",
+            escape(&path.to_string_lossy().replace('\\', "/")),
+            escape(&id)
+        )
+        .unwrap();
+
+        let url = entry.map_or_else(|| Some(history::photo_url(&id)), Entry::photo_url);
+        let credit = entry.map_or(String::new(), |entry| {
+            let name = escape(entry.photographer.name());
+
+            match entry.photographer.profile_url() {
+                Some(url) => format!(" by <a href=\"{}\">{}</a>", escape(url), name),
+                None => format!(" by {}", name),
+            }
+        });
+        let pinned = if entry.is_some_and(|entry| entry.pinned) {
+            " <span class=\"pinned\">pinned</span>"
+        } else {
+            ""
+        };
+        writeln!(
+            html,
+            "<figcaption>{}{}{}</figcaption>",
+            photo_link(&id, url.as_deref()),
+            credit,
+            pinned
+        )
+        .unwrap();
+        writeln!(html, "</figure>").unwrap();
+    }
+    writeln!(html, "</div>").unwrap();
+
+    writeln!(html, "</body></html>").unwrap();
+
+    html
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 1200px; margin: 2em auto; }
+.photos { display: grid; grid-template-columns: repeat(auto-fill, minmax(220px, 1fr)); gap: 1em; }
+.photos img { width: 100%; height: 150px; object-fit: cover; border-radius: 4px; }
+figure { margin: 0; }
+figcaption { font-size: 0.9em; }
+.pinned { background: #216e39; color: white; border-radius: 2px; padding: 0 4px; }";
@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::history::History;
+
+const HEADER_SIZE: usize = 64 * 1024;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const MIN_SATURATION: f64 = 0.15;
+const HUES: [&str; 12] = [
+    "red", "orange", "yellow", "lime", "green", "teal", "cyan", "azure", "blue", "violet",
+    "magenta", "pink",
+];
+const RESOLUTIONS: [(u32, &str); 5] = [
+    (0, "below 1080p"),
+    (1080, "1080p"),
+    (1440, "1440p"),
+    (2160, "4K"),
+    (2880, "5K and up"),
+];
+const ASPECT_RATIOS: [(f64, &str); 6] = [
+    (0.0, "portrait"),
+    (0.9, "square"),
+    (1.1, "4:3"),
+    (1.45, "16:10"),
+    (1.7, "16:9"),
+    (1.9, "ultrawide"),
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Histogram {
+    pub buckets: Vec<(String, usize)>,
+}
+
+impl Histogram {
+    fn with_labels<'a, I: IntoIterator<Item = &'a str>>(labels: I) -> Self {
+        Self {
+            buckets: labels
+                .into_iter()
+                .map(|label| (label.to_owned(), 0))
+                .collect(),
+        }
+    }
+
+    fn add(&mut self, label: &str) {
+        match self.buckets.iter_mut().find(|(bucket, _)| bucket == label) {
+            Some((_, count)) => *count += 1,
+            None => self.buckets.push((label.to_owned(), 1)),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    pub fn sparkline(&self) -> String {
+        let max = self
+            .buckets
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+
+        self.buckets
+            .iter()
+            .map(|(_, count)| match count {
+                0 => ' ',
+                count => SPARKS[(count * (SPARKS.len() - 1)).div_ceil(max.max(1))],
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PoolHistograms {
+    pub resolutions: Histogram,
+    pub aspect_ratios: Histogram,
+    pub hues: Histogram,
+    pub photographers: Histogram,
+}
+
+impl PoolHistograms {
+    pub fn new<P: AsRef<Path>>(paths: &[P], history: &History, top_photographers: usize) -> Self {
+        let mut resolutions = Histogram::with_labels(RESOLUTIONS.map(|(_, label)| label));
+        let mut aspect_ratios = Histogram::with_labels(ASPECT_RATIOS.map(|(_, label)| label));
+        let mut hues = Histogram::with_labels(HUES.into_iter().chain(["gray"]));
+        let mut photographers: HashMap<String, usize> = HashMap::new();
+
+        for path in paths {
+            let path = path.as_ref();
+
+            if let Ok(Some((width, height))) = dimensions(path) {
+                resolutions.add(resolution(width, height));
+                aspect_ratios.add(aspect_ratio(width, height));
+            }
+
+            let Some(entry) = history.get(&history.photo_id(path)) else {
+                continue;
+            };
+            if let Some(hue) = entry.color.as_deref().and_then(hue) {
+                hues.add(hue);
+            }
+            *photographers
+                .entry(entry.photographer.username().to_owned())
+                .or_default() += 1;
+        }
+
+        let mut photographers: Vec<_> = photographers.into_iter().collect();
+        photographers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        photographers.truncate(top_photographers);
+
+        Self {
+            resolutions,
+            aspect_ratios,
+            hues,
+            photographers: Histogram {
+                buckets: photographers,
+            },
+        }
+    }
+}
+
+pub fn dimensions<P: AsRef<Path>>(path: P) -> io::Result<Option<(u32, u32)>> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    File::open(path)?
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)?;
+
+    Ok(png_dimensions(&header)
+        .or_else(|| jpeg_dimensions(&header))
+        .or_else(|| webp_dimensions(&header)))
+}
+
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") || data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    Some((be32(data, 16)?, be32(data, 20)?))
+}
+
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut offset = 2;
+    loop {
+        while *data.get(offset)? != 0xff {
+            offset += 1;
+        }
+        while *data.get(offset)? == 0xff {
+            offset += 1;
+        }
+
+        let marker = *data.get(offset)?;
+        offset += 1;
+        if matches!(marker, 0xd0..=0xd9 | 0x01) {
+            continue;
+        }
+
+        let length = be16(data, offset)? as usize;
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let height = be16(data, offset + 3)? as u32;
+            let width = be16(data, offset + 5)? as u32;
+
+            return Some((width, height));
+        }
+        offset += length;
+    }
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(0..4)? != b"RIFF" || data.get(8..12)? != b"WEBP" {
+        return None;
+    }
+
+    match data.get(12..16)? {
+        b"VP8 " => Some((
+            (le16(data, 26)? & 0x3fff) as u32,
+            (le16(data, 28)? & 0x3fff) as u32,
+        )),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => Some((le24(data, 24)? + 1, le24(data, 27)? + 1)),
+        _ => None,
+    }
+}
+
+fn resolution(width: u32, height: u32) -> &'static str {
+    let short = width.min(height);
+
+    RESOLUTIONS
+        .iter()
+        .rev()
+        .find(|(min, _)| short >= *min)
+        .map_or(RESOLUTIONS[0].1, |(_, label)| label)
+}
+
+fn aspect_ratio(width: u32, height: u32) -> &'static str {
+    let ratio = width as f64 / height.max(1) as f64;
+
+    ASPECT_RATIOS
+        .iter()
+        .rev()
+        .find(|(min, _)| ratio >= *min)
+        .map_or(ASPECT_RATIOS[0].1, |(_, label)| label)
+}
+
+fn hue(color: &str) -> Option<&'static str> {
+    let color = color.strip_prefix('#').unwrap_or(color);
+    if color.len() != 6 {
+        return None;
+    }
+
+    let channel = |index: usize| {
+        u8::from_str_radix(color.get(index..index + 2)?, 16)
+            .ok()
+            .map(|value| value as f64 / 255.0)
+    };
+    let (r, g, b) = (channel(0)?, channel(2)?, channel(4)?);
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let lightness = (max + min) / 2.0;
+    let saturation = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
+    if saturation < MIN_SATURATION {
+        return Some("gray");
+    }
+
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let sector = 360.0 / HUES.len() as f64;
+
+    Some(HUES[((hue + sector / 2.0) / sector) as usize % HUES.len()])
+}
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn le24(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 3)?;
+
+    Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+}
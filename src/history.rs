@@ -0,0 +1,269 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spotlight,
+    state::{self, Migration},
+    Photo, Query, User,
+};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: String,
+    pub query: Option<Query>,
+    pub downloaded_at: DateTime<Utc>,
+    pub path: PathBuf,
+    pub photographer: User,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default = "Entry::default_shown")]
+    pub shown: bool,
+    #[serde(default)]
+    pub likes: Option<u32>,
+    #[serde(default)]
+    pub removed: bool,
+    #[serde(default)]
+    pub refreshed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blur_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    pub fn new<P: AsRef<Path>>(photo: &Photo, path: P) -> Self {
+        Self {
+            id: photo.id().to_owned(),
+            query: photo.query().cloned(),
+            downloaded_at: Utc::now(),
+            path: path.as_ref().to_owned(),
+            photographer: photo.user().clone(),
+            hidden: false,
+            pinned: false,
+            sha256: None,
+            shown: false,
+            likes: Some(photo.likes()),
+            removed: false,
+            refreshed_at: None,
+            color: photo.color().map(ToOwned::to_owned),
+            blur_hash: photo.blur_hash().map(ToOwned::to_owned),
+            tags: photo.tags().map(ToOwned::to_owned).collect(),
+            expires_at: None,
+        }
+    }
+
+    pub fn imported<P: AsRef<Path>>(id: &str, path: P, photographer: User) -> Self {
+        Self {
+            id: id.to_owned(),
+            query: None,
+            downloaded_at: Utc::now(),
+            path: path.as_ref().to_owned(),
+            photographer,
+            hidden: false,
+            pinned: false,
+            sha256: None,
+            shown: false,
+            likes: None,
+            removed: false,
+            refreshed_at: None,
+            color: None,
+            blur_hash: None,
+            tags: Vec::new(),
+            expires_at: None,
+        }
+    }
+
+    pub fn is_from_unsplash(&self) -> bool {
+        !self.id.starts_with(spotlight::ID_PREFIX)
+    }
+
+    pub fn photo_url(&self) -> Option<String> {
+        (self.is_from_unsplash() && !self.removed).then(|| photo_url(&self.id))
+    }
+
+    pub fn refresh(&mut self, photo: &Photo) {
+        self.photographer = photo.user().clone();
+        self.likes = Some(photo.likes());
+        self.color = photo.color().map(ToOwned::to_owned);
+        self.blur_hash = photo.blur_hash().map(ToOwned::to_owned);
+        if photo.tags().next().is_some() {
+            self.tags = photo.tags().map(ToOwned::to_owned).collect();
+        }
+        self.removed = false;
+        self.refreshed_at = Some(Utc::now());
+    }
+
+    pub fn mark_removed(&mut self) {
+        self.removed = true;
+        self.refreshed_at = Some(Utc::now());
+    }
+
+    fn default_shown() -> bool {
+        true
+    }
+
+    pub fn with_sha256(mut self, sha256: String) -> Self {
+        self.sha256 = Some(sha256);
+        self
+    }
+
+    pub fn with_expires_at(mut self, expires_at: Option<DateTime<Utc>>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+}
+
+pub fn photo_url(id: &str) -> String {
+    format!("https://unsplash.com/photos/{}", id)
+}
+
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    entries: BTreeMap<String, Entry>,
+    revision: AtomicU64,
+}
+
+impl Clone for History {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            entries: self.entries.clone(),
+            revision: AtomicU64::new(self.revision.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl History {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let (entries, revision) = state::load_revision(&path, MIGRATIONS)?;
+
+        Ok(Self {
+            path,
+            entries,
+            revision: AtomicU64::new(revision),
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let revision = state::save_revision(
+            &self.path,
+            &self.entries,
+            MIGRATIONS,
+            self.revision.load(Ordering::SeqCst),
+        )?;
+        self.revision.store(revision, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn reload_if_changed(&mut self) -> io::Result<bool> {
+        if state::revision(&self.path)? == self.revision.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        *self = Self::open(&self.path)?;
+
+        Ok(true)
+    }
+
+    pub fn record(&mut self, entry: Entry) {
+        let (hidden, pinned) = self
+            .entries
+            .get(&entry.id)
+            .map_or((false, false), |previous| {
+                (previous.hidden, previous.pinned)
+            });
+
+        self.entries.insert(
+            entry.id.clone(),
+            Entry {
+                hidden,
+                pinned,
+                ..entry
+            },
+        );
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.entries.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Entry> {
+        self.entries.get_mut(id)
+    }
+
+    pub fn photo_id<P: AsRef<Path>>(&self, path: P) -> String {
+        let path = path.as_ref();
+
+        self.entries
+            .values()
+            .find(|entry| entry.path == path)
+            .map_or_else(
+                || {
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                },
+                |entry| entry.id.clone(),
+            )
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    pub fn is_hidden(&self, id: &str) -> bool {
+        self.entries.get(id).is_some_and(|entry| entry.hidden)
+    }
+
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.entries.get(id).is_some_and(|entry| entry.pinned)
+    }
+
+    pub fn hide(&mut self, id: &str) -> bool {
+        match self.entries.get_mut(id) {
+            Some(entry) => {
+                entry.hidden = true;
+                true
+            }
+
+            None => false,
+        }
+    }
+
+    pub fn mark_shown(&mut self, id: &str) -> bool {
+        match self.entries.get_mut(id) {
+            Some(entry) if !entry.shown => {
+                entry.shown = true;
+                true
+            }
+
+            _ => false,
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+}
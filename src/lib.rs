@@ -1,2 +1,74 @@
 pub mod unsplash;
 pub use unsplash::*;
+
+pub mod accessibility;
+pub mod attribution;
+pub mod bandwidth;
+pub mod blocklist;
+pub mod blurhash;
+pub mod broken;
+pub mod bundle;
+pub mod calendar;
+pub mod changelog;
+pub mod cleanup;
+pub mod cloud;
+pub mod commands;
+pub mod config;
+pub mod control;
+pub mod cron;
+pub mod daily;
+pub mod display;
+pub mod events;
+pub mod experimental;
+pub mod explain;
+pub mod filesystem;
+#[cfg(feature = "frame")]
+pub mod frame;
+pub mod gallery;
+pub mod histogram;
+pub mod history;
+pub mod lock;
+pub mod manifest;
+#[cfg(feature = "metadata")]
+pub mod metadata;
+#[cfg(feature = "notifications")]
+pub mod notification;
+#[cfg(feature = "parallax")]
+pub mod parallax;
+pub mod pipeline;
+#[cfg(feature = "processing")]
+pub mod processing;
+pub mod recap;
+pub mod redact;
+#[cfg(any(feature = "frame", feature = "s3", feature = "webdav"))]
+mod remote;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod scheduler;
+pub mod selection;
+pub mod snapshot;
+#[cfg(feature = "span")]
+pub mod span;
+pub mod spotlight;
+pub mod state;
+pub mod stats;
+pub mod storage;
+pub mod summary;
+pub mod taste;
+pub mod template;
+#[cfg(test)]
+mod testing;
+pub mod theme;
+#[cfg(feature = "thumbnails")]
+pub mod thumbnail;
+pub mod timing;
+pub mod wallpaper;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "webdav")]
+pub mod webdav;
+pub mod workflow;
+pub use selection::SelectionStrategy;
+pub use workflow::{save_photos, Workflow};
@@ -0,0 +1,47 @@
+use std::{
+    fs::{self, File, TryLockError},
+    io,
+    path::Path,
+};
+
+const LOCK_FILE: &str = ".backdrop.lock";
+
+#[derive(Debug)]
+pub struct FolderLock {
+    _file: File,
+}
+
+impl FolderLock {
+    pub fn try_acquire<P: AsRef<Path>>(folder: P) -> io::Result<Option<Self>> {
+        let file = open(folder.as_ref())?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err),
+        }
+    }
+
+    pub fn acquire<P: AsRef<Path>>(folder: P) -> io::Result<Self> {
+        let file = open(folder.as_ref())?;
+        file.lock()?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+pub fn is_lock_file<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .file_name()
+        .is_some_and(|name| name == LOCK_FILE)
+}
+
+fn open(folder: &Path) -> io::Result<File> {
+    fs::create_dir_all(folder)?;
+
+    File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(folder.join(LOCK_FILE))
+}
@@ -0,0 +1,62 @@
+use std::{fs, io, path::Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{pipeline::Saved, Download, Photo};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub photo: Photo,
+    pub url: String,
+    pub file_name: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub created_at: DateTime<Utc>,
+    pub download: Download,
+    pub entries: Vec<Entry>,
+}
+
+impl Manifest {
+    pub fn new(download: &Download) -> Self {
+        Self {
+            created_at: Utc::now(),
+            download: download.clone(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+
+        serde_json::from_str(&content).map_err(Into::into)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(Into::<io::Error>::into)?;
+
+        fs::write(path, content)
+    }
+
+    pub fn push(&mut self, saved: &Saved) {
+        self.entries.push(Entry {
+            photo: saved.photo.clone(),
+            url: self.download.url(&saved.photo),
+            file_name: saved
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            sha256: saved.sha256.clone(),
+        });
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
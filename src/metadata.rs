@@ -0,0 +1,39 @@
+use std::{fs, io, path::Path};
+
+use little_exif::{exif_tag::ExifTag, filetype::FileExtension, metadata::Metadata};
+
+use crate::{Format, Photo};
+
+pub fn embed_attribution<P: AsRef<Path>>(
+    photo: &Photo,
+    path: P,
+    format: &Format,
+) -> io::Result<()> {
+    let file_type = match format {
+        Format::Png => FileExtension::PNG {
+            as_zTXt_chunk: false,
+        },
+        Format::Jpeg { .. } => FileExtension::JPEG,
+        Format::Webp { .. } => FileExtension::WEBP,
+        Format::Avif { .. } => return Ok(()),
+    };
+
+    let user = photo.user();
+    let mut copyright = format!("Photo by {} on Unsplash", user.name());
+    if let Some(page_url) = photo.page_url() {
+        copyright.push_str(&format!(" ({})", page_url));
+    }
+
+    let mut metadata = Metadata::new();
+    metadata.set_tag(ExifTag::Artist(user.name().to_owned()));
+    metadata.set_tag(ExifTag::Copyright(copyright));
+    if let Some(description) = photo.description().or(photo.alt_description()) {
+        metadata.set_tag(ExifTag::ImageDescription(description.to_owned()));
+    }
+
+    let path = path.as_ref();
+    let mut data = fs::read(path)?;
+    metadata.write_to_vec(&mut data, file_type)?;
+
+    fs::write(path, data)
+}
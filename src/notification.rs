@@ -0,0 +1,85 @@
+use notify_rust::{error::Result, Notification};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribution::Attribution,
+    template::{self, Template},
+};
+
+const APP_NAME: &str = "Backdrop";
+const PLACEHOLDERS: &[&str] = &["id", "author", "username", "description", "url", "profile"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MessageTemplate(Template);
+
+impl MessageTemplate {
+    pub fn parse(template: &str) -> std::result::Result<Self, template::ParseError> {
+        Template::parse(template, PLACEHOLDERS).map(Self)
+    }
+
+    pub fn render(&self, attribution: &Attribution) -> String {
+        let message = self.0.render(|placeholder| {
+            match placeholder {
+                "id" => Some(attribution.id.as_str()),
+                "author" => Some(attribution.photographer.as_str()),
+                "username" => Some(attribution.username.as_str()),
+                "description" => attribution.description.as_deref(),
+                "url" => attribution.page_url.as_deref(),
+                "profile" => attribution.profile_url.as_deref(),
+                _ => unreachable!(),
+            }
+            .unwrap_or_default()
+            .to_owned()
+        });
+
+        message
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for MessageTemplate {
+    fn default() -> Self {
+        Self::parse("{description}\nPhoto by {author} on Unsplash").unwrap()
+    }
+}
+
+impl TryFrom<String> for MessageTemplate {
+    type Error = template::ParseError;
+
+    fn try_from(template: String) -> std::result::Result<Self, Self::Error> {
+        Self::parse(&template)
+    }
+}
+
+impl From<MessageTemplate> for String {
+    fn from(template: MessageTemplate) -> Self {
+        template.0.as_str().to_owned()
+    }
+}
+
+pub fn wallpaper_changed(
+    attribution: Option<&Attribution>,
+    template: &MessageTemplate,
+) -> Result<()> {
+    let mut notification = Notification::new();
+    notification.appname(APP_NAME).summary("New wallpaper");
+
+    if let Some(attribution) = attribution {
+        notification.body(&template.render(attribution));
+    }
+
+    notification.show().map(drop)
+}
+
+pub fn error(message: &str) -> Result<()> {
+    Notification::new()
+        .appname(APP_NAME)
+        .summary("Backdrop failed")
+        .body(message)
+        .show()
+        .map(drop)
+}
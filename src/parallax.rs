@@ -0,0 +1,137 @@
+use std::{fs, io, path::Path};
+
+use image::{imageops::FilterType, GrayImage, ImageBuffer, ImageError, Luma, Rgb, RgbImage, Rgba};
+use thiserror::Error;
+use tract_onnx::prelude::*;
+
+const INPUT_SIZE: u32 = 256;
+const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Image(#[from] ImageError),
+
+    #[error("Failed to run depth model: {0}")]
+    Model(String),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub type DepthMap = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+pub trait DepthEstimator {
+    fn estimate(&self, image: &RgbImage) -> Result<DepthMap>;
+}
+
+pub struct OnnxDepthEstimator {
+    model: Arc<TypedRunnableModel>,
+}
+
+impl OnnxDepthEstimator {
+    pub fn new<P: AsRef<Path>>(model_path: P) -> Result<Self> {
+        let model = tract_onnx::onnx()
+            .model_for_path(model_path)
+            .and_then(|model| {
+                model.with_input_fact(
+                    0,
+                    f32::fact([1, 3, INPUT_SIZE as usize, INPUT_SIZE as usize]).into(),
+                )
+            })
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|err| Error::Model(err.to_string()))?;
+
+        Ok(Self { model })
+    }
+}
+
+impl DepthEstimator for OnnxDepthEstimator {
+    fn estimate(&self, image: &RgbImage) -> Result<DepthMap> {
+        let resized = image::imageops::resize(image, INPUT_SIZE, INPUT_SIZE, FilterType::Triangle);
+
+        let input: Tensor = tract_ndarray::Array4::from_shape_fn(
+            (1, 3, INPUT_SIZE as usize, INPUT_SIZE as usize),
+            |(_, channel, y, x)| {
+                let value = resized.get_pixel(x as u32, y as u32)[channel] as f32 / 255.0;
+
+                (value - MEAN[channel]) / STD[channel]
+            },
+        )
+        .into();
+
+        let outputs = self
+            .model
+            .run(tvec!(input.into()))
+            .map_err(|err| Error::Model(err.to_string()))?;
+
+        let depth = outputs[0]
+            .to_plain_array_view::<f32>()
+            .map_err(|err| Error::Model(err.to_string()))?;
+
+        let values: Vec<f32> = depth.iter().copied().collect();
+        let (min, max) = values
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        let range = (max - min).max(f32::EPSILON);
+
+        let normalized = values
+            .into_iter()
+            .map(|value| (value - min) / range)
+            .collect();
+        let depth = DepthMap::from_raw(INPUT_SIZE, INPUT_SIZE, normalized)
+            .ok_or_else(|| Error::Model("Unexpected depth output shape".to_owned()))?;
+
+        Ok(image::imageops::resize(
+            &depth,
+            image.width(),
+            image.height(),
+            FilterType::Triangle,
+        ))
+    }
+}
+
+pub fn export_layers<E, P, Q>(estimator: &E, image_path: P, folder: Q, layers: u32) -> Result<()>
+where
+    E: DepthEstimator,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let folder = folder.as_ref();
+    fs::create_dir_all(folder)?;
+
+    let image = image::open(image_path)?.into_rgb8();
+    let depth = estimator.estimate(&image)?;
+
+    let preview = GrayImage::from_fn(depth.width(), depth.height(), |x, y| {
+        Luma([(depth.get_pixel(x, y)[0] * 255.0) as u8])
+    });
+    preview.save(folder.join("depth.png"))?;
+
+    image.save(folder.join("layer-0.png"))?;
+
+    for layer in 1..layers {
+        let threshold = layer as f32 / layers as f32;
+
+        let cutout = ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+            let Rgb([r, g, b]) = *image.get_pixel(x, y);
+            let alpha = if depth.get_pixel(x, y)[0] >= threshold {
+                255
+            } else {
+                0
+            };
+
+            Rgba([r, g, b, alpha])
+        });
+
+        cutout.save(folder.join(format!("layer-{}.png", layer)))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,443 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    fs,
+    sync::{mpsc, Semaphore},
+    task::JoinSet,
+};
+
+use crate::{
+    events::{Event, EventBus},
+    storage::Storage,
+    timing::{self, Timer},
+    BatchReport, Client, Download, Error, Fetch, Limit, Photo, Result, SlowFallback,
+    PART_EXTENSION,
+};
+
+pub type Stage<T> = mpsc::Receiver<Result<T>>;
+
+#[derive(Debug)]
+pub struct Downloaded {
+    pub photo: Photo,
+    pub temp_path: PathBuf,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub download_time: Duration,
+    pub processing_time: Duration,
+}
+
+#[derive(Debug)]
+pub struct Saved {
+    pub photo: Photo,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub download_time: Duration,
+    pub processing_time: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Slowdown {
+    slow: u32,
+    degraded: bool,
+}
+
+impl Slowdown {
+    fn observe(&mut self, fallback: &SlowFallback, elapsed: Duration) -> bool {
+        if elapsed > fallback.time_budget() {
+            self.slow += 1;
+        } else {
+            self.slow = 0;
+        }
+
+        if !self.degraded && self.slow >= fallback.after.max(1) {
+            self.degraded = true;
+
+            return true;
+        }
+
+        false
+    }
+}
+
+pub fn fetch(client: Client, fetch: Fetch, timer: Timer, capacity: usize) -> Stage<Photo> {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+
+    tokio::spawn(async move {
+        let start = Instant::now();
+        let result = client.fetch_photos(&fetch).await;
+        timer.record(timing::Stage::Fetch, start.elapsed());
+
+        match result {
+            Ok(photos) => {
+                for photo in photos {
+                    if tx.send(Ok(photo)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+
+            Err(err) => {
+                let _ = tx.send(Err(err)).await;
+            }
+        }
+    });
+
+    rx
+}
+
+pub fn filter<F>(mut input: Stage<Photo>, mut predicate: F, capacity: usize) -> Stage<Photo>
+where
+    F: FnMut(&Photo) -> bool + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+
+    tokio::spawn(async move {
+        while let Some(photo) = input.recv().await {
+            if photo.as_ref().is_ok_and(|photo| !predicate(photo)) {
+                continue;
+            }
+
+            if tx.send(photo).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+pub fn download<P: AsRef<Path>>(
+    client: Client,
+    download: Download,
+    folder: P,
+    max_concurrent: usize,
+    mut input: Stage<Photo>,
+    timer: Timer,
+    capacity: usize,
+) -> Stage<Downloaded> {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let folder = folder.as_ref().to_owned();
+
+    tokio::spawn(async move {
+        if let Err(err) = fs::create_dir_all(&folder).await {
+            let _ = tx.send(Err(err.into())).await;
+
+            return;
+        }
+
+        let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        let slowdown = Arc::new(Mutex::new(Slowdown::default()));
+
+        let mut taken = HashSet::new();
+        let mut tasks = JoinSet::new();
+        while let Some(photo) = input.recv().await {
+            let photo = match photo {
+                Ok(photo) => photo,
+
+                Err(err) => {
+                    let _ = tx.send(Err(err)).await;
+                    continue;
+                }
+            };
+
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            let client = client.clone();
+            let mut download = download.clone();
+            let slowdown = slowdown.clone();
+            let timer = timer.clone();
+            let tx = tx.clone();
+
+            if let Some(fallback) = &download.slow_fallback
+                && slowdown.lock().unwrap().degraded
+            {
+                download.resolution = fallback.resolution.clone();
+            }
+
+            let path = download.path(&folder, &photo, &mut taken);
+            let temp_path = path.with_extension(format!(
+                "{}.{}",
+                download.format.extension(),
+                PART_EXTENSION
+            ));
+
+            tasks.spawn(async move {
+                let start = Instant::now();
+                let result = match fs::create_dir_all(temp_path.parent().unwrap()).await {
+                    Ok(()) => {
+                        client
+                            .download_verified_to(&photo, &download, &temp_path)
+                            .await
+                    }
+
+                    Err(err) => Err(err.into()),
+                };
+                drop(permit);
+
+                match &result {
+                    Ok(_) => {
+                        tracing::info!(id = photo.id(), elapsed = ?start.elapsed(), "downloaded photo");
+                        timer.record(timing::Stage::Download, start.elapsed());
+
+                        if let Some(fallback) = &download.slow_fallback
+                            && slowdown.lock().unwrap().observe(fallback, start.elapsed())
+                        {
+                            tracing::warn!(
+                                budget = ?fallback.time_budget(),
+                                resolution = ?fallback.resolution,
+                                "downloads are exceeding the time budget, falling back to a lower resolution for the rest of the batch"
+                            );
+                        }
+                    }
+                    Err(err) => tracing::error!(id = photo.id(), error = %err, "download failed"),
+                }
+
+                let _ = tx
+                    .send(result.map(|sha256| Downloaded {
+                        photo,
+                        temp_path,
+                        path,
+                        sha256,
+                        download_time: start.elapsed(),
+                        processing_time: Duration::ZERO,
+                    }))
+                    .await;
+            });
+        }
+
+        tasks.join_all().await;
+    });
+
+    rx
+}
+
+pub fn process<F>(
+    mut input: Stage<Downloaded>,
+    processor: F,
+    max_workers: usize,
+    timer: Timer,
+    capacity: usize,
+) -> Stage<Downloaded>
+where
+    F: Fn(&Downloaded) -> Result<()> + Send + Sync + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let processor = Arc::new(processor);
+
+    tokio::spawn(async move {
+        let semaphore = Arc::new(Semaphore::new(max_workers.max(1)));
+
+        let mut tasks = JoinSet::new();
+        while let Some(downloaded) = input.recv().await {
+            let mut downloaded = match downloaded {
+                Ok(downloaded) => downloaded,
+
+                Err(err) => {
+                    let _ = tx.send(Err(err)).await;
+                    continue;
+                }
+            };
+
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            let processor = processor.clone();
+            let timer = timer.clone();
+            let tx = tx.clone();
+
+            tasks.spawn(async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    let start = Instant::now();
+                    if let Err(err) = processor(&downloaded) {
+                        discard(&downloaded.temp_path);
+
+                        return Err(err);
+                    }
+                    downloaded.processing_time = start.elapsed();
+                    timer.record(timing::Stage::Processing, downloaded.processing_time);
+
+                    Ok(downloaded)
+                })
+                .await
+                .unwrap();
+                drop(permit);
+
+                let _ = tx.send(result).await;
+            });
+        }
+
+        tasks.join_all().await;
+    });
+
+    rx
+}
+
+pub fn tap<T, F>(mut input: Stage<T>, events: EventBus, event: F, capacity: usize) -> Stage<T>
+where
+    T: Send + 'static,
+    F: Fn(&T) -> Event + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+
+    tokio::spawn(async move {
+        while let Some(item) = input.recv().await {
+            match &item {
+                Ok(item) => events.emit(event(item)),
+                Err(err) => events.emit(Event::Error(err.to_string())),
+            }
+
+            if tx.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+pub fn persist<S: Storage + ?Sized + 'static>(
+    mut input: Stage<Downloaded>,
+    storage: Arc<S>,
+    timer: Timer,
+    capacity: usize,
+) -> Stage<Saved> {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+
+    tokio::spawn(async move {
+        while let Some(downloaded) = input.recv().await {
+            let result = match downloaded {
+                Ok(Downloaded {
+                    photo,
+                    temp_path,
+                    path,
+                    sha256,
+                    download_time,
+                    processing_time,
+                }) => {
+                    let storage = storage.clone();
+                    let timer = timer.clone();
+                    tokio::task::spawn_blocking(move || {
+                        timer
+                            .time(timing::Stage::Write, || storage.put(&temp_path, &path))
+                            .inspect_err(|_| discard(&temp_path))
+                            .map(|()| Saved {
+                                photo,
+                                path,
+                                sha256,
+                                download_time,
+                                processing_time,
+                            })
+                    })
+                    .await
+                    .unwrap()
+                    .map_err(Into::into)
+                }
+
+                Err(err) => Err(err),
+            };
+
+            if tx.send(result).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+fn discard(temp_path: &Path) {
+    match std::fs::remove_file(temp_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => tracing::warn!(
+            path = %temp_path.display(),
+            error = %err,
+            "failed to remove the partial download"
+        ),
+    }
+}
+
+pub async fn collect<T>(mut input: Stage<T>) -> Result<Vec<T>> {
+    let mut items = Vec::new();
+    while let Some(item) = input.recv().await {
+        items.push(item?);
+    }
+
+    Ok(items)
+}
+
+pub async fn collect_within_limits<T>(mut input: Stage<T>) -> (BatchReport<T>, Option<Limit>) {
+    let mut report = BatchReport::default();
+    let mut reached = None;
+    while let Some(item) = input.recv().await {
+        match item {
+            Ok(item) => report.saved.push(item),
+            Err(Error::LimitReached(limit)) => reached = Some(limit),
+            Err(err) => report.failed.push(err),
+        }
+    }
+
+    (report, reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{
+        filesystem::RealFs,
+        testing::{self, UNREACHABLE},
+    };
+
+    fn downloaded(name: &str) -> Downloaded {
+        let temp_path = testing::temp_path(name).with_extension(PART_EXTENSION);
+        std::fs::write(&temp_path, [0; 16]).unwrap();
+
+        Downloaded {
+            photo: testing::photo(name, UNREACHABLE),
+            path: temp_path.with_file_name("missing").join(name),
+            temp_path,
+            sha256: String::new(),
+            download_time: Duration::ZERO,
+            processing_time: Duration::ZERO,
+        }
+    }
+
+    fn stage<T>(item: T) -> Stage<T> {
+        let (tx, rx) = mpsc::channel(1);
+        tx.try_send(Ok(item)).unwrap();
+
+        rx
+    }
+
+    #[tokio::test]
+    async fn failed_processing_removes_the_part_file() {
+        let downloaded = downloaded("process");
+        let temp_path = downloaded.temp_path.clone();
+
+        let processed = process(
+            stage(downloaded),
+            |_: &Downloaded| Err(io::Error::other("processing failed").into()),
+            1,
+            Timer::default(),
+            1,
+        );
+
+        assert!(collect(processed).await.is_err());
+        assert!(!temp_path.exists());
+    }
+
+    #[tokio::test]
+    async fn failed_writes_remove_the_part_file() {
+        let downloaded = downloaded("persist");
+        let temp_path = downloaded.temp_path.clone();
+
+        let saved = persist(stage(downloaded), Arc::new(RealFs), Timer::default(), 1);
+
+        assert!(collect(saved).await.is_err());
+        assert!(!temp_path.exists());
+    }
+}
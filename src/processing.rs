@@ -0,0 +1,288 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use ab_glyph::{point, Font, FontVec, InvalidFont, PxScale, ScaleFont};
+use image::{
+    codecs::{
+        png::{CompressionType, FilterType, PngEncoder},
+        webp::WebPEncoder,
+    },
+    DynamicImage, ImageError, ImageReader, Rgb, RgbImage,
+};
+use jpeg_encoder::{ColorType, EncodingError, SamplingFactor};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{display, span::crop_to_aspect_ratio, Format, Photo};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Image(#[from] ImageError),
+
+    #[error("{0}")]
+    Font(#[from] InvalidFont),
+
+    #[error("{0}")]
+    Jpeg(#[from] EncodingError),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Processing {
+    #[serde(default)]
+    pub crop_to_display: bool,
+    #[serde(default)]
+    pub blur: Option<f32>,
+    #[serde(default)]
+    pub darken: Option<u8>,
+    #[serde(default)]
+    pub grayscale: bool,
+    #[serde(default)]
+    pub overlay: Option<Overlay>,
+    #[serde(default)]
+    pub encoding: Encoding,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Encoding {
+    #[serde(default)]
+    pub png: PngEncoding,
+    #[serde(default)]
+    pub jpeg: JpegEncoding,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PngEncoding {
+    #[serde(default)]
+    pub compression_level: Option<u8>,
+}
+
+impl PngEncoding {
+    fn compression(&self) -> CompressionType {
+        match self.compression_level {
+            None => CompressionType::Fast,
+            Some(0) => CompressionType::Uncompressed,
+            Some(level) => CompressionType::Level(level.min(9)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JpegEncoding {
+    #[serde(default)]
+    pub subsampling: ChromaSubsampling,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ChromaSubsampling {
+    #[serde(rename = "4:4:4")]
+    Yuv444,
+    #[serde(rename = "4:2:2")]
+    Yuv422,
+    #[default]
+    #[serde(rename = "4:2:0")]
+    Yuv420,
+}
+
+impl From<ChromaSubsampling> for SamplingFactor {
+    fn from(subsampling: ChromaSubsampling) -> Self {
+        match subsampling {
+            ChromaSubsampling::Yuv444 => SamplingFactor::R_4_4_4,
+            ChromaSubsampling::Yuv422 => SamplingFactor::R_4_2_2,
+            ChromaSubsampling::Yuv420 => SamplingFactor::R_4_2_0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Overlay {
+    #[serde(default = "Overlay::default_font")]
+    pub font: PathBuf,
+    #[serde(default = "Overlay::default_font_size")]
+    pub font_size: f32,
+    #[serde(default)]
+    pub corner: Corner,
+    #[serde(default = "Overlay::default_opacity")]
+    pub opacity: f32,
+}
+
+impl Overlay {
+    fn default_font() -> PathBuf {
+        if cfg!(windows) {
+            PathBuf::from(r"C:\Windows\Fonts\segoeui.ttf")
+        } else {
+            PathBuf::from("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf")
+        }
+    }
+
+    fn default_font_size() -> f32 {
+        24.0
+    }
+
+    fn default_opacity() -> f32 {
+        0.8
+    }
+
+    fn draw(&self, image: &mut RgbImage, text: &str) -> Result<()> {
+        let font = FontVec::try_from_vec(fs::read(&self.font)?)?;
+        let font = font.as_scaled(PxScale::from(self.font_size));
+
+        let mut glyphs = Vec::new();
+        let mut caret = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+
+            glyphs.push(id.with_scale_and_position(font.scale(), point(caret, font.ascent())));
+            caret += font.h_advance(id);
+            previous = Some(id);
+        }
+
+        let margin = self.font_size.round() as i64;
+        let (width, height) = (caret.ceil() as i64, font.height().ceil() as i64);
+        let (image_width, image_height) = (image.width() as i64, image.height() as i64);
+        let (x, y) = match self.corner {
+            Corner::TopLeft => (margin, margin),
+            Corner::TopRight => (image_width - width - margin, margin),
+            Corner::BottomLeft => (margin, image_height - height - margin),
+            Corner::BottomRight => (image_width - width - margin, image_height - height - margin),
+        };
+
+        let shadow = (self.font_size / 16.0).ceil().max(1.0) as i64;
+        for (offset, color) in [(shadow, Rgb([0, 0, 0])), (0, Rgb([255, 255, 255]))] {
+            for glyph in &glyphs {
+                let Some(outlined) = font.outline_glyph(glyph.clone()) else {
+                    continue;
+                };
+
+                let bounds = outlined.px_bounds();
+                outlined.draw(|glyph_x, glyph_y, coverage| {
+                    let px = x + offset + bounds.min.x as i64 + glyph_x as i64;
+                    let py = y + offset + bounds.min.y as i64 + glyph_y as i64;
+                    if !(0..image_width).contains(&px) || !(0..image_height).contains(&py) {
+                        return;
+                    }
+
+                    let alpha = (coverage * self.opacity).clamp(0.0, 1.0);
+                    let pixel = image.get_pixel_mut(px as u32, py as u32);
+                    for (channel, value) in pixel.0.iter_mut().zip(color.0) {
+                        *channel =
+                            (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Processing {
+    pub fn is_empty(&self) -> bool {
+        !self.crop_to_display
+            && self.blur.is_none()
+            && self.darken.is_none()
+            && !self.grayscale
+            && self.overlay.is_none()
+    }
+
+    pub fn apply<P: AsRef<Path>>(&self, photo: &Photo, path: P, format: &Format) -> Result<()> {
+        if self.is_empty() || matches!(format, Format::Avif { .. }) {
+            return Ok(());
+        }
+
+        let path = path.as_ref();
+        let mut image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+
+        if self.crop_to_display
+            && let Some((width, height)) = display::primary_resolution()
+        {
+            image = crop_to_aspect_ratio(&image, width, height);
+        }
+
+        if let Some(sigma) = self.blur {
+            image = image.blur(sigma);
+        }
+
+        if let Some(percent) = self.darken {
+            image = darken(image, percent);
+        }
+
+        if self.grayscale {
+            image = DynamicImage::ImageRgb8(image.grayscale().to_rgb8());
+        }
+
+        if let Some(overlay) = &self.overlay {
+            let mut rgb = image.to_rgb8();
+            overlay.draw(
+                &mut rgb,
+                &format!("Photo by {} on Unsplash", photo.user().name()),
+            )?;
+
+            image = DynamicImage::ImageRgb8(rgb);
+        }
+
+        save(&image, path, format, &self.encoding)
+    }
+}
+
+fn darken(image: DynamicImage, percent: u8) -> DynamicImage {
+    let factor = 1.0 - percent.min(100) as f32 / 100.0;
+
+    let mut image = image.to_rgb8();
+    for pixel in image.pixels_mut() {
+        for channel in pixel.0.iter_mut() {
+            *channel = (*channel as f32 * factor).round() as u8;
+        }
+    }
+
+    DynamicImage::ImageRgb8(image)
+}
+
+fn save(image: &DynamicImage, path: &Path, format: &Format, encoding: &Encoding) -> Result<()> {
+    let image = DynamicImage::ImageRgb8(image.to_rgb8());
+    let writer = BufWriter::new(File::create(path)?);
+
+    match *format {
+        Format::Png => image.write_with_encoder(PngEncoder::new_with_quality(
+            writer,
+            encoding.png.compression(),
+            FilterType::Adaptive,
+        ))?,
+        Format::Jpeg { quality } => {
+            let mut encoder = jpeg_encoder::Encoder::new(writer, quality);
+            encoder.set_sampling_factor(encoding.jpeg.subsampling.into());
+            encoder.encode(
+                image.as_bytes(),
+                image.width() as u16,
+                image.height() as u16,
+                ColorType::Rgb,
+            )?;
+        }
+        Format::Webp { .. } => image.write_with_encoder(WebPEncoder::new_lossless(writer))?,
+        Format::Avif { .. } => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::{cmp::Reverse, collections::HashMap, fmt::Write};
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::{
+    history::{self, Entry, History},
+    stats::Stats,
+};
+
+const TOP_PHOTOS: usize = 12;
+const TOP_PHOTOGRAPHERS: usize = 10;
+
+pub fn year_in_review(history: &History, stats: &Stats, year: i32) -> String {
+    let changes: Vec<_> = stats
+        .counters()
+        .changes
+        .iter()
+        .filter(|change| change.at.year() == year)
+        .collect();
+
+    let mut photos: HashMap<&str, usize> = HashMap::new();
+    let mut photographers: HashMap<&str, usize> = HashMap::new();
+    let mut days: HashMap<NaiveDate, usize> = HashMap::new();
+    for change in &changes {
+        *photos.entry(&change.id).or_default() += 1;
+        *days.entry(change.at.date_naive()).or_default() += 1;
+
+        if let Some(entry) = history.get(&change.id) {
+            *photographers.entry(entry.photographer.name()).or_default() += 1;
+        }
+    }
+
+    let mut photos: Vec<_> = photos.into_iter().collect();
+    photos.sort_by_key(|&(id, count)| (Reverse(count), id));
+
+    let mut photographers: Vec<_> = photographers.into_iter().collect();
+    photographers.sort_by_key(|&(name, count)| (Reverse(count), name));
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>Backdrop {} in review</title>", year).unwrap();
+    writeln!(html, "<style>{}</style>", STYLE).unwrap();
+    writeln!(html, "</head><body>").unwrap();
+    writeln!(html, "<h1>{} in review</h1>", year).unwrap();
+    writeln!(
+        html,
+        "<p>{} wallpaper changes, {} different photos.</p>",
+        changes.len(),
+        photos.len()
+    )
+    .unwrap();
+
+    writeln!(html, "<h2>Most shown</h2><div class=\"photos\">").unwrap();
+    for (id, count) in photos.iter().take(TOP_PHOTOS) {
+        let entry = history.get(id);
+
+        writeln!(html, "<figure>").unwrap();
+        if let Some(entry) = entry.filter(|entry| entry.path.exists()) {
+            writeln!(
+                html,
+                "<img src=\"file:///{}\" alt=\"{}\">",
+                escape(&entry.path.to_string_lossy().replace('\\', "/")),
+                escape(id)
+            )
+            .unwrap();
+        }
+
+        let url = entry.map_or_else(|| Some(history::photo_url(id)), Entry::photo_url);
+        let credit = entry.map_or(String::new(), |entry| {
+            format!(" by {}", escape(entry.photographer.name()))
+        });
+        writeln!(
+            html,
+            "<figcaption>{}{}, shown {}&times;</figcaption>",
+            photo_link(id, url.as_deref()),
+            credit,
+            count
+        )
+        .unwrap();
+        writeln!(html, "</figure>").unwrap();
+    }
+    writeln!(html, "</div>").unwrap();
+
+    writeln!(html, "<h2>Top photographers</h2><ol>").unwrap();
+    for (name, count) in photographers.iter().take(TOP_PHOTOGRAPHERS) {
+        writeln!(html, "<li>{} ({})</li>", escape(name), count).unwrap();
+    }
+    writeln!(html, "</ol>").unwrap();
+
+    writeln!(html, "<h2>Changes</h2>").unwrap();
+    write_heatmap(&mut html, year, &days);
+
+    writeln!(html, "</body></html>").unwrap();
+
+    html
+}
+
+fn write_heatmap(html: &mut String, year: i32, days: &HashMap<NaiveDate, usize>) {
+    let max = days.values().copied().max().unwrap_or(0).max(1);
+
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let start = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+
+    writeln!(html, "<table class=\"heatmap\">").unwrap();
+    for weekday in 0..7 {
+        writeln!(html, "<tr>").unwrap();
+
+        let mut day = start + Duration::days(weekday);
+        while day.year() <= year {
+            if day.year() < year {
+                writeln!(html, "<td></td>").unwrap();
+            } else {
+                let count = days.get(&day).copied().unwrap_or(0);
+                let level = (count * 4).div_ceil(max);
+
+                writeln!(
+                    html,
+                    "<td class=\"level-{}\" title=\"{}: {}\"></td>",
+                    level, day, count
+                )
+                .unwrap();
+            }
+
+            day += Duration::days(7);
+        }
+
+        writeln!(html, "</tr>").unwrap();
+    }
+    writeln!(html, "</table>").unwrap();
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub(crate) fn photo_link(id: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("<a href=\"{}\">{}</a>", escape(url), escape(id)),
+        None => escape(id),
+    }
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 960px; margin: 2em auto; }
+.photos { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 1em; }
+.photos img { width: 100%; height: 140px; object-fit: cover; border-radius: 4px; }
+figure { margin: 0; }
+.heatmap { border-spacing: 3px; }
+.heatmap td { width: 11px; height: 11px; border-radius: 2px; padding: 0; }
+.level-0 { background: #ebedf0; }
+.level-1 { background: #9be9a8; }
+.level-2 { background: #40c463; }
+.level-3 { background: #30a14e; }
+.level-4 { background: #216e39; }";
@@ -0,0 +1,71 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Redacted<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "hunter2";
+
+    #[derive(Debug, Deserialize)]
+    struct Settings {
+        token: Option<Redacted<String>>,
+    }
+
+    #[test]
+    fn formatting_hides_the_value() {
+        let secret = Redacted::new(SECRET.to_owned());
+
+        assert!(!format!("{:?}", secret).contains(SECRET));
+        assert!(!format!("{:#?}", secret).contains(SECRET));
+        assert!(!format!("{}", secret).contains(SECRET));
+        assert_eq!(secret.expose(), SECRET);
+    }
+
+    #[test]
+    fn formatting_a_containing_struct_hides_the_value() {
+        let settings: Settings = serde_json::from_str(r#"{ "token": "hunter2" }"#).unwrap();
+
+        assert!(!format!("{:?}", settings).contains(SECRET));
+        assert!(!format!("{:#?}", settings).contains(SECRET));
+        assert_eq!(settings.token.unwrap().expose(), SECRET);
+    }
+}
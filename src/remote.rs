@@ -0,0 +1,200 @@
+#[cfg(any(feature = "s3", feature = "webdav"))]
+use std::{env, io, sync::OnceLock};
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+use reqwest::blocking::Client;
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+use crate::redact::Redacted;
+
+const UNRESERVED: &[u8] = b"-_.~";
+
+pub fn percent_encode(text: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || UNRESERVED.contains(&byte)
+            || (keep_slashes && byte == b'/')
+        {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+#[cfg(feature = "webdav")]
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+pub fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+
+        let tag = &rest[..end];
+        let tag_name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if tag_name.rsplit(':').next() != Some(name) {
+            continue;
+        }
+
+        rest = &rest[end + 1..];
+        if tag.ends_with('/') {
+            found.push("");
+            continue;
+        }
+
+        let close = format!("</{}>", tag_name);
+        let Some(close_at) = rest.find(&close) else {
+            break;
+        };
+
+        found.push(&rest[..close_at]);
+        rest = &rest[close_at + close.len()..];
+    }
+
+    found
+}
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+pub fn element(xml: &str, name: &str) -> Option<String> {
+    elements(xml, name).first().map(|text| {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    })
+}
+
+/// A blocking HTTP client that is built by its first request. The blocking client can't be
+/// built on a runtime thread, while storage requests always run on a blocking one.
+#[cfg(any(feature = "s3", feature = "webdav"))]
+#[derive(Debug, Default)]
+pub struct LazyClient(OnceLock<Client>);
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+impl LazyClient {
+    pub fn get(&self) -> &Client {
+        self.0.get_or_init(Client::new)
+    }
+}
+
+/// A secret that is read from the credential store or an environment variable.
+#[cfg(any(feature = "s3", feature = "webdav"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Secret {
+    variable: &'static str,
+    #[cfg_attr(not(feature = "keyring"), allow(dead_code))]
+    keyring_user: &'static str,
+}
+
+#[cfg(any(feature = "s3", feature = "webdav"))]
+impl Secret {
+    #[cfg(feature = "keyring")]
+    const KEYRING_SERVICE: &str = "backdrop";
+
+    pub const fn new(variable: &'static str, keyring_user: &'static str) -> Self {
+        Self {
+            variable,
+            keyring_user,
+        }
+    }
+
+    pub fn lookup(&self) -> io::Result<Redacted<String>> {
+        #[cfg(feature = "keyring")]
+        if let Ok(secret) = self
+            .keyring_entry()
+            .and_then(|entry| entry.get_password().map_err(io::Error::other))
+        {
+            return Ok(Redacted::new(secret));
+        }
+
+        env::var(self.variable).map(Redacted::new).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not set", self.variable),
+            )
+        })
+    }
+
+    #[cfg(feature = "keyring")]
+    pub fn store(&self, secret: &Redacted<String>) -> io::Result<()> {
+        self.keyring_entry()?
+            .set_password(secret.expose())
+            .map_err(io::Error::other)
+    }
+
+    #[cfg(feature = "keyring")]
+    fn keyring_entry(&self) -> io::Result<keyring::Entry> {
+        keyring::Entry::new(Self::KEYRING_SERVICE, self.keyring_user).map_err(io::Error::other)
+    }
+}
+
+#[cfg(all(test, any(feature = "s3", feature = "webdav")))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::UNREACHABLE;
+
+    #[tokio::test]
+    async fn lazy_client_is_built_on_a_blocking_thread() {
+        let client = Arc::new(LazyClient::default());
+
+        let sent = tokio::task::spawn_blocking(move || client.get().get(UNREACHABLE).send())
+            .await
+            .unwrap();
+
+        assert!(sent.is_err());
+    }
+
+    #[test]
+    fn secret_falls_back_to_the_environment() {
+        const VARIABLE: &str = "BACKDROP_TEST_REMOTE_SECRET";
+        let secret = Secret::new(VARIABLE, "backdrop-test");
+
+        assert_eq!(secret.lookup().unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        unsafe { env::set_var(VARIABLE, "hunter2") };
+        let found = secret.lookup();
+        unsafe { env::remove_var(VARIABLE) };
+
+        assert_eq!(found.unwrap().expose(), "hunter2");
+    }
+}
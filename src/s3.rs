@@ -0,0 +1,293 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    filesystem::{File, Filesystem},
+    redact::Redacted,
+    remote::{element, elements, percent_encode, LazyClient, Secret},
+    storage::Storage,
+};
+
+const SECRET_ACCESS_KEY: Secret = Secret::new("S3_SECRET_ACCESS_KEY", "s3");
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: Redacted<String>,
+}
+
+impl Credentials {
+    pub fn new(access_key_id: &str, secret_access_key: Redacted<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.to_owned(),
+            secret_access_key,
+        }
+    }
+
+    pub fn lookup_secret() -> io::Result<Redacted<String>> {
+        SECRET_ACCESS_KEY.lookup()
+    }
+
+    #[cfg(feature = "keyring")]
+    pub fn store_secret(secret_access_key: &Redacted<String>) -> io::Result<()> {
+        SECRET_ACCESS_KEY.store(secret_access_key)
+    }
+}
+
+#[derive(Debug)]
+pub struct S3Storage {
+    http: LazyClient,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    prefix: String,
+    root: PathBuf,
+    credentials: Credentials,
+}
+
+impl S3Storage {
+    pub fn new<P: AsRef<Path>>(
+        endpoint: Url,
+        bucket: &str,
+        region: &str,
+        credentials: Credentials,
+        root: P,
+    ) -> Self {
+        Self {
+            http: LazyClient::default(),
+            endpoint,
+            bucket: bucket.to_owned(),
+            region: region.to_owned(),
+            prefix: String::new(),
+            root: root.as_ref().to_owned(),
+            credentials,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_matches('/').to_owned();
+        if !self.prefix.is_empty() {
+            self.prefix.push('/');
+        }
+
+        self
+    }
+
+    fn key(&self, path: &Path) -> io::Result<String> {
+        let relative = path.strip_prefix(&self.root).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not inside the storage folder", path.display()),
+            )
+        })?;
+
+        let mut key = self.prefix.clone();
+        for component in relative.components() {
+            let Component::Normal(component) = component else {
+                return Err(io::ErrorKind::InvalidInput.into());
+            };
+
+            if !key.is_empty() && !key.ends_with('/') {
+                key.push('/');
+            }
+            key.push_str(&component.to_string_lossy());
+        }
+
+        Ok(key)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let relative = key.strip_prefix(&self.prefix).unwrap_or(key);
+
+        relative
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .fold(self.root.clone(), |path, segment| path.join(segment))
+    }
+
+    fn list_objects(&self, folder: &Path) -> io::Result<(Vec<File>, Vec<PathBuf>)> {
+        let mut prefix = self.key(folder)?;
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        let mut files = Vec::new();
+        let mut folders = Vec::new();
+        let mut continuation = None;
+        loop {
+            let mut query = vec![
+                ("delimiter", "/".to_owned()),
+                ("list-type", "2".to_owned()),
+                ("prefix", prefix.clone()),
+            ];
+            if let Some(token) = continuation.take() {
+                query.push(("continuation-token", token));
+            }
+
+            let body = self.send(Method::GET, "", &query, Vec::new())?;
+            let body = String::from_utf8_lossy(&body);
+
+            for object in elements(&body, "Contents") {
+                let Some(key) = element(object, "Key") else {
+                    continue;
+                };
+
+                files.push(File {
+                    path: self.path(&key),
+                    size: element(object, "Size")
+                        .and_then(|size| size.parse().ok())
+                        .unwrap_or(0),
+                    modified: element(object, "LastModified")
+                        .and_then(|modified| DateTime::parse_from_rfc3339(&modified).ok())
+                        .map(SystemTime::from),
+                });
+            }
+
+            for common_prefix in elements(&body, "CommonPrefixes") {
+                if let Some(key) = element(common_prefix, "Prefix") {
+                    folders.push(self.path(&key));
+                }
+            }
+
+            match element(&body, "NextContinuationToken") {
+                Some(token) if element(&body, "IsTruncated").as_deref() == Some("true") => {
+                    continuation = Some(token)
+                }
+                _ => return Ok((files, folders)),
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> io::Result<Vec<u8>> {
+        let now = Utc::now();
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let mut path = self.endpoint.path().trim_end_matches('/').to_owned();
+        path.push('/');
+        path.push_str(&percent_encode(&self.bucket, false));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&percent_encode(key, true));
+        }
+
+        let mut query: Vec<_> = query
+            .iter()
+            .map(|(name, value)| (percent_encode(name, false), percent_encode(value, false)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = match (self.endpoint.host_str(), self.endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => return Err(io::ErrorKind::InvalidInput.into()),
+        };
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n\
+             host;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, query, host, payload_hash, timestamp, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.credentials.secret_access_key.expose());
+        let signing_key = [date.as_str(), &self.region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(secret.into_bytes(), |key, part| hmac(&key, part.as_bytes()));
+        let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query((!query.is_empty()).then_some(query.as_str()));
+
+        let request = self
+            .http
+            .get()
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", timestamp)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, \
+                     SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.credentials.access_key_id, scope, signature
+                ),
+            )
+            .body(body);
+
+        let response = request.send().map_err(io::Error::other)?;
+        let status = response.status();
+        let body = response.bytes().map_err(io::Error::other)?;
+
+        match status {
+            status if status.is_success() => Ok(body.to_vec()),
+            StatusCode::NOT_FOUND => Err(io::ErrorKind::NotFound.into()),
+            status => Err(io::Error::other(format!(
+                "S3 request failed with {}: {}",
+                status,
+                element(&String::from_utf8_lossy(&body), "Message").unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+impl Filesystem for S3Storage {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>> {
+        self.list_objects(folder).map(|(files, _)| files)
+    }
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        self.list_objects(folder).map(|(_, folders)| folders)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.send(Method::DELETE, &self.key(path)?, &[], Vec::new())
+            .map(drop)
+    }
+}
+
+impl Storage for S3Storage {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()> {
+        self.send(Method::PUT, &self.key(path)?, &[], fs::read(source)?)?;
+
+        fs::rename(source, path)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
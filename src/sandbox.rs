@@ -0,0 +1,90 @@
+use std::{io, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Enforced,
+    PartiallyEnforced,
+    Unsupported,
+}
+
+pub fn restrict(writable: &[PathBuf]) -> io::Result<Status> {
+    restrict_native(writable)
+}
+
+#[cfg(target_os = "linux")]
+fn restrict_native(writable: &[PathBuf]) -> io::Result<Status> {
+    use std::env;
+
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+        RulesetStatus, ABI,
+    };
+
+    let abi = ABI::V2;
+    let runtime = [
+        Some(PathBuf::from("/dev")),
+        Some(env::temp_dir()),
+        dirs::runtime_dir(),
+        dirs::home_dir().map(|home| home.join(".fehbg")),
+    ];
+    let writable = writable
+        .iter()
+        .cloned()
+        .chain(runtime.into_iter().flatten());
+
+    let status = Ruleset::default()
+        .handle_access(AccessFs::from_all(abi))
+        .and_then(|ruleset| ruleset.create())
+        .and_then(|ruleset| ruleset.add_rules(path_beneath_rules(["/"], AccessFs::from_read(abi))))
+        .and_then(|ruleset| {
+            ruleset.add_rules(path_beneath_rules(writable, AccessFs::from_all(abi)))
+        })
+        .and_then(|ruleset| ruleset.restrict_self())
+        .map_err(io::Error::other)?;
+
+    Ok(match status.ruleset {
+        RulesetStatus::FullyEnforced => Status::Enforced,
+        RulesetStatus::PartiallyEnforced => Status::PartiallyEnforced,
+        RulesetStatus::NotEnforced => Status::Unsupported,
+    })
+}
+
+#[cfg(windows)]
+fn restrict_native(_: &[PathBuf]) -> io::Result<Status> {
+    use std::{ffi::c_void, mem};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::System::{
+            JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+                SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+                JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION,
+            },
+            Threading::GetCurrentProcess,
+        },
+    };
+
+    let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    info.BasicLimitInformation.LimitFlags =
+        JOB_OBJECT_LIMIT_ACTIVE_PROCESS | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+    info.BasicLimitInformation.ActiveProcessLimit = 1;
+
+    unsafe {
+        let job = CreateJobObjectW(None, PCWSTR::null())?;
+        SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const c_void,
+            mem::size_of_val(&info) as u32,
+        )?;
+        AssignProcessToJobObject(job, GetCurrentProcess())?;
+    }
+
+    Ok(Status::PartiallyEnforced)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn restrict_native(_: &[PathBuf]) -> io::Result<Status> {
+    Ok(Status::Unsupported)
+}
@@ -0,0 +1,221 @@
+use std::{io, path::Path, process::Command, time::Duration};
+
+#[cfg(not(windows))]
+use std::{fs, path::PathBuf};
+
+#[cfg(not(target_os = "macos"))]
+const NAME: &str = "backdrop";
+
+pub fn install<P: AsRef<Path>>(executable: P, interval: Duration) -> io::Result<()> {
+    if interval < Duration::from_secs(60) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The interval must be at least a minute",
+        ));
+    }
+
+    install_native(executable.as_ref(), interval)
+}
+
+pub fn uninstall() -> io::Result<()> {
+    uninstall_native()
+}
+
+pub fn next_run() -> io::Result<Option<String>> {
+    next_run_native()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn output(command: &mut Command) -> io::Result<Option<String>> {
+    let output = command.output()?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+fn run(command: &mut Command) -> io::Result<()> {
+    let status = command.status()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            command.get_program().to_string_lossy(),
+            status
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn install_native(executable: &Path, interval: Duration) -> io::Result<()> {
+    let minutes = interval.as_secs() / 60;
+    let (schedule, modifier) = if minutes.is_multiple_of(24 * 60) {
+        ("DAILY", minutes / (24 * 60))
+    } else if minutes.is_multiple_of(60) && minutes < 24 * 60 {
+        ("HOURLY", minutes / 60)
+    } else {
+        ("MINUTE", minutes)
+    };
+
+    run(Command::new("schtasks")
+        .args(["/Create", "/F", "/TN", NAME, "/SC", schedule, "/MO"])
+        .arg(modifier.to_string())
+        .arg("/TR")
+        .arg(format!("\"{}\" run", executable.display())))
+}
+
+#[cfg(windows)]
+fn uninstall_native() -> io::Result<()> {
+    run(Command::new("schtasks").args(["/Delete", "/F", "/TN", NAME]))
+}
+
+#[cfg(windows)]
+fn next_run_native() -> io::Result<Option<String>> {
+    let Some(output) =
+        output(Command::new("schtasks").args(["/Query", "/TN", NAME, "/FO", "LIST"]))?
+    else {
+        return Ok(None);
+    };
+
+    Ok(output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "Next Run Time").then(|| value.trim().to_owned())
+    }))
+}
+
+#[cfg(target_os = "macos")]
+fn install_native(executable: &Path, interval: Duration) -> io::Result<()> {
+    let path = launchd_path()?;
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>run</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        LAUNCHD_LABEL,
+        executable.display(),
+        interval.as_secs()
+    );
+
+    if path.exists() {
+        let _ = run(Command::new("launchctl").arg("unload").arg(&path));
+    }
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, plist)?;
+
+    run(Command::new("launchctl").args(["load", "-w"]).arg(&path))
+}
+
+#[cfg(target_os = "macos")]
+fn uninstall_native() -> io::Result<()> {
+    let path = launchd_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    run(Command::new("launchctl").args(["unload", "-w"]).arg(&path))?;
+    fs::remove_file(path)
+}
+
+#[cfg(target_os = "macos")]
+fn next_run_native() -> io::Result<Option<String>> {
+    Ok(None)
+}
+
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "io.github.ohhboi427.backdrop";
+
+#[cfg(target_os = "macos")]
+fn launchd_path() -> io::Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| io::Error::other("The home folder could not be found"))?
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn install_native(executable: &Path, interval: Duration) -> io::Result<()> {
+    let folder = systemd_folder()?;
+    fs::create_dir_all(&folder)?;
+
+    let service = format!(
+        "[Unit]\nDescription=Fetch a new wallpaper from Unsplash\n\n\
+         [Service]\nType=oneshot\nExecStart=\"{}\" run\n",
+        executable.display()
+    );
+    let timer = format!(
+        "[Unit]\nDescription=Run Backdrop every {} seconds\n\n\
+         [Timer]\nOnBootSec=1min\nOnUnitActiveSec={}s\n\n\
+         [Install]\nWantedBy=timers.target\n",
+        interval.as_secs(),
+        interval.as_secs()
+    );
+
+    fs::write(folder.join(format!("{}.service", NAME)), service)?;
+    fs::write(folder.join(format!("{}.timer", NAME)), timer)?;
+
+    run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+    run(Command::new("systemctl")
+        .args(["--user", "enable", "--now"])
+        .arg(format!("{}.timer", NAME)))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn uninstall_native() -> io::Result<()> {
+    let folder = systemd_folder()?;
+    let timer = folder.join(format!("{}.timer", NAME));
+    if !timer.exists() {
+        return Ok(());
+    }
+
+    run(Command::new("systemctl")
+        .args(["--user", "disable", "--now"])
+        .arg(format!("{}.timer", NAME)))?;
+
+    fs::remove_file(timer)?;
+    fs::remove_file(folder.join(format!("{}.service", NAME)))?;
+
+    run(Command::new("systemctl").args(["--user", "daemon-reload"]))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn next_run_native() -> io::Result<Option<String>> {
+    let output = output(
+        Command::new("systemctl")
+            .args([
+                "--user",
+                "show",
+                "--property=NextElapseUSecRealtime",
+                "--value",
+            ])
+            .arg(format!("{}.timer", NAME)),
+    )?;
+
+    Ok(output
+        .map(|output| output.trim().to_owned())
+        .filter(|next| !next.is_empty() && next != "n/a"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn systemd_folder() -> io::Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| io::Error::other("The config folder could not be found"))?
+        .join("systemd/user"))
+}
@@ -0,0 +1,173 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasher, Hasher, RandomState},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{history::History, stats::Stats};
+
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub id: String,
+    pub path: PathBuf,
+    pub downloaded_at: Option<DateTime<Utc>>,
+    pub likes: Option<u32>,
+    pub times_shown: usize,
+    pub last_shown: Option<DateTime<Utc>>,
+}
+
+pub trait SelectionStrategy {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    #[default]
+    Random,
+    RandomNoRepeat,
+    LeastShown,
+    Newest,
+    RatingWeighted,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Selection::Random => "random",
+            Selection::RandomNoRepeat => "random_no_repeat",
+            Selection::LeastShown => "least_shown",
+            Selection::Newest => "newest",
+            Selection::RatingWeighted => "rating_weighted",
+        })
+    }
+}
+
+impl Selection {
+    pub fn strategy(self) -> Box<dyn SelectionStrategy + Send + Sync> {
+        match self {
+            Selection::Random => Box::new(Random),
+            Selection::RandomNoRepeat => Box::new(RandomNoRepeat),
+            Selection::LeastShown => Box::new(LeastShown),
+            Selection::Newest => Box::new(Newest),
+            Selection::RatingWeighted => Box::new(RatingWeighted),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+impl SelectionStrategy for Random {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice> {
+        pick(choices)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomNoRepeat;
+
+impl SelectionStrategy for RandomNoRepeat {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice> {
+        let fewest = choices.iter().map(|choice| choice.times_shown).min()?;
+        let unseen: Vec<_> = choices
+            .iter()
+            .filter(|choice| choice.times_shown == fewest)
+            .collect();
+
+        pick(&unseen).copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeastShown;
+
+impl SelectionStrategy for LeastShown {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice> {
+        choices
+            .iter()
+            .min_by_key(|choice| (choice.times_shown, choice.last_shown))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Newest;
+
+impl SelectionStrategy for Newest {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice> {
+        choices
+            .iter()
+            .min_by_key(|choice| (choice.times_shown, Reverse(choice.downloaded_at)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RatingWeighted;
+
+impl SelectionStrategy for RatingWeighted {
+    fn select<'a>(&self, choices: &'a [Choice]) -> Option<&'a Choice> {
+        let weight = |choice: &Choice| choice.likes.unwrap_or(0) as u64 + 1;
+
+        let total: u64 = choices.iter().map(weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut target = random() % total;
+        for choice in choices {
+            if target < weight(choice) {
+                return Some(choice);
+            }
+            target -= weight(choice);
+        }
+
+        None
+    }
+}
+
+pub fn choices<P: AsRef<Path>>(paths: &[P], history: &History, stats: &Stats) -> Vec<Choice> {
+    let mut shown: HashMap<&str, (usize, DateTime<Utc>)> = HashMap::new();
+    for change in &stats.counters().changes {
+        let (count, last) = shown.entry(&change.id).or_insert((0, change.at));
+        *count += 1;
+        *last = (*last).max(change.at);
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let id = history.photo_id(path);
+            let entry = history.get(&id);
+            let (times_shown, last_shown) = shown
+                .get(id.as_str())
+                .map_or((0, None), |&(count, last)| (count, Some(last)));
+
+            Choice {
+                downloaded_at: entry.map(|entry| entry.downloaded_at),
+                likes: entry.and_then(|entry| entry.likes),
+                path: path.to_owned(),
+                times_shown,
+                last_shown,
+                id,
+            }
+        })
+        .collect()
+}
+
+fn pick<T>(items: &[T]) -> Option<&T> {
+    if items.is_empty() {
+        return None;
+    }
+
+    items.get(random() as usize % items.len())
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,80 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+const MAX_SNAPSHOTS: usize = 10;
+
+#[derive(Debug)]
+pub struct Snapshots {
+    state_folder: PathBuf,
+    folder: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new<P: AsRef<Path>>(state_folder: P) -> Self {
+        let state_folder = state_folder.as_ref().to_owned();
+        let folder = state_folder.join("snapshots");
+
+        Self {
+            state_folder,
+            folder,
+        }
+    }
+
+    pub fn create(&self, files: &[&str]) -> io::Result<PathBuf> {
+        let snapshot = self
+            .folder
+            .join(Local::now().format("%Y%m%d-%H%M%S%3f").to_string());
+        fs::create_dir_all(&snapshot)?;
+
+        for file in files {
+            let source = self.state_folder.join(file);
+            if source.exists() {
+                fs::copy(&source, snapshot.join(file))?;
+            }
+        }
+
+        let snapshots = self.list()?;
+        for old in snapshots
+            .iter()
+            .take(snapshots.len().saturating_sub(MAX_SNAPSHOTS))
+        {
+            fs::remove_dir_all(old)?;
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn rollback(&self) -> io::Result<Option<PathBuf>> {
+        let Some(snapshot) = self.list()?.pop() else {
+            return Ok(None);
+        };
+
+        for file in snapshot.read_dir()? {
+            let file = file?;
+            fs::copy(file.path(), self.state_folder.join(file.file_name()))?;
+        }
+
+        fs::remove_dir_all(&snapshot)?;
+
+        Ok(Some(snapshot))
+    }
+
+    fn list(&self) -> io::Result<Vec<PathBuf>> {
+        if !self.folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = self
+            .folder
+            .read_dir()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        snapshots.sort();
+
+        Ok(snapshots)
+    }
+}
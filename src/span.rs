@@ -0,0 +1,79 @@
+use std::{io, path::Path};
+
+use image::{imageops, DynamicImage, ImageError, ImageFormat, ImageReader, RgbImage};
+use thiserror::Error;
+
+use crate::display::{self, Monitor};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Image(#[from] ImageError),
+
+    #[error("No monitors were found to span the wallpaper across")]
+    NoMonitors,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub fn compose<P: AsRef<Path>, Q: AsRef<Path>>(
+    monitors: &[Monitor],
+    photos: &[P],
+    output: Q,
+) -> Result<()> {
+    let (left, top, width, height) = display::bounds(monitors).ok_or(Error::NoMonitors)?;
+
+    let canvas = if let [photo] = photos {
+        resize_to_fill(&open(photo)?, width, height)
+    } else {
+        let mut monitors: Vec<_> = monitors.iter().collect();
+        monitors.sort_by_key(|monitor| (monitor.x, monitor.y));
+
+        let mut canvas = RgbImage::new(width, height);
+        for (monitor, photo) in monitors.into_iter().zip(photos.iter().cycle()) {
+            imageops::replace(
+                &mut canvas,
+                &resize_to_fill(&open(photo)?, monitor.width, monitor.height),
+                (monitor.x - left) as i64,
+                (monitor.y - top) as i64,
+            );
+        }
+
+        canvas
+    };
+
+    canvas.save_with_format(output, ImageFormat::Png)?;
+
+    Ok(())
+}
+
+pub(crate) fn crop_to_aspect_ratio(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let (image_width, image_height) = (image.width() as u64, image.height() as u64);
+    let (width, height) = (width as u64, height as u64);
+
+    let (crop_width, crop_height) = if image_width * height > image_height * width {
+        (image_height * width / height, image_height)
+    } else {
+        (image_width, image_width * height / width)
+    };
+
+    image.crop_imm(
+        ((image_width - crop_width) / 2) as u32,
+        ((image_height - crop_height) / 2) as u32,
+        crop_width as u32,
+        crop_height as u32,
+    )
+}
+
+fn resize_to_fill(image: &DynamicImage, width: u32, height: u32) -> RgbImage {
+    crop_to_aspect_ratio(image, width, height)
+        .resize_exact(width, height, imageops::FilterType::Lanczos3)
+        .to_rgb8()
+}
+
+fn open<P: AsRef<Path>>(path: P) -> Result<DynamicImage> {
+    Ok(ImageReader::open(path)?.with_guessed_format()?.decode()?)
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    history::{Entry, History},
+    User,
+};
+
+const ASSETS_FOLDER: &str =
+    "Packages/Microsoft.Windows.ContentDeliveryManager_cw5n1h2txyewy/LocalState/Assets";
+const MIN_WIDTH: u16 = 1280;
+
+pub const ID_PREFIX: &str = "spotlight-";
+
+pub fn assets_folder() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|folder| folder.join(ASSETS_FOLDER))
+}
+
+pub fn photographer() -> User {
+    User::new("windows-spotlight", "Windows Spotlight")
+}
+
+pub fn import<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    folder: Q,
+    history: &History,
+) -> io::Result<Vec<Entry>> {
+    let folder = folder.as_ref();
+    fs::create_dir_all(folder)?;
+
+    let mut seen = HashSet::new();
+    let mut imported = Vec::new();
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let bytes = fs::read(entry.path())?;
+        match jpeg_dimensions(&bytes) {
+            Some((width, height)) if width >= MIN_WIDTH && width > height => {}
+            _ => continue,
+        }
+
+        let sha256 = format!("{:x}", Sha256::digest(&bytes));
+        let id = format!("{}{}", ID_PREFIX, &sha256[..16]);
+        if history.contains(&id) || !seen.insert(id.clone()) {
+            continue;
+        }
+
+        let path = folder.join(format!("{}.jpg", id));
+        if !path.exists() {
+            fs::write(&path, &bytes)?;
+        }
+
+        imported.push(Entry::imported(&id, &path, photographer()).with_sha256(sha256));
+    }
+
+    Ok(imported)
+}
+
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u16, u16)> {
+    if !bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return None;
+    }
+
+    let mut offset = 2;
+    while offset + 4 <= bytes.len() {
+        if bytes[offset] != 0xFF {
+            return None;
+        }
+
+        let marker = bytes[offset + 1];
+        if marker == 0xFF {
+            offset += 1;
+            continue;
+        }
+
+        let length = u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]) as usize;
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let frame = bytes.get(offset + 5..offset + 9)?;
+            let height = u16::from_be_bytes([frame[0], frame[1]]);
+            let width = u16::from_be_bytes([frame[2], frame[3]]);
+            return Some((width, height));
+        }
+
+        offset += 2 + length;
+    }
+
+    None
+}
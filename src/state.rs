@@ -0,0 +1,265 @@
+use std::{
+    error, fmt,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cloud;
+
+pub type Migration = fn(Value) -> Value;
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revision: Option<u64>,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Stored {
+    version: u32,
+    #[serde(default)]
+    revision: Option<u64>,
+    data: Value,
+}
+
+#[derive(Debug)]
+struct Conflict {
+    path: PathBuf,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} was changed by another Backdrop process, try again",
+            self.path.display()
+        )
+    }
+}
+
+impl error::Error for Conflict {}
+
+pub fn is_conflict(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|inner| inner.is::<Conflict>())
+}
+
+pub fn load<T: DeserializeOwned + Serialize + Default>(
+    path: &Path,
+    migrations: &[Migration],
+) -> io::Result<T> {
+    load_revision(path, migrations).map(|(data, _)| data)
+}
+
+pub fn load_revision<T: DeserializeOwned + Serialize + Default>(
+    path: &Path,
+    migrations: &[Migration],
+) -> io::Result<(T, u64)> {
+    let content = match cloud::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((T::default(), 0)),
+        Err(err) => return Err(err),
+    };
+
+    let current = migrations.len() as u32;
+    let (version, revision, mut data) = match serde_json::from_str::<Value>(&content) {
+        Ok(value) => match serde_json::from_value::<Stored>(value.clone()) {
+            Ok(stored) => (stored.version, stored.revision, stored.data),
+            Err(_) => (0, None, value),
+        },
+
+        Err(err) => return recover(path, err).map(|data| (data, 0)),
+    };
+
+    if version > current {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} was written by a newer version of Backdrop (version {}, expected {})",
+                path.display(),
+                version,
+                current
+            ),
+        ));
+    }
+
+    if version < current {
+        let backup = backup_path(path, version);
+        fs::copy(path, &backup)?;
+        tracing::info!(
+            path = %path.display(),
+            backup = %backup.display(),
+            from = version,
+            to = current,
+            "migrating state file"
+        );
+
+        for migration in &migrations[version as usize..] {
+            data = migration(data);
+        }
+    }
+
+    match serde_json::from_value(data) {
+        Ok(data) => {
+            if version < current {
+                write(path, &data, migrations, revision)?;
+            }
+
+            Ok((data, revision.unwrap_or(0)))
+        }
+
+        Err(err) => recover(path, err).map(|data| (data, 0)),
+    }
+}
+
+pub fn save<T: Serialize>(path: &Path, data: &T, migrations: &[Migration]) -> io::Result<()> {
+    write(path, data, migrations, None)
+}
+
+pub fn save_revision<T: Serialize>(
+    path: &Path,
+    data: &T,
+    migrations: &[Migration],
+    revision: u64,
+) -> io::Result<u64> {
+    let _lock = lock(path)?;
+    if self::revision(path)? != revision {
+        return Err(io::Error::other(Conflict {
+            path: path.to_owned(),
+        }));
+    }
+
+    write(path, data, migrations, Some(revision + 1))?;
+
+    Ok(revision + 1)
+}
+
+pub fn revision(path: &Path) -> io::Result<u64> {
+    match cloud::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str::<Stored>(&content)
+            .ok()
+            .and_then(|stored| stored.revision)
+            .unwrap_or(0)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+fn lock(path: &Path) -> io::Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(PathBuf::from(lock_path))?;
+    file.lock()?;
+
+    Ok(file)
+}
+
+fn write<T: Serialize>(
+    path: &Path,
+    data: &T,
+    migrations: &[Migration],
+    revision: Option<u64>,
+) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(&Versioned {
+        version: migrations.len() as u32,
+        revision,
+        data,
+    })
+    .map_err(Into::<io::Error>::into)?;
+
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, path)
+}
+
+fn recover<T: Default>(path: &Path, err: serde_json::Error) -> io::Result<T> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".corrupt");
+    let backup = PathBuf::from(backup);
+
+    fs::rename(path, &backup)?;
+    tracing::warn!(
+        path = %path.display(),
+        backup = %backup.display(),
+        error = %err,
+        "could not read the state file, starting over"
+    );
+
+    Ok(T::default())
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+
+    PathBuf::from(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use super::*;
+
+    const THREADS: usize = 8;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("backdrop-{}-{}.json", name, std::process::id()))
+    }
+
+    fn remove(path: &Path) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+    }
+
+    #[test]
+    fn stale_revision_is_a_conflict() {
+        let path = temp_path("stale");
+
+        let first = save_revision(&path, &1, &[], 0).unwrap();
+        let stale = save_revision(&path, &2, &[], 0).unwrap_err();
+        let reloaded: (u32, u64) = load_revision(&path, &[]).unwrap();
+        remove(&path);
+
+        assert_eq!(first, 1);
+        assert!(is_conflict(&stale));
+        assert_eq!(reloaded, (1, 1));
+    }
+
+    #[test]
+    fn concurrent_saves_from_the_same_revision_conflict() {
+        let path = temp_path("concurrent");
+        let barrier = Barrier::new(THREADS);
+
+        let saved = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|value| {
+                    let (path, barrier) = (&path, &barrier);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        save_revision(path, &value, &[], 0)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(Result::is_ok)
+                .count()
+        });
+        remove(&path);
+
+        assert_eq!(saved, 1);
+    }
+}
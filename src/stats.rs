@@ -0,0 +1,105 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{self, Migration},
+    Photo,
+};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub id: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Counters {
+    #[serde(default)]
+    pub photos_fetched: u64,
+    #[serde(default)]
+    pub bytes_downloaded: u64,
+    #[serde(default)]
+    pub wallpapers_set: u64,
+    #[serde(default)]
+    pub authors: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug)]
+pub struct Stats {
+    path: PathBuf,
+    counters: Counters,
+}
+
+impl Stats {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let counters = state::load(&path, MIGRATIONS)?;
+
+        Ok(Self { path, counters })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        state::save(&self.path, &self.counters, MIGRATIONS)
+    }
+
+    pub fn record_download(&mut self, photo: &Photo, bytes: u64) {
+        self.counters.photos_fetched += 1;
+        self.counters.bytes_downloaded += bytes;
+
+        *self
+            .counters
+            .authors
+            .entry(photo.user().username().to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn record_wallpaper_set(&mut self, id: &str) {
+        self.counters.wallpapers_set += 1;
+        self.counters.changes.push(Change {
+            id: id.to_owned(),
+            at: Utc::now(),
+        });
+    }
+
+    pub fn changes_per_day(&self, window: TimeDelta) -> Option<f64> {
+        let now = Utc::now();
+        let recent: Vec<_> = self
+            .counters
+            .changes
+            .iter()
+            .filter(|change| now - change.at <= window)
+            .collect();
+
+        let oldest = recent.iter().map(|change| change.at).min()?;
+        let days = (now - oldest).num_minutes() as f64 / (24.0 * 60.0);
+        (recent.len() >= 2 && days > 0.0).then(|| recent.len() as f64 / days)
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn favorite_authors(&self) -> Vec<(&str, u64)> {
+        let mut authors: Vec<_> = self
+            .counters
+            .authors
+            .iter()
+            .map(|(username, count)| (username.as_str(), *count))
+            .collect();
+        authors.sort_by_key(|&(_, count)| Reverse(count));
+
+        authors
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "test-util")]
+use crate::filesystem::FakeFs;
+use crate::filesystem::{File, Filesystem, RealFs};
+
+pub trait Storage: Filesystem + Send + Sync {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()>;
+}
+
+impl Storage for RealFs {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()> {
+        sync(source)?;
+
+        fs::rename(source, path)
+    }
+}
+
+/// Flushes `path` to disk, so a crash right after renaming it can't leave an empty file behind.
+fn sync(path: &Path) -> io::Result<()> {
+    fs::OpenOptions::new().write(true).open(path)?.sync_all()
+}
+
+#[derive(Debug, Clone)]
+pub struct FolderStorage {
+    root: PathBuf,
+    target: PathBuf,
+}
+
+impl FolderStorage {
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(root: P, target: Q) -> Self {
+        Self {
+            root: root.as_ref().to_owned(),
+            target: target.as_ref().to_owned(),
+        }
+    }
+
+    fn target_path(&self, path: &Path) -> io::Result<PathBuf> {
+        let relative = path.strip_prefix(&self.root).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not inside the storage folder", path.display()),
+            )
+        })?;
+
+        Ok(self.target.join(relative))
+    }
+
+    fn root_path(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.target) {
+            Ok(relative) => self.root.join(relative),
+            Err(_) => path.to_owned(),
+        }
+    }
+}
+
+impl Filesystem for FolderStorage {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>> {
+        Ok(RealFs
+            .list(&self.target_path(folder)?)?
+            .into_iter()
+            .map(|file| File {
+                path: self.root_path(&file.path),
+                ..file
+            })
+            .collect())
+    }
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(RealFs
+            .list_folders(&self.target_path(folder)?)?
+            .iter()
+            .map(|path| self.root_path(path))
+            .collect())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(self.target_path(path)?)
+    }
+}
+
+impl Storage for FolderStorage {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()> {
+        let target = self.target_path(path)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        sync(source)?;
+        fs::copy(source, target)?;
+
+        fs::rename(source, path)
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl Storage for FakeFs {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()> {
+        let metadata = fs::metadata(source)?;
+        self.add_file(path, metadata.len(), std::time::SystemTime::now());
+
+        fs::remove_file(source)
+    }
+}
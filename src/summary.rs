@@ -0,0 +1,68 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::Eviction,
+    timing::{self, Timings},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub fetched: usize,
+    pub bytes_downloaded: u64,
+    pub evicted: Vec<Eviction>,
+    #[serde(default)]
+    pub timings: Timings,
+    pub budget_remaining: Option<u32>,
+}
+
+impl RunSummary {
+    pub fn log(&self) {
+        tracing::info!(
+            fetched = self.fetched,
+            bytes_downloaded = self.bytes_downloaded,
+            evicted = self.evicted.len(),
+            bytes_evicted = self
+                .evicted
+                .iter()
+                .map(|eviction| eviction.size)
+                .sum::<u64>(),
+            duration_ms = self.duration_ms,
+            budget_remaining = self.budget_remaining,
+            "run finished"
+        );
+        timing::log(&self.timings);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RunLog {
+    path: PathBuf,
+}
+
+impl RunLog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn append(&self, summary: &RunSummary) -> io::Result<()> {
+        let mut line = serde_json::to_string(summary)?;
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
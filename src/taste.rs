@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{self, Migration},
+    Photo,
+};
+
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Block,
+    Unpin,
+    Skip,
+    Pin,
+}
+
+impl Signal {
+    fn weight(self) -> f64 {
+        match self {
+            Signal::Block => -1.0,
+            Signal::Unpin => -0.5,
+            Signal::Skip => -0.25,
+            Signal::Pin => 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Score {
+    pub weight: f64,
+    pub observations: u32,
+}
+
+impl Score {
+    pub fn confidence(&self) -> f64 {
+        let observations = self.observations as f64;
+
+        observations / (observations + 1.0)
+    }
+
+    fn value(&self) -> f64 {
+        self.weight * self.confidence()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Weights {
+    #[serde(default)]
+    pub authors: BTreeMap<String, Score>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, Score>,
+}
+
+#[derive(Debug)]
+pub struct Taste {
+    path: PathBuf,
+    weights: Weights,
+}
+
+impl Taste {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let weights = state::load(&path, MIGRATIONS)?;
+
+        Ok(Self { path, weights })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        state::save(&self.path, &self.weights, MIGRATIONS)
+    }
+
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    pub fn reset(&mut self) {
+        self.weights = Weights::default();
+    }
+
+    pub fn record<'a, I>(&mut self, author: &str, tags: I, signal: Signal)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        observe(
+            self.weights.authors.entry(author.to_owned()).or_default(),
+            signal,
+        );
+        for tag in tags {
+            observe(
+                self.weights.tags.entry(tag.to_lowercase()).or_default(),
+                signal,
+            );
+        }
+    }
+
+    pub fn score<'a, I>(&self, author: &str, tags: I) -> f64
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let author = self.weights.authors.get(author).map_or(0.0, Score::value);
+
+        let tags: Vec<_> = tags
+            .into_iter()
+            .filter_map(|tag| self.weights.tags.get(&tag.to_lowercase()))
+            .map(Score::value)
+            .collect();
+        let tags = if tags.is_empty() {
+            0.0
+        } else {
+            tags.iter().sum::<f64>() / tags.len() as f64
+        };
+
+        author + tags
+    }
+
+    pub fn score_photo(&self, photo: &Photo) -> f64 {
+        self.score(photo.user().username(), photo.tags())
+    }
+}
+
+fn observe(score: &mut Score, signal: Signal) {
+    score.weight += signal.weight();
+    score.observations += 1;
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    #[error("Unknown placeholder `{{{0}}}`")]
+    UnknownPlaceholder(String),
+
+    #[error("Unterminated placeholder")]
+    Unterminated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str, placeholders: &[&str]) -> Result<Self, ParseError> {
+        let mut parts = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}').ok_or(ParseError::Unterminated)?;
+            let placeholder = &rest[start + 1..end];
+            if !placeholders.contains(&placeholder) {
+                return Err(ParseError::UnknownPlaceholder(placeholder.to_owned()));
+            }
+
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_owned()));
+            }
+            parts.push(Part::Placeholder(placeholder.to_owned()));
+
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_owned()));
+        }
+
+        Ok(Self {
+            source: source.to_owned(),
+            parts,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn contains(&self, placeholder: &str) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Placeholder(name) if name == placeholder))
+    }
+
+    pub fn render<F: FnMut(&str) -> String>(&self, mut value: F) -> String {
+        let mut output = String::with_capacity(self.source.len());
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => output.push_str(text),
+                Part::Placeholder(name) => output.push_str(&value(name)),
+            }
+        }
+
+        output
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
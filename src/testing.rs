@@ -0,0 +1,69 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{redact::Redacted, Client, Photo, RetryPolicy};
+
+pub const UNREACHABLE: &str = "http://127.0.0.1:9";
+
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("backdrop-{}-{}", name, std::process::id()))
+}
+
+pub fn unreachable_client() -> Client {
+    Client::new(&Redacted::from("key".to_owned()))
+        .unwrap()
+        .with_base_url(UNREACHABLE.parse().unwrap())
+        .with_retry(RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        })
+}
+
+pub fn photo(id: &str, base_url: &str) -> Photo {
+    let base_url = base_url.trim_end_matches('/');
+
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "width": 1,
+        "height": 1,
+        "user": { "username": "user" },
+        "urls": { "raw": format!("{}/raw", base_url) },
+        "links": { "download_location": format!("{}/track", base_url) },
+    }))
+    .unwrap()
+}
+
+/// Serves `body` to every request and records the requested paths.
+pub async fn serve(body: &'static [u8]) -> (Url, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let paths = Arc::new(Mutex::new(Vec::new()));
+
+    let requested = paths.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = vec![0; 4096];
+            let read = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]);
+            let path = request.split(' ').nth(1).unwrap_or_default().to_owned();
+            requested.lock().unwrap().push(path);
+
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(body).await.unwrap();
+        }
+    });
+
+    (url.parse().unwrap(), paths)
+}
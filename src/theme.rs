@@ -0,0 +1,36 @@
+#[cfg(windows)]
+pub fn is_dark() -> bool {
+    use windows::{
+        core::w,
+        Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+    };
+
+    let mut light = 1u32;
+    let mut size = size_of::<u32>() as u32;
+
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut light as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+
+    result.is_ok() && light == 0
+}
+
+#[cfg(not(windows))]
+pub fn is_dark() -> bool {
+    use std::process::Command;
+
+    Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "color-scheme"])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success() && output.stdout.trim_ascii() == b"'prefer-dark'"
+        })
+}
@@ -0,0 +1,121 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Cursor},
+    path::Path,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use bytes::Bytes;
+use image::{codecs::jpeg::JpegEncoder, ImageError};
+use thiserror::Error;
+
+const MIN_SIZE: u32 = 32;
+const MAX_SIZE: u32 = 1024;
+const SIZE_STEP: u32 = 32;
+const QUALITY: u8 = 80;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Image(#[from] ImageError),
+}
+
+#[derive(Debug)]
+struct Entry {
+    modified: Option<SystemTime>,
+    data: Bytes,
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<(String, u32), Entry>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+pub struct ThumbnailCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ThumbnailCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn size(requested: u32) -> u32 {
+        requested.clamp(MIN_SIZE, MAX_SIZE).div_ceil(SIZE_STEP) * SIZE_STEP
+    }
+
+    pub fn get<P: AsRef<Path>>(&self, id: &str, path: P, size: u32) -> Result<Bytes, Error> {
+        let path = path.as_ref();
+        let size = Self::size(size);
+        let key = (id.to_owned(), size);
+        let modified = fs::metadata(path)?.modified().ok();
+
+        if let Some(data) = self.lookup(&key, modified) {
+            return Ok(data);
+        }
+
+        let data = Bytes::from(render(path, size)?);
+        tracing::debug!(id, size, bytes = data.len(), "generated thumbnail");
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let used = entries.clock;
+        entries.entries.insert(
+            key,
+            Entry {
+                modified,
+                data: data.clone(),
+                used,
+            },
+        );
+
+        while entries.entries.len() > self.capacity {
+            let Some(oldest) = entries
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.entries.remove(&oldest);
+        }
+
+        Ok(data)
+    }
+
+    fn lookup(&self, key: &(String, u32), modified: Option<SystemTime>) -> Option<Bytes> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let entry = entries.entries.get_mut(key)?;
+        if entry.modified != modified {
+            return None;
+        }
+        entry.used = clock;
+
+        Some(entry.data.clone())
+    }
+}
+
+fn render(path: &Path, size: u32) -> Result<Vec<u8>, Error> {
+    let thumbnail = image::open(path)?.thumbnail(size, size).into_rgb8();
+
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(Cursor::new(&mut data), QUALITY).encode_image(&thumbnail)?;
+
+    Ok(data)
+}
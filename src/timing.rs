@@ -0,0 +1,154 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Fetch,
+    Download,
+    Processing,
+    Write,
+    Cleanup,
+    Apply,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Fetch => "fetch",
+            Stage::Download => "download",
+            Stage::Processing => "processing",
+            Stage::Write => "write",
+            Stage::Cleanup => "cleanup",
+            Stage::Apply => "apply",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    pub count: usize,
+    pub total_ms: f64,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    fn new(elapsed: Duration) -> Self {
+        let elapsed = millis(elapsed);
+
+        Self {
+            count: 1,
+            total_ms: elapsed,
+            min_ms: elapsed,
+            avg_ms: elapsed,
+            max_ms: elapsed,
+        }
+    }
+
+    fn merge(&mut self, other: &Timing) {
+        self.count += other.count;
+        self.total_ms += other.total_ms;
+        self.min_ms = self.min_ms.min(other.min_ms);
+        self.max_ms = self.max_ms.max(other.max_ms);
+        self.avg_ms = self.total_ms / self.count as f64;
+    }
+}
+
+pub type Timings = BTreeMap<Stage, Timing>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    timings: Arc<Mutex<Timings>>,
+}
+
+impl Timer {
+    pub fn record(&self, stage: Stage, elapsed: Duration) {
+        tracing::debug!(%stage, elapsed_ms = millis(elapsed), "stage finished");
+
+        self.merge(Timings::from([(stage, Timing::new(elapsed))]));
+    }
+
+    pub fn time<T, F: FnOnce() -> T>(&self, stage: Stage, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(stage, start.elapsed());
+
+        result
+    }
+
+    pub fn merge(&self, other: Timings) {
+        let mut timings = self.timings.lock().unwrap();
+        for (stage, timing) in other {
+            timings
+                .entry(stage)
+                .and_modify(|existing| existing.merge(&timing))
+                .or_insert(timing);
+        }
+    }
+
+    pub fn take(&self) -> Timings {
+        std::mem::take(&mut *self.timings.lock().unwrap())
+    }
+}
+
+pub fn log(timings: &Timings) {
+    for (stage, timing) in timings {
+        tracing::info!(
+            %stage,
+            count = timing.count,
+            total_ms = timing.total_ms,
+            min_ms = timing.min_ms,
+            avg_ms = timing.avg_ms,
+            max_ms = timing.max_ms,
+            "stage timing"
+        );
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers_are_independent() {
+        let first = Timer::default();
+        let second = Timer::default();
+
+        first.record(Stage::Fetch, Duration::from_millis(10));
+
+        assert_eq!(first.take().len(), 1);
+        assert!(second.take().is_empty());
+        assert!(first.take().is_empty());
+    }
+
+    #[test]
+    fn samples_are_aggregated() {
+        let timer = Timer::default();
+        timer.record(Stage::Download, Duration::from_millis(10));
+        timer.record(Stage::Download, Duration::from_millis(30));
+        timer.merge(Timings::from([(
+            Stage::Download,
+            Timing::new(Duration::from_millis(20)),
+        )]));
+
+        let timing = timer.take()[&Stage::Download];
+
+        assert_eq!(timing.count, 3);
+        assert_eq!(timing.total_ms, 60.0);
+        assert_eq!(timing.min_ms, 10.0);
+        assert_eq!(timing.avg_ms, 20.0);
+        assert_eq!(timing.max_ms, 30.0);
+    }
+}
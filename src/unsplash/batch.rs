@@ -0,0 +1,207 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio::{fs, sync::Semaphore, task::JoinSet};
+
+use super::{Client, Download, Error, Photo, Result};
+use crate::timing::Timings;
+
+pub const PART_EXTENSION: &str = "part";
+
+#[derive(Debug)]
+pub struct BatchReport<T> {
+    pub saved: Vec<T>,
+    pub failed: Vec<Error>,
+    pub timings: Timings,
+}
+
+impl<T> BatchReport<T> {
+    pub fn is_total_failure(&self) -> bool {
+        self.saved.is_empty() && !self.failed.is_empty()
+    }
+
+    pub fn into_result(mut self) -> Result<Vec<T>> {
+        if self.is_total_failure() {
+            return Err(self.failed.swap_remove(0));
+        }
+
+        Ok(self.saved)
+    }
+}
+
+impl<T> Default for BatchReport<T> {
+    fn default() -> Self {
+        Self {
+            saved: Vec::new(),
+            failed: Vec::new(),
+            timings: Timings::new(),
+        }
+    }
+}
+
+impl Client {
+    pub async fn download_photo_to_file<P: AsRef<Path>>(
+        &self,
+        photo: &Photo,
+        download: &Download,
+        path: P,
+    ) -> Result<String> {
+        let path = path.as_ref();
+
+        let mut part = path.as_os_str().to_owned();
+        part.push(".");
+        part.push(PART_EXTENSION);
+
+        let sha256 = self.download_verified_to(photo, download, &part).await?;
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&part)
+            .await?
+            .sync_all()
+            .await?;
+        fs::rename(&part, path).await?;
+
+        Ok(sha256)
+    }
+
+    pub async fn download_verified_to<P: AsRef<Path>>(
+        &self,
+        photo: &Photo,
+        download: &Download,
+        path: P,
+    ) -> Result<String> {
+        let path = path.as_ref();
+
+        self.track_download(photo).await?;
+
+        let mut attempts = 0;
+        loop {
+            let mut file = fs::File::create(path).await?;
+            let result = self.write_photo_to(photo, download, &mut file).await;
+            drop(file);
+
+            let result = match result {
+                Ok(_) => match fs::metadata(path).await?.len() {
+                    bytes if bytes < download.min_bytes => Err(Error::TooSmall {
+                        id: photo.id().to_owned(),
+                        bytes,
+                    }),
+                    _ => result,
+                },
+
+                result => result,
+            };
+
+            #[cfg(feature = "verify")]
+            let result = match result {
+                Ok(_) if !super::verify::decodes(path.to_owned(), &download.format).await => {
+                    Err(Error::InvalidImage(photo.id().to_owned()))
+                }
+
+                result => result,
+            };
+
+            match result {
+                Ok(sha256) => return Ok(sha256),
+
+                Err(
+                    err @ (Error::ChecksumMismatch(_)
+                    | Error::InvalidImage(_)
+                    | Error::TooSmall { .. }),
+                ) if attempts + 1 < self.retry.max_attempts => {
+                    self.wait_to_redownload(photo, &err, attempts).await;
+                    attempts += 1;
+                }
+
+                Err(err) => {
+                    if let Err(cleanup) = fs::remove_file(path).await {
+                        tracing::warn!(
+                            path = %path.display(),
+                            error = %cleanup,
+                            "failed to remove the partial download"
+                        );
+                    }
+
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    pub async fn download_all<P: AsRef<Path>>(
+        &self,
+        photos: Vec<Photo>,
+        download: &Download,
+        folder: P,
+        max_concurrent: usize,
+    ) -> Result<BatchReport<(Photo, PathBuf)>> {
+        let folder = folder.as_ref();
+        fs::create_dir_all(folder).await?;
+
+        let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+
+        let mut taken = HashSet::new();
+        let mut tasks = JoinSet::new();
+        for photo in photos {
+            let client = self.clone();
+            let download = download.clone();
+            let semaphore = semaphore.clone();
+            let path = download.path(folder, &photo, &mut taken);
+
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.unwrap();
+                match client
+                    .download_photo_to_file(&photo, &download, &path)
+                    .await
+                {
+                    Ok(_) => Ok((photo, path)),
+
+                    Err(err) => {
+                        tracing::error!(id = photo.id(), error = %err, "download failed");
+
+                        Err(err)
+                    }
+                }
+            });
+        }
+
+        let mut report = BatchReport::default();
+        for result in tasks.join_all().await {
+            match result {
+                Ok(saved) => report.saved.push(saved),
+                Err(err) => report.failed.push(err),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, UNREACHABLE};
+
+    #[tokio::test]
+    async fn failed_downloads_keep_their_error_and_remove_the_part_file() {
+        let folder = testing::temp_path("batch");
+        std::fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("photo.png");
+
+        let result = testing::unreachable_client()
+            .download_photo_to_file(
+                &testing::photo("photo", UNREACHABLE),
+                &Download::default(),
+                &path,
+            )
+            .await;
+        let remaining = std::fs::read_dir(&folder).unwrap().count();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert!(matches!(result, Err(Error::Request { .. })));
+        assert_eq!(remaining, 0);
+    }
+}
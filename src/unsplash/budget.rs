@@ -0,0 +1,86 @@
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+const WINDOW: TimeDelta = TimeDelta::hours(1);
+
+#[derive(Debug)]
+pub struct RequestBudget {
+    path: PathBuf,
+    per_hour: u32,
+    requests: VecDeque<DateTime<Utc>>,
+}
+
+impl RequestBudget {
+    pub fn open<P: AsRef<Path>>(path: P, per_hour: u32) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let requests = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(Into::<io::Error>::into)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => VecDeque::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut budget = Self {
+            path,
+            per_hour,
+            requests,
+        };
+        budget.expire(Utc::now());
+
+        Ok(budget)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let content = serde_json::to_string(&self.requests).map_err(Into::<io::Error>::into)?;
+
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)
+    }
+
+    pub fn per_hour(&self) -> u32 {
+        self.per_hour
+    }
+
+    pub fn used(&self, now: DateTime<Utc>) -> u32 {
+        self.requests
+            .iter()
+            .filter(|&&at| now - at < WINDOW)
+            .count() as u32
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> u32 {
+        self.per_hour.saturating_sub(self.used(now))
+    }
+
+    pub fn available_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.remaining(now) > 0 {
+            return None;
+        }
+
+        let recent: Vec<_> = self
+            .requests
+            .iter()
+            .filter(|&&at| now - at < WINDOW)
+            .collect();
+        let index = recent.len() - self.per_hour as usize;
+
+        recent.get(index).map(|&&at| at + WINDOW)
+    }
+
+    pub(crate) fn record(&mut self, now: DateTime<Utc>) {
+        self.expire(now);
+        self.requests.push_back(now);
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        while self.requests.front().is_some_and(|&at| now - at >= WINDOW) {
+            self.requests.pop_front();
+        }
+    }
+}
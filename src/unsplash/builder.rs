@@ -0,0 +1,293 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Certificate, Client as HttpClient, Proxy, Url,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dns::{IpVersion, Resolver},
+    Client, Error, Result, API_URL,
+};
+use crate::redact::Redacted;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Network {
+    #[serde(default = "Network::default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    #[serde(default = "Network::default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    #[serde(default)]
+    pub pin_root_certificates: bool,
+    #[serde(default)]
+    pub ip_version: IpVersion,
+    #[serde(default)]
+    pub dns_overrides: BTreeMap<String, Vec<IpAddr>>,
+    #[serde(default)]
+    pub dns_over_https: Option<String>,
+}
+
+impl Network {
+    fn default_connect_timeout_secs() -> u64 {
+        30
+    }
+
+    fn default_read_timeout_secs() -> u64 {
+        60
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: Self::default_connect_timeout_secs(),
+            read_timeout_secs: Self::default_read_timeout_secs(),
+            timeout_secs: None,
+            proxy: None,
+            root_certificates: Vec::new(),
+            pin_root_certificates: false,
+            ip_version: IpVersion::default(),
+            dns_overrides: BTreeMap::new(),
+            dns_over_https: None,
+        }
+    }
+}
+
+pub struct ClientBuilder {
+    api_key: Redacted<String>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    pin_root_certificates: bool,
+    ip_version: IpVersion,
+    dns_overrides: Vec<(String, Vec<IpAddr>)>,
+    dns_over_https: Option<String>,
+}
+
+impl ClientBuilder {
+    pub(super) fn new(api_key: &Redacted<String>) -> Self {
+        Self {
+            api_key: api_key.clone(),
+            connect_timeout: None,
+            read_timeout: None,
+            timeout: None,
+            proxy: None,
+            root_certificates: Vec::new(),
+            pin_root_certificates: false,
+            ip_version: IpVersion::default(),
+            dns_overrides: Vec::new(),
+            dns_over_https: None,
+        }
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    pub fn root_certificate(mut self, pem: Vec<u8>) -> Self {
+        self.root_certificates.push(pem);
+        self
+    }
+
+    pub fn pin_root_certificates(mut self, pin: bool) -> Self {
+        self.pin_root_certificates = pin;
+        self
+    }
+
+    pub fn ip_version(mut self, ip_version: IpVersion) -> Self {
+        self.ip_version = ip_version;
+        self
+    }
+
+    pub fn resolve<S: Into<String>>(mut self, domain: S, addrs: Vec<IpAddr>) -> Self {
+        self.dns_overrides.push((domain.into(), addrs));
+        self
+    }
+
+    pub fn dns_over_https<S: Into<String>>(mut self, url: S) -> Self {
+        self.dns_over_https = Some(url.into());
+        self
+    }
+
+    pub fn network(mut self, network: &Network) -> Result<Self> {
+        self = self
+            .connect_timeout(Duration::from_secs(network.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(network.read_timeout_secs));
+
+        if let Some(timeout) = network.timeout_secs {
+            self = self.timeout(Duration::from_secs(timeout));
+        }
+
+        if let Some(proxy) = &network.proxy {
+            self = self.proxy(proxy);
+        }
+
+        for path in &network.root_certificates {
+            let pem = fs::read(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            self = self.root_certificate(pem);
+        }
+
+        self = self
+            .pin_root_certificates(network.pin_root_certificates)
+            .ip_version(network.ip_version);
+        for (domain, addrs) in &network.dns_overrides {
+            self = self.resolve(domain, addrs.clone());
+        }
+
+        if let Some(url) = &network.dns_over_https {
+            self = self.dns_over_https(url);
+        }
+
+        Ok(self)
+    }
+
+    /// Applies the timeouts, proxy and certificates, which every request has to go through,
+    /// including the DNS over HTTPS lookups.
+    fn http_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut http = HttpClient::builder();
+
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.read_timeout {
+            http = http.read_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            http = http.proxy(Proxy::all(proxy).map_err(Error::HttpClient)?);
+        }
+
+        for pem in &self.root_certificates {
+            http =
+                http.add_root_certificate(Certificate::from_pem(pem).map_err(Error::HttpClient)?);
+        }
+
+        if self.pin_root_certificates {
+            http = http.tls_built_in_root_certs(false);
+        }
+
+        Ok(http)
+    }
+
+    fn resolver(&self) -> Result<Option<Resolver>> {
+        if self.ip_version == IpVersion::Any && self.dns_over_https.is_none() {
+            return Ok(None);
+        }
+
+        let doh = match &self.dns_over_https {
+            Some(url) => {
+                let url =
+                    Url::parse(url).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                let http = self.http_builder()?.build().map_err(Error::HttpClient)?;
+
+                Some((http, url))
+            }
+
+            None => None,
+        };
+
+        Ok(Some(Resolver::new(self.ip_version, doh)))
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let auth = format!("Client-ID {}", self.api_key.expose());
+        let mut auth = HeaderValue::from_str(&auth).map_err(|_| Error::InvalidApiKey)?;
+        auth.set_sensitive(true);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", auth);
+
+        let mut http = self.http_builder()?.default_headers(headers);
+
+        if let Some(resolver) = self.resolver()? {
+            http = http.dns_resolver(Arc::new(resolver));
+        }
+
+        for (domain, addrs) in &self.dns_overrides {
+            let addrs: Vec<_> = addrs.iter().map(|&ip| SocketAddr::new(ip, 0)).collect();
+            http = http.resolve_to_addrs(domain, &addrs);
+        }
+
+        Ok(Client {
+            http: http.build().map_err(Error::HttpClient)?,
+            base_url: API_URL.to_owned(),
+            retry: Default::default(),
+            limits: Default::default(),
+            usage: Default::default(),
+            progress: None,
+            cache: None,
+            tracks: None,
+            budget: None,
+            shared_cache: None,
+            rate_limit: Default::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[tokio::test]
+    async fn dns_over_https_goes_through_the_proxy() {
+        let (proxy, requests) = testing::serve(br#"{ "Answer": [{ "data": "192.0.2.1" }] }"#).await;
+
+        let resolver = ClientBuilder::new(&Redacted::from("key".to_owned()))
+            .proxy(proxy.as_str())
+            .dns_over_https("http://doh.invalid/dns-query")
+            .resolver()
+            .unwrap()
+            .unwrap();
+        let addrs = resolver.lookup("images.unsplash.com").await.unwrap();
+        let mut requests = requests.lock().unwrap().clone();
+        requests.sort();
+
+        assert_eq!(addrs, [IpAddr::from([192, 0, 2, 1]); 2]);
+        assert_eq!(
+            requests,
+            [
+                "http://doh.invalid/dns-query?name=images.unsplash.com&type=A",
+                "http://doh.invalid/dns-query?name=images.unsplash.com&type=AAAA",
+            ]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Topic;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedResponse {
+    pub etag: String,
+    pub body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Entries {
+    #[serde(default)]
+    topics: BTreeMap<String, Topic>,
+    #[serde(default)]
+    responses: BTreeMap<String, CachedResponse>,
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    path: PathBuf,
+    entries: Entries,
+}
+
+impl ResponseCache {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Entries::default(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let content =
+            serde_json::to_string_pretty(&self.entries).map_err(Into::<io::Error>::into)?;
+
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)
+    }
+
+    pub(crate) fn topic(&self, id_or_slug: &str) -> Option<&Topic> {
+        self.entries.topics.get(id_or_slug)
+    }
+
+    pub(crate) fn insert_topic(&mut self, topic: &Topic) {
+        for key in [topic.id(), topic.slug()] {
+            if !key.is_empty() {
+                self.entries.topics.insert(key.to_owned(), topic.clone());
+            }
+        }
+    }
+
+    pub(crate) fn response(&self, url: &str) -> Option<&CachedResponse> {
+        self.entries.responses.get(url)
+    }
+
+    pub(crate) fn insert_response(&mut self, url: String, response: CachedResponse) {
+        self.entries.responses.insert(url, response);
+    }
+}
@@ -0,0 +1,87 @@
+use md5::Md5;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, ETAG};
+use sha2::{Digest, Sha256};
+
+use super::verify::HEADER_LENGTH;
+
+#[derive(Debug, Default)]
+pub(crate) struct Checksum {
+    length: Option<u64>,
+    md5: Option<String>,
+    sha256: Option<String>,
+}
+
+impl Checksum {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let length = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok()?.parse().ok());
+
+        let etag = headers
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .filter(|etag| !etag.starts_with("W/"))
+            .map(|etag| etag.trim_matches('"').to_ascii_lowercase())
+            .filter(|etag| etag.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let (md5, sha256) = match etag {
+            Some(etag) if etag.len() == 32 => (Some(etag), None),
+            Some(etag) if etag.len() == 64 => (None, Some(etag)),
+            _ => (None, None),
+        };
+
+        Self {
+            length,
+            md5,
+            sha256,
+        }
+    }
+
+    pub(crate) fn hasher(&self) -> Hasher {
+        Hasher {
+            length: 0,
+            header: Vec::with_capacity(HEADER_LENGTH),
+            md5: self.md5.is_some().then(Md5::new),
+            sha256: Sha256::new(),
+        }
+    }
+}
+
+pub(crate) struct Hasher {
+    length: u64,
+    header: Vec<u8>,
+    md5: Option<Md5>,
+    sha256: Sha256,
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        let missing = HEADER_LENGTH - self.header.len();
+        self.header
+            .extend_from_slice(&data[..missing.min(data.len())]);
+        self.sha256.update(data);
+
+        if let Some(md5) = &mut self.md5 {
+            md5.update(data);
+        }
+    }
+
+    pub(crate) fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    pub(crate) fn verify(self, expected: &Checksum) -> Option<String> {
+        let sha256 = format!("{:x}", self.sha256.finalize());
+        let md5 = self.md5.map(|md5| format!("{:x}", md5.finalize()));
+
+        let verified = expected.length.is_none_or(|length| length == self.length)
+            && (expected.md5.is_none() || expected.md5 == md5)
+            && expected
+                .sha256
+                .as_ref()
+                .is_none_or(|expected| *expected == sha256);
+
+        verified.then_some(sha256)
+    }
+}
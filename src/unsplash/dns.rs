@@ -0,0 +1,148 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::ACCEPT,
+    Client as HttpClient, Url,
+};
+use serde::{Deserialize, Serialize};
+
+const DNS_JSON: &str = "application/dns-json";
+const RECORD_A: &str = "A";
+const RECORD_AAAA: &str = "AAAA";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpVersion {
+    #[default]
+    Any,
+    V4,
+    V6,
+    PreferV4,
+    PreferV6,
+}
+
+impl IpVersion {
+    fn record_types(self) -> &'static [&'static str] {
+        match self {
+            IpVersion::V4 => &[RECORD_A],
+            IpVersion::V6 => &[RECORD_AAAA],
+            IpVersion::Any | IpVersion::PreferV4 => &[RECORD_A, RECORD_AAAA],
+            IpVersion::PreferV6 => &[RECORD_AAAA, RECORD_A],
+        }
+    }
+
+    fn order(self, mut addrs: Vec<IpAddr>) -> Vec<IpAddr> {
+        match self {
+            IpVersion::Any => {}
+            IpVersion::V4 => addrs.retain(IpAddr::is_ipv4),
+            IpVersion::V6 => addrs.retain(IpAddr::is_ipv6),
+            IpVersion::PreferV4 => addrs.sort_by_key(IpAddr::is_ipv6),
+            IpVersion::PreferV6 => addrs.sort_by_key(IpAddr::is_ipv4),
+        }
+
+        addrs
+    }
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    data: String,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Resolver {
+    ip_version: IpVersion,
+    doh: Option<(HttpClient, Url)>,
+}
+
+impl Resolver {
+    pub(super) fn new(ip_version: IpVersion, doh: Option<(HttpClient, Url)>) -> Self {
+        Self { ip_version, doh }
+    }
+
+    pub(super) async fn lookup(&self, name: &str) -> io::Result<Vec<IpAddr>> {
+        let addrs = match &self.doh {
+            Some((http, url)) => match self.ip_version.record_types() {
+                [first, second] => {
+                    let (first, second) = tokio::join!(
+                        lookup_doh(http, url, name, first),
+                        lookup_doh(http, url, name, second)
+                    );
+
+                    [first?, second?].concat()
+                }
+
+                record_types => {
+                    let mut addrs = Vec::new();
+                    for record_type in record_types {
+                        addrs.extend(lookup_doh(http, url, name, record_type).await?);
+                    }
+                    addrs
+                }
+            },
+
+            None => tokio::net::lookup_host((name, 0))
+                .await?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+
+        let addrs = self.ip_version.order(addrs);
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has no {:?} addresses", name, self.ip_version),
+            ));
+        }
+
+        Ok(addrs)
+    }
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+
+            Ok(addrs)
+        })
+    }
+}
+
+async fn lookup_doh(
+    http: &HttpClient,
+    url: &Url,
+    name: &str,
+    record_type: &str,
+) -> io::Result<Vec<IpAddr>> {
+    let response: DohResponse = http
+        .get(url.clone())
+        .query(&[("name", name), ("type", record_type)])
+        .header(ACCEPT, DNS_JSON)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(io::Error::other)?
+        .json()
+        .await
+        .map_err(io::Error::other)?;
+
+    Ok(response
+        .answer
+        .iter()
+        .filter_map(|answer| answer.data.parse().ok())
+        .collect())
+}
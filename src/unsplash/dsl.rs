@@ -0,0 +1,126 @@
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use super::{Fetch, Mode, Query, Search, WeightedQuery};
+
+#[derive(Debug, Error)]
+pub enum ParseQueryError {
+    #[error("Unknown query filter `{0}`")]
+    UnknownFilter(String),
+
+    #[error("Invalid value `{value}` for query filter `{filter}`")]
+    InvalidValue { filter: String, value: String },
+
+    #[error("Unterminated quote in query")]
+    UnterminatedQuote,
+}
+
+impl Fetch {
+    pub fn apply_query(&mut self, input: &str) -> Result<(), ParseQueryError> {
+        let mut topics = Vec::new();
+        let mut combined = Vec::new();
+        let mut words = Vec::new();
+        let mut excluded = Vec::new();
+
+        for token in tokenize(input)? {
+            if let Some(keyword) = token
+                .strip_prefix('-')
+                .filter(|keyword| !keyword.is_empty())
+            {
+                excluded.push(keyword.to_owned());
+                continue;
+            }
+
+            let Some((filter, value)) = token.split_once(':') else {
+                words.push(token);
+                continue;
+            };
+
+            let invalid = || ParseQueryError::InvalidValue {
+                filter: filter.to_owned(),
+                value: value.to_owned(),
+            };
+
+            match filter {
+                "topic" => topics.push(value.to_owned()),
+                "exclude" => excluded.push(value.to_owned()),
+                "topics" => combined.push(value.split(',').map(ToOwned::to_owned).collect()),
+                "text" | "query" => words.push(value.to_owned()),
+                "count" => self.count = value.parse().map_err(|_| invalid())?,
+                "orientation" => self.orientation = parse_variant(value).ok_or_else(invalid)?,
+                "content" => self.content_filter = parse_variant(value).ok_or_else(invalid)?,
+                "min-likes" => self.min_likes = Some(value.parse().map_err(|_| invalid())?),
+                "min-width" => self.min_width = Some(value.parse().map_err(|_| invalid())?),
+                "min-height" => self.min_height = Some(value.parse().map_err(|_| invalid())?),
+                "color" => {
+                    self.search_mut();
+                    self.color = Some(parse_variant(value).ok_or_else(invalid)?)
+                }
+                "order" => {
+                    self.search_mut().order_by = Some(parse_variant(value).ok_or_else(invalid)?)
+                }
+                "page" => self.search_mut().page = value.parse().map_err(|_| invalid())?,
+                _ => return Err(ParseQueryError::UnknownFilter(filter.to_owned())),
+            }
+        }
+
+        if !words.is_empty() || !topics.is_empty() || !combined.is_empty() {
+            self.queries = topics
+                .into_iter()
+                .map(Query::Topic)
+                .chain(combined.into_iter().map(Query::Topics))
+                .chain((!words.is_empty()).then(|| Query::Text(words.join(" "))))
+                .map(|query| WeightedQuery { query, weight: 1 })
+                .collect();
+        }
+
+        if !excluded.is_empty() {
+            self.exclude = excluded;
+        }
+
+        Ok(())
+    }
+
+    fn search_mut(&mut self) -> &mut Search {
+        if let Mode::Random = self.mode {
+            self.mode = Mode::Search(Default::default());
+        }
+
+        match &mut self.mode {
+            Mode::Search(search) => search,
+            Mode::Random => unreachable!(),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>, ParseQueryError> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(ParseQueryError::UnterminatedQuote);
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_variant<T: DeserializeOwned>(value: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(value.replace('-', "_"))).ok()
+}
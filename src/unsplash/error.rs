@@ -1,19 +1,170 @@
+use std::io;
+
+use chrono::{DateTime, Local, Utc};
 use reqwest::StatusCode;
+use serde::Deserialize;
 use thiserror::Error;
 
+use super::Limit;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Missing or invalid access key")]
     InvalidApiKey,
 
-    #[error("Failed to parse response")]
-    InvalidResponse,
+    #[error("Failed to parse the response from {url}: {}", causes(source.as_ref()))]
+    InvalidResponse {
+        url: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("Failed to create the HTTP client: {}", causes(.0))]
+    HttpClient(#[source] reqwest::Error),
+
+    #[error("The request could not be built")]
+    InvalidRequest,
+
+    #[error("Failed to send request to {url}: {}", causes(source))]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("TLS verification failed for {url}: {reason}")]
+    Tls { url: String, reason: String },
+
+    #[error("HTTP status {status} from {url} (retried {retries} times)")]
+    Status {
+        status: StatusCode,
+        url: String,
+        retries: u32,
+    },
+
+    #[error("Unsplash rejected the request to {url} ({status}): {}", messages.join("; "))]
+    Api {
+        status: StatusCode,
+        url: String,
+        messages: Vec<String>,
+    },
+
+    #[error("The hourly Unsplash request quota is exhausted")]
+    RateLimitExceeded,
+
+    #[error("The hourly request budget is spent until {}", .0.with_timezone(&Local).format("%H:%M"))]
+    BudgetSpent(DateTime<Utc>),
+
+    #[error("The {0} limit for this run has been reached")]
+    LimitReached(Limit),
+
+    #[error("Downloaded photo {0} does not match its checksum")]
+    ChecksumMismatch(String),
+
+    #[error("Downloaded photo {0} is not a valid image")]
+    InvalidImage(String),
+
+    #[error("Downloaded photo {id} is only {bytes} bytes")]
+    TooSmall { id: String, bytes: u64 },
+
+    #[error("{0}")]
+    Io(#[from] io::Error),
 
-    #[error("Failed to send request")]
-    Request,
+    #[cfg(feature = "keyring")]
+    #[error("{0}")]
+    Keyring(#[from] keyring::Error),
+}
+
+impl Error {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::InvalidApiKey => Some(StatusCode::UNAUTHORIZED),
+            Error::Status { status, .. } | Error::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Error::Request { .. } | Error::RateLimitExceeded | Error::BudgetSpent(_) => true,
+            Error::Status { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn request(url: String, source: reqwest::Error) -> Self {
+        match tls_error(&source) {
+            Some(tls) => Error::Tls {
+                url,
+                reason: tls.to_string(),
+            },
+            None => Error::Request {
+                url,
+                source: source.without_url(),
+            },
+        }
+    }
+
+    pub fn broken_photo(&self) -> Option<&str> {
+        match self {
+            Error::ChecksumMismatch(id) | Error::InvalidImage(id) | Error::TooSmall { id, .. } => {
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        if let Error::Tls { .. } = self {
+            return Some(
+                "If a proxy intercepts TLS traffic, add its root certificate to network.root_certificates",
+            );
+        }
+
+        match self.status()? {
+            StatusCode::BAD_REQUEST => Some("Check the query filters in the configuration"),
+            StatusCode::UNAUTHORIZED => {
+                Some("Check that UNSPLASH_API_KEY in the .env file is a valid access key")
+            }
+            StatusCode::FORBIDDEN => Some("The access key is not allowed to perform this request"),
+            StatusCode::NOT_FOUND => {
+                Some("Check that the configured topic, collection or photo id exists")
+            }
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn tls_error(error: &reqwest::Error) -> Option<&native_tls::Error> {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(tls) = error.downcast_ref::<native_tls::Error>() {
+            return Some(tls);
+        }
+        source = error.source();
+    }
+
+    None
+}
+
+fn causes(error: &dyn std::error::Error) -> String {
+    let mut causes = error.to_string();
+
+    let mut source = error.source();
+    while let Some(error) = source {
+        causes.push_str(": ");
+        causes.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    causes
+}
 
-    #[error("HTTP status: {0}")]
-    Status(StatusCode),
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApiErrors {
+    pub errors: Vec<String>,
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -0,0 +1,179 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Photo;
+use crate::template::{self, Template};
+
+const PLACEHOLDERS: &[&str] = &[
+    "id", "user", "name", "date", "time", "width", "height", "ext",
+];
+
+#[derive(Debug, Error)]
+pub enum ParseTemplateError {
+    #[error("Unknown placeholder `{{{0}}}` in filename template")]
+    UnknownPlaceholder(String),
+
+    #[error("Unterminated placeholder in filename template")]
+    Unterminated,
+
+    #[error("The filename template must end with `.{{ext}}`")]
+    MissingExtension,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Naming {
+    #[serde(default)]
+    pub transliterate: bool,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+impl Naming {
+    fn apply(&self, value: &str) -> String {
+        let value = if self.transliterate {
+            deunicode::deunicode_with_tofu(value, "_")
+        } else {
+            value.to_owned()
+        };
+
+        match self.max_length {
+            Some(max_length) if value.chars().count() > max_length => value
+                .chars()
+                .take(max_length)
+                .collect::<String>()
+                .trim_end_matches([' ', '.', '-', '_'])
+                .to_owned(),
+            _ => value,
+        }
+    }
+}
+
+impl From<template::ParseError> for ParseTemplateError {
+    fn from(err: template::ParseError) -> Self {
+        match err {
+            template::ParseError::UnknownPlaceholder(placeholder) => {
+                ParseTemplateError::UnknownPlaceholder(placeholder)
+            }
+            template::ParseError::Unterminated => ParseTemplateError::Unterminated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilenameTemplate(Template);
+
+impl FilenameTemplate {
+    pub fn parse(template: &str) -> Result<Self, ParseTemplateError> {
+        let parsed = Template::parse(template, PLACEHOLDERS)?;
+
+        if !template.ends_with(".{ext}") {
+            return Err(ParseTemplateError::MissingExtension);
+        }
+
+        Ok(Self(parsed))
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.0.contains("id")
+    }
+
+    pub fn render(
+        &self,
+        photo: &Photo,
+        extension: &str,
+        date: DateTime<Local>,
+        naming: &Naming,
+    ) -> String {
+        self.0.render(|placeholder| {
+            let value = match placeholder {
+                "id" => photo.id().to_owned(),
+                "user" => naming.apply(photo.user().username()),
+                "name" => naming.apply(photo.user().name()),
+                "date" => date.format("%Y-%m-%d").to_string(),
+                "time" => date.format("%H%M%S").to_string(),
+                "width" => photo.width().to_string(),
+                "height" => photo.height().to_string(),
+                "ext" => extension.to_owned(),
+                _ => unreachable!(),
+            };
+
+            value.chars().map(sanitize).collect()
+        })
+    }
+
+    pub fn path<P: AsRef<Path>>(
+        &self,
+        folder: P,
+        photo: &Photo,
+        extension: &str,
+        naming: &Naming,
+        taken: &mut HashSet<PathBuf>,
+    ) -> PathBuf {
+        let path = folder
+            .as_ref()
+            .join(self.render(photo, extension, Local::now(), naming));
+        let path = if self.is_unique() {
+            path
+        } else {
+            available_path(path, taken)
+        };
+
+        taken.insert(path.clone());
+        path
+    }
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        Self::parse("{id}.{ext}").unwrap()
+    }
+}
+
+impl fmt::Display for FilenameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl TryFrom<String> for FilenameTemplate {
+    type Error = ParseTemplateError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        Self::parse(&template)
+    }
+}
+
+impl From<FilenameTemplate> for String {
+    fn from(template: FilenameTemplate) -> Self {
+        template.0.as_str().to_owned()
+    }
+}
+
+fn sanitize(c: char) -> char {
+    match c {
+        '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+        c if c.is_control() => '_',
+        c => c,
+    }
+}
+
+fn available_path(path: PathBuf, taken: &HashSet<PathBuf>) -> PathBuf {
+    if !path.exists() && !taken.contains(&path) {
+        return path;
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !path.exists() && !taken.contains(path))
+        .unwrap()
+}
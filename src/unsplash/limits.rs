@@ -0,0 +1,91 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Limits {
+    #[serde(default)]
+    pub max_requests: Option<u32>,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub max_duration_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Requests,
+    Bytes,
+    Duration,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Requests => "request",
+            Limit::Bytes => "download size",
+            Limit::Duration => "duration",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Usage {
+    started: Instant,
+    requests: AtomicU32,
+    bytes: AtomicU64,
+}
+
+impl Usage {
+    pub fn requests(&self) -> u32 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub(crate) fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn exceeded(&self, limits: &Limits) -> Option<Limit> {
+        if limits
+            .max_requests
+            .is_some_and(|max| self.requests() >= max)
+        {
+            Some(Limit::Requests)
+        } else if limits.max_bytes.is_some_and(|max| self.bytes() >= max) {
+            Some(Limit::Bytes)
+        } else if limits
+            .max_duration_minutes
+            .is_some_and(|max| self.elapsed() >= Duration::from_secs(max * 60))
+        {
+            Some(Limit::Duration)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Usage {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: Default::default(),
+            bytes: Default::default(),
+        }
+    }
+}
@@ -1,27 +1,93 @@
-use std::env;
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    env, fmt, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use bytes::Bytes;
+use chrono::Utc;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Client as HttpClient, RequestBuilder, Response,
+    header::{ETAG, IF_NONE_MATCH, RETRY_AFTER},
+    Client as HttpClient, RequestBuilder, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
 };
-use serde::{Deserialize, Serialize};
-use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+
+use crate::{display, redact::Redacted};
 
 mod models;
-pub use models::Photo;
-use models::Topic;
+use models::SearchResults;
+pub use models::{Exif, Location, Photo, Topic, User};
+
+mod batch;
+pub use batch::{BatchReport, PART_EXTENSION};
+
+mod budget;
+pub use budget::RequestBudget;
+
+mod builder;
+pub use builder::{ClientBuilder, Network};
+
+mod dns;
+pub use dns::IpVersion;
+
+mod cache;
+use cache::CachedResponse;
+pub use cache::ResponseCache;
+
+mod checksum;
+use checksum::{Checksum, Hasher};
+
+mod verify;
+
+mod filename;
+pub use filename::{FilenameTemplate, Naming, ParseTemplateError};
+
+mod dsl;
+pub use dsl::ParseQueryError;
 
 mod error;
+use error::ApiErrors;
 pub use error::{Error, Result};
 
+mod limits;
+pub use limits::{Limit, Limits, Usage};
+
+mod progress;
+pub use progress::ProgressEvent;
+
+mod tracking;
+pub use tracking::TrackQueue;
+
+mod rate_limit;
+pub use rate_limit::RateLimit;
+
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "backdrop";
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "unsplash";
+
+#[cfg(feature = "keyring")]
+fn keyring_entry() -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?)
+}
+
+const API_URL: &str = "https://api.unsplash.com";
+
 macro_rules! unsplash_api {
-    ($end_point:expr) => {
-        concat!("https://api.unsplash.com", $end_point)
+    ($client:expr, $end_point:expr) => {
+        format!("{}{}", $client.base_url, $end_point)
     };
 
-    ($end_point:expr, $($arg:expr),+) => {
-        format!(unsplash_api!($end_point), $($arg),+)
+    ($client:expr, $end_point:expr, $($arg:expr),+) => {
+        format!("{}{}", $client.base_url, format!($end_point, $($arg),+))
     };
 }
 
@@ -33,6 +99,10 @@ macro_rules! query_params {
     };
 }
 
+const MAX_PER_PAGE: u32 = 30;
+const MAX_REFETCHES: u32 = 2;
+const REGULAR_WIDTH: u32 = 1080;
+
 type QueryParam = (&'static str, String);
 
 trait ToQueryParams {
@@ -44,32 +114,432 @@ trait ToQueryParams {
 pub enum Query {
     Text(String),
     Topic(String),
+    Topics(Vec<String>),
+}
+
+impl Query {
+    pub fn slug(&self) -> String {
+        let text = match self {
+            Query::Text(text) | Query::Topic(text) => text.clone(),
+            Query::Topics(topics) => topics.join("-"),
+        };
+
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedQuery {
+    #[serde(flatten)]
+    pub query: Query,
+    #[serde(default = "WeightedQuery::default_weight")]
+    pub weight: u32,
+}
+
+impl WeightedQuery {
+    fn default_weight() -> u32 {
+        1
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Landscape,
+    Portrait,
+    Squarish,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilter {
+    #[default]
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    Relevant,
+    Latest,
+    Oldest,
+    Popular,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicOrder {
+    Featured,
+    Latest,
+    Oldest,
+    #[default]
+    Position,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    BlackAndWhite,
+    Black,
+    White,
+    Yellow,
+    Orange,
+    Red,
+    Purple,
+    Magenta,
+    Green,
+    Teal,
+    Blue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Search {
+    #[serde(default)]
+    pub order_by: Option<OrderBy>,
+    #[serde(default = "Search::first_page")]
+    pub page: u32,
+}
+
+impl Search {
+    fn first_page() -> u32 {
+        1
+    }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            order_by: None,
+            page: Self::first_page(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Random,
+    Search,
+    Topic,
+    List,
+}
+
+impl Endpoint {
+    pub fn for_query(mode: &Mode, query: Option<&Query>) -> Self {
+        match (mode, query) {
+            (Mode::Random, _) => Endpoint::Random,
+            (Mode::Search(_), Some(Query::Text(_))) => Endpoint::Search,
+            (Mode::Search(_), Some(Query::Topic(_))) => Endpoint::Topic,
+            (Mode::Search(_), Some(Query::Topics(topics))) if !topics.is_empty() => Endpoint::Topic,
+            (Mode::Search(_), Some(Query::Topics(_)) | None) => Endpoint::List,
+        }
+    }
+
+    pub fn supports_color(self) -> bool {
+        self == Endpoint::Search
+    }
+
+    pub fn supports_orientation(self) -> bool {
+        matches!(self, Endpoint::Random | Endpoint::Search | Endpoint::Topic)
+    }
+
+    pub fn supports_content_filter(self) -> bool {
+        matches!(self, Endpoint::Random | Endpoint::Search)
+    }
+
+    pub fn supports_order(self, order_by: OrderBy) -> bool {
+        match self {
+            Endpoint::Random => false,
+            Endpoint::Search => matches!(order_by, OrderBy::Relevant | OrderBy::Latest),
+            Endpoint::Topic | Endpoint::List => {
+                matches!(
+                    order_by,
+                    OrderBy::Latest | OrderBy::Oldest | OrderBy::Popular
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Endpoint::Random => "random photos",
+            Endpoint::Search => "text searches",
+            Endpoint::Topic => "topic listings",
+            Endpoint::List => "photo listings",
+        })
+    }
+}
+
+impl Search {
+    fn query_params_for(&self, endpoint: Endpoint) -> Vec<QueryParam> {
+        let mut params = Vec::new();
+
+        if let Some(order_by) = self
+            .order_by
+            .filter(|&order_by| endpoint.supports_order(order_by))
+        {
+            params.extend_from_slice(query_params!(
+                "order_by" => serde_variant(&order_by),
+            ));
+        }
+
+        params
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Random,
+    Search(Search),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "FetchRepr")]
 pub struct Fetch {
     pub count: u32,
-    pub query: Option<Query>,
+    #[serde(default)]
+    pub orientation: Orientation,
+    #[serde(default)]
+    pub content_filter: ContentFilter,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub queries: Vec<WeightedQuery>,
+    #[serde(default)]
+    pub min_likes: Option<u32>,
+    #[serde(default)]
+    pub min_width: Option<u32>,
+    #[serde(default)]
+    pub min_height: Option<u32>,
+    #[serde(default)]
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FetchRepr {
+    count: u32,
+    #[serde(default)]
+    orientation: Orientation,
+    #[serde(default)]
+    content_filter: ContentFilter,
+    #[serde(default)]
+    mode: Mode,
+    #[serde(default)]
+    query: Option<Query>,
+    #[serde(default)]
+    queries: Vec<WeightedQuery>,
+    #[serde(default)]
+    min_likes: Option<u32>,
+    #[serde(default)]
+    min_width: Option<u32>,
+    #[serde(default)]
+    min_height: Option<u32>,
+    #[serde(default)]
+    color: Option<Color>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+impl From<FetchRepr> for Fetch {
+    fn from(repr: FetchRepr) -> Self {
+        let mut queries = repr.queries;
+        if let Some(query) = repr.query {
+            queries.insert(
+                0,
+                WeightedQuery {
+                    query,
+                    weight: WeightedQuery::default_weight(),
+                },
+            );
+        }
+
+        Self {
+            count: repr.count,
+            orientation: repr.orientation,
+            content_filter: repr.content_filter,
+            mode: repr.mode,
+            queries,
+            min_likes: repr.min_likes,
+            min_width: repr.min_width,
+            min_height: repr.min_height,
+            color: repr.color,
+            exclude: repr.exclude,
+        }
+    }
+}
+
+impl Fetch {
+    pub fn split_count(&self) -> Vec<(Option<&Query>, u32)> {
+        let total_weight: u64 = self.queries.iter().map(|query| query.weight as u64).sum();
+        if total_weight == 0 {
+            return vec![(None, self.count)];
+        }
+
+        let mut shares: Vec<_> = self
+            .queries
+            .iter()
+            .map(|query| {
+                let exact = self.count as u64 * query.weight as u64;
+
+                (
+                    &query.query,
+                    (exact / total_weight) as u32,
+                    exact % total_weight,
+                )
+            })
+            .collect();
+
+        let assigned: u32 = shares.iter().map(|(_, count, _)| count).sum();
+
+        let mut order: Vec<_> = (0..shares.len()).collect();
+        order.sort_by_key(|&i| Reverse(shares[i].2));
+        for &i in order.iter().take((self.count - assigned) as usize) {
+            shares[i].1 += 1;
+        }
+
+        shares
+            .into_iter()
+            .filter(|(_, count, _)| *count > 0)
+            .map(|(query, count, _)| (Some(query), count))
+            .collect()
+    }
+
+    pub fn estimated_requests(&self) -> u32 {
+        self.split_count()
+            .into_iter()
+            .map(|(query, count)| {
+                let pages = count.div_ceil(MAX_PER_PAGE);
+                let requests = match (&self.mode, query) {
+                    (Mode::Random, Some(Query::Topic(_))) => 2 * pages,
+                    (Mode::Random, Some(Query::Topics(topics))) => {
+                        (1 + topics.len() as u32) * pages
+                    }
+                    (Mode::Search(_), Some(Query::Topics(topics))) => {
+                        pages.max(topics.len() as u32)
+                    }
+                    _ => pages,
+                };
+
+                requests + count
+            })
+            .sum()
+    }
+
+    fn accepts(&self, photo: &Photo) -> bool {
+        self.min_likes.is_none_or(|min| photo.likes() >= min)
+            && self.min_width.is_none_or(|min| photo.width() >= min)
+            && self.min_height.is_none_or(|min| photo.height() >= min)
+            && !self.excludes(photo)
+    }
+
+    fn excludes(&self, photo: &Photo) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+
+        let text = [photo.description(), photo.alt_description()]
+            .into_iter()
+            .flatten()
+            .chain(photo.tags())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        self.exclude
+            .iter()
+            .any(|keyword| text.contains(&keyword.to_lowercase()))
+    }
 }
 
 impl Default for Fetch {
     fn default() -> Self {
         Self {
             count: 10,
-            query: None,
+            orientation: Orientation::Landscape,
+            content_filter: ContentFilter::Low,
+            mode: Mode::Random,
+            queries: Vec::new(),
+            min_likes: None,
+            min_width: None,
+            min_height: None,
+            color: None,
+            exclude: Vec::new(),
         }
     }
 }
 
-impl ToQueryParams for Fetch {
-    fn to_query_params(&self) -> Vec<QueryParam> {
-        let params = Vec::from(query_params!(
-            "count" => self.count,
-            "orientation" => "landscape",
-        ));
+impl Fetch {
+    fn query_params_for(&self, endpoint: Endpoint) -> Vec<QueryParam> {
+        let mut params = Vec::new();
+
+        if endpoint.supports_content_filter() {
+            params.extend_from_slice(query_params!(
+                "content_filter" => serde_variant(&self.content_filter),
+            ));
+        }
+
+        if endpoint.supports_orientation() && !matches!(self.orientation, Orientation::Any) {
+            params.extend_from_slice(query_params!(
+                "orientation" => serde_variant(&self.orientation),
+            ));
+        }
+
+        if let Some(color) = self.color.filter(|_| endpoint.supports_color()) {
+            params.extend_from_slice(query_params!(
+                "color" => serde_variant(&color),
+            ));
+        }
 
         params
     }
+
+    /// Returns the filters that are set but can't be honored by `endpoint`.
+    pub fn ignored_filters(&self, endpoint: Endpoint) -> Vec<&'static str> {
+        let mut ignored = Vec::new();
+        if self.color.is_some() && !endpoint.supports_color() {
+            ignored.push("color");
+        }
+        if !matches!(self.orientation, Orientation::Any) && !endpoint.supports_orientation() {
+            ignored.push("orientation");
+        }
+        if matches!(self.content_filter, ContentFilter::High) && !endpoint.supports_content_filter()
+        {
+            ignored.push("content_filter");
+        }
+        if let Mode::Search(search) = &self.mode
+            && search
+                .order_by
+                .is_some_and(|order_by| !endpoint.supports_order(order_by))
+        {
+            ignored.push("mode.order_by");
+        }
+
+        ignored
+    }
+
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        let mut endpoints = Vec::new();
+        for (query, _) in self.split_count() {
+            let endpoint = Endpoint::for_query(&self.mode, query);
+            if !endpoints.contains(&endpoint) {
+                endpoints.push(endpoint);
+            }
+        }
+
+        endpoints
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,88 +547,542 @@ impl ToQueryParams for Fetch {
 pub enum Format {
     Png,
     Jpeg { quality: u8 },
+    Webp { quality: u8 },
+    Avif { quality: u8 },
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg { .. } => "jpg",
+            Format::Webp { .. } => "webp",
+            Format::Avif { .. } => "avif",
+        }
+    }
+
+    fn quality(&self) -> Option<u8> {
+        match *self {
+            Format::Png => None,
+            Format::Jpeg { quality } | Format::Webp { quality } | Format::Avif { quality } => {
+                Some(quality)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged, rename_all = "snake_case")]
+#[serde(from = "ResolutionRepr", into = "ResolutionRepr")]
 pub enum Resolution {
     Raw,
+    Regular,
     Custom { width: u32, height: u32 },
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedResolution {
+    Raw,
+    Regular,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ResolutionRepr {
+    Named(NamedResolution),
+    Custom { width: u32, height: u32 },
+    Null(()),
+}
+
+impl From<ResolutionRepr> for Resolution {
+    fn from(repr: ResolutionRepr) -> Self {
+        match repr {
+            ResolutionRepr::Named(NamedResolution::Raw) | ResolutionRepr::Null(()) => Self::Raw,
+            ResolutionRepr::Named(NamedResolution::Regular) => Self::Regular,
+            ResolutionRepr::Custom { width, height } => Self::Custom { width, height },
+        }
+    }
+}
+
+impl From<Resolution> for ResolutionRepr {
+    fn from(resolution: Resolution) -> Self {
+        match resolution {
+            Resolution::Raw => Self::Named(NamedResolution::Raw),
+            Resolution::Regular => Self::Named(NamedResolution::Regular),
+            Resolution::Custom { width, height } => Self::Custom { width, height },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Download {
     pub format: Format,
     pub resolution: Resolution,
+    #[serde(default)]
+    pub filename_template: FilenameTemplate,
+    #[serde(default)]
+    pub naming: Naming,
+    #[serde(default)]
+    pub subfolders: bool,
+    #[serde(default)]
+    pub slow_fallback: Option<SlowFallback>,
+    #[serde(default = "Download::default_min_bytes")]
+    pub min_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowFallback {
+    pub time_budget_secs: u64,
+    #[serde(default = "SlowFallback::default_after")]
+    pub after: u32,
+    #[serde(default = "SlowFallback::default_resolution")]
+    pub resolution: Resolution,
+}
+
+impl SlowFallback {
+    fn default_after() -> u32 {
+        2
+    }
+
+    fn default_resolution() -> Resolution {
+        Resolution::Regular
+    }
+
+    pub fn time_budget(&self) -> Duration {
+        Duration::from_secs(self.time_budget_secs)
+    }
 }
 
 impl Default for Download {
     fn default() -> Self {
         Self {
             format: Format::Png,
-            resolution: unsafe {
-                Resolution::Custom {
-                    width: GetSystemMetrics(SM_CXSCREEN) as u32,
-                    height: GetSystemMetrics(SM_CYSCREEN) as u32,
-                }
-            },
+            resolution: display::primary_resolution()
+                .map(|(width, height)| Resolution::Custom { width, height })
+                .unwrap_or(Resolution::Raw),
+            filename_template: Default::default(),
+            naming: Default::default(),
+            subfolders: false,
+            slow_fallback: None,
+            min_bytes: Self::default_min_bytes(),
         }
     }
 }
 
+impl Download {
+    fn default_min_bytes() -> u64 {
+        1024
+    }
+
+    pub fn url(&self, photo: &Photo) -> String {
+        Url::parse_with_params(photo.file_url(), self.to_query_params())
+            .map_or_else(|_| photo.file_url().to_owned(), String::from)
+    }
+
+    pub fn path<P: AsRef<Path>>(
+        &self,
+        folder: P,
+        photo: &Photo,
+        taken: &mut HashSet<PathBuf>,
+    ) -> PathBuf {
+        self.path_with_extension(folder, photo, self.format.extension(), taken)
+    }
+
+    pub fn path_with_extension<P: AsRef<Path>>(
+        &self,
+        folder: P,
+        photo: &Photo,
+        extension: &str,
+        taken: &mut HashSet<PathBuf>,
+    ) -> PathBuf {
+        let mut folder = folder.as_ref().to_owned();
+        if self.subfolders
+            && let Some(slug) = photo
+                .query()
+                .map(Query::slug)
+                .filter(|slug| !slug.is_empty())
+        {
+            folder.push(slug);
+        }
+
+        self.filename_template
+            .path(folder, photo, extension, &self.naming, taken)
+    }
+}
+
 impl ToQueryParams for Download {
     fn to_query_params(&self) -> Vec<QueryParam> {
         let mut params = Vec::from(query_params!(
-            "fm" => "png",
+            "fm" => self.format.extension(),
         ));
 
-        if let Resolution::Custom { width, height } = self.resolution {
+        if let Some(quality) = self.format.quality() {
             params.extend_from_slice(query_params!(
+                "q" => quality,
+            ))
+        }
+
+        match self.resolution {
+            Resolution::Raw => {}
+
+            Resolution::Regular => params.extend_from_slice(query_params!(
+                "w" => REGULAR_WIDTH,
+            )),
+
+            Resolution::Custom { width, height } => params.extend_from_slice(query_params!(
                 "w" => width,
                 "h" => height,
                 "fit" => "min",
-            ))
+            )),
         }
 
         params
     }
 }
 
+fn serde_variant<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(variant)) => variant,
+        _ => unreachable!(),
+    }
+}
+
+async fn parse_json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let url = response.url().to_string();
+
+    response
+        .json()
+        .await
+        .map_err(|source| Error::InvalidResponse {
+            url,
+            source: source.without_url().into(),
+        })
+}
+
+async fn status_error(response: Response, url: String, retries: u32) -> Error {
+    let status = response.status();
+
+    match response.json::<ApiErrors>().await {
+        Ok(body) if !body.errors.is_empty() => Error::Api {
+            status,
+            url,
+            messages: body.errors,
+        },
+        _ => Error::Status {
+            status,
+            url,
+            retries,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    #[serde(default = "RetryPolicy::default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "RetryPolicy::default_base_delay_ms")]
+    pub base_delay_ms: u64,
+    #[serde(default = "RetryPolicy::default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    fn default_max_attempts() -> u32 {
+        3
+    }
+
+    fn default_base_delay_ms() -> u64 {
+        1000
+    }
+
+    fn default_max_delay_ms() -> u64 {
+        60 * 1000
+    }
+
+    fn backoff(&self, retries: u32) -> Duration {
+        Duration::from_millis(self.base_delay_ms.saturating_mul(1 << retries.min(16)))
+    }
+
+    fn delay(&self, retry_after: Option<Duration>, retries: u32) -> Duration {
+        retry_after
+            .unwrap_or_else(|| self.backoff(retries))
+            .min(Duration::from_millis(self.max_delay_ms))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::default_max_attempts(),
+            base_delay_ms: Self::default_base_delay_ms(),
+            max_delay_ms: Self::default_max_delay_ms(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     http: HttpClient,
+    base_url: String,
+    retry: RetryPolicy,
+    limits: Limits,
+    usage: Arc<Usage>,
+    progress: Option<mpsc::UnboundedSender<ProgressEvent>>,
+    cache: Option<Arc<Mutex<ResponseCache>>>,
+    tracks: Option<Arc<Mutex<TrackQueue>>>,
+    budget: Option<Arc<Mutex<RequestBudget>>>,
+    shared_cache: Option<PathBuf>,
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
 }
 
 impl Client {
-    pub fn new(api_key: &str) -> Result<Self> {
-        let auth = format!("Client-ID {}", api_key);
-        let mut auth = HeaderValue::from_str(&auth).map_err(|_| Error::InvalidApiKey)?;
-        auth.set_sensitive(true);
-
-        let mut headers = HeaderMap::new();
-        headers.insert("Authorization", auth);
-
-        Ok(Self {
-            http: HttpClient::builder()
-                .default_headers(headers)
-                .build()
-                .unwrap(),
-        })
+    pub fn new(api_key: &Redacted<String>) -> Result<Self> {
+        Self::builder(api_key).build()
+    }
+
+    pub fn builder(api_key: &Redacted<String>) -> ClientBuilder {
+        ClientBuilder::new(api_key)
+    }
+
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url.as_str().trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: mpsc::UnboundedSender<ProgressEvent>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(Arc::new(Mutex::new(cache)));
+        self
+    }
+
+    pub fn with_track_queue(mut self, tracks: TrackQueue) -> Self {
+        self.tracks = Some(Arc::new(Mutex::new(tracks)));
+        self
+    }
+
+    pub fn with_request_budget(mut self, budget: RequestBudget) -> Self {
+        self.budget = Some(Arc::new(Mutex::new(budget)));
+        self
+    }
+
+    pub fn with_shared_cache<P: AsRef<Path>>(mut self, folder: P) -> Self {
+        self.shared_cache = Some(folder.as_ref().to_owned());
+        self
+    }
+
+    async fn read_shared(&self, photo: &Photo, download: &Download) -> Option<Bytes> {
+        let path = self.shared_cache.as_ref()?.join(format!(
+            "{}.{}",
+            photo.id(),
+            download.format.extension()
+        ));
+
+        match fs::read(&path).await {
+            Ok(data) if download.format.matches_header(&data) => {
+                tracing::info!(id = photo.id(), path = %path.display(), "using the shared cache");
+
+                Some(Bytes::from(data))
+            }
+
+            Ok(_) => {
+                tracing::warn!(path = %path.display(), "ignoring an invalid image in the shared cache");
+
+                None
+            }
+
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "failed to read the shared cache");
+
+                None
+            }
+        }
+    }
+
+    pub async fn retry_pending_tracks(&self) -> Result<usize> {
+        let Some(tracks) = &self.tracks else {
+            return Ok(0);
+        };
+
+        let pending = tracks.lock().unwrap().pending();
+
+        let mut tracked = 0;
+        for (id, track) in pending {
+            let result = self.send_request(self.http.get(&track.url)).await;
+
+            let mut tracks = tracks.lock().unwrap();
+            match result {
+                Ok(_) => {
+                    tracing::info!(id, "sent the queued download ping");
+                    tracks.complete(&id);
+                    tracked += 1;
+                }
+
+                Err(Error::LimitReached(limit)) => return Err(Error::LimitReached(limit)),
+
+                Err(err) => {
+                    tracing::warn!(id, error = %err, "queued download ping failed again");
+                    tracks.fail(&id);
+                }
+            }
+            tracks.save()?;
+        }
+
+        Ok(tracked)
     }
 
     pub fn new_from_env() -> Result<Self> {
-        let api_key = env::var("UNSPLASH_API_KEY").map_err(|_| Error::InvalidApiKey)?;
+        Self::new(&Self::api_key_from_env()?)
+    }
+
+    pub fn api_key_from_env() -> Result<Redacted<String>> {
+        Ok(env::var("UNSPLASH_API_KEY")
+            .map_err(|_| Error::InvalidApiKey)?
+            .into())
+    }
+
+    #[cfg(feature = "keyring")]
+    pub fn new_from_keyring() -> Result<Self> {
+        Self::new(&Self::api_key_from_keyring()?)
+    }
+
+    #[cfg(feature = "keyring")]
+    pub fn api_key_from_keyring() -> Result<Redacted<String>> {
+        Ok(keyring_entry()?.get_password()?.into())
+    }
+
+    #[cfg(feature = "keyring")]
+    pub fn store_api_key(api_key: &Redacted<String>) -> Result<()> {
+        keyring_entry()?.set_password(api_key.expose())?;
+
+        Ok(())
+    }
 
-        Self::new(&api_key)
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    fn report_progress(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.send(event);
+        }
     }
 
     pub async fn fetch_photos(&self, fetch: &Fetch) -> Result<Vec<Photo>> {
+        let mut ids = HashSet::new();
+        let mut photos = Vec::new();
+
+        for (query, count) in fetch.split_count() {
+            let ignored = fetch.ignored_filters(Endpoint::for_query(&fetch.mode, query));
+            if !ignored.is_empty() {
+                tracing::warn!(?query, ?ignored, "the endpoint ignores these filters");
+            }
+
+            let round_robin = match (&fetch.mode, query) {
+                (Mode::Search(_), Some(Query::Topics(topics))) if !topics.is_empty() => {
+                    Some(topics)
+                }
+                _ => None,
+            };
+
+            let per_request =
+                round_robin.map_or(count, |topics| count.div_ceil(topics.len() as u32));
+            let max_requests = count
+                .div_ceil(MAX_PER_PAGE)
+                .max(count.div_ceil(per_request.max(1)))
+                + MAX_REFETCHES;
+
+            let mut accepted = 0;
+            for request in 0..max_requests {
+                let (batch, exhausted) = match &fetch.mode {
+                    Mode::Random => {
+                        let missing = (count - accepted).min(MAX_PER_PAGE);
+                        let batch = self.fetch_random_photos(fetch, query, missing).await?;
+
+                        (batch, false)
+                    }
+
+                    Mode::Search(search) => match round_robin {
+                        Some(topics) => {
+                            let topic =
+                                Query::Topic(topics[request as usize % topics.len()].clone());
+                            let page = search.page + request / topics.len() as u32;
+                            let batch = self.search_page(fetch, search, Some(&topic), page).await?;
+
+                            (batch, false)
+                        }
+
+                        None => {
+                            let page = search.page + request;
+                            let batch = self.search_page(fetch, search, query, page).await?;
+
+                            let exhausted = batch.len() < MAX_PER_PAGE as usize;
+                            (batch, exhausted)
+                        }
+                    },
+                };
+
+                let mut taken = 0;
+                for mut photo in batch {
+                    if accepted == count || taken == per_request {
+                        break;
+                    }
+
+                    if !fetch.accepts(&photo) || !ids.insert(photo.id().to_owned()) {
+                        continue;
+                    }
+
+                    photo.set_query(query.cloned());
+                    photos.push(photo);
+                    accepted += 1;
+                    taken += 1;
+                }
+
+                if accepted == count || exhausted {
+                    break;
+                }
+            }
+
+            tracing::info!(?query, requested = count, accepted, "fetched photos");
+        }
+
+        Ok(photos)
+    }
+
+    async fn fetch_random_photos(
+        &self,
+        fetch: &Fetch,
+        query: Option<&Query>,
+        count: u32,
+    ) -> Result<Vec<Photo>> {
         let mut request = self
             .http
-            .get(unsplash_api!("/photos/random"))
-            .query(&fetch.to_query_params());
+            .get(unsplash_api!(self, "/photos/random"))
+            .query(&fetch.query_params_for(Endpoint::Random))
+            .query(query_params!(
+                "count" => count
+            ));
 
-        if let Some(query) = &fetch.query {
+        if let Some(query) = query {
             match query {
                 Query::Text(text) => {
                     request = request.query(query_params!(
@@ -167,51 +1091,712 @@ impl Client {
                 }
 
                 Query::Topic(id_or_slug) => {
-                    let topic = self.find_topic(&id_or_slug).await?;
+                    let topic = self.find_topic(id_or_slug).await?;
                     request = request.query(query_params!(
                         "topics" => topic.id()
                     ));
                 }
+
+                Query::Topics(ids_or_slugs) => {
+                    let mut ids = Vec::new();
+                    for id_or_slug in ids_or_slugs {
+                        ids.push(self.find_topic(id_or_slug).await?.id().to_owned());
+                    }
+
+                    request = request.query(query_params!(
+                        "topics" => ids.join(",")
+                    ));
+                }
+            }
+        }
+
+        let response = self.send_request(request).await?;
+        let photos = parse_json(response).await?;
+
+        Ok(photos)
+    }
+
+    async fn search_page(
+        &self,
+        fetch: &Fetch,
+        search: &Search,
+        query: Option<&Query>,
+        page: u32,
+    ) -> Result<Vec<Photo>> {
+        let endpoint = Endpoint::for_query(&fetch.mode, query);
+        let request = match query {
+            Some(Query::Text(text)) => {
+                self.http
+                    .get(unsplash_api!(self, "/search/photos"))
+                    .query(query_params!(
+                        "query" => text
+                    ))
+            }
+
+            Some(Query::Topic(id_or_slug)) => {
+                self.http
+                    .get(unsplash_api!(self, "/topics/{}/photos", id_or_slug))
             }
+
+            Some(Query::Topics(_)) | None => self.http.get(unsplash_api!(self, "/photos")),
         }
+        .query(&fetch.query_params_for(endpoint))
+        .query(&search.query_params_for(endpoint))
+        .query(query_params!(
+            "page" => page,
+            "per_page" => MAX_PER_PAGE,
+        ));
 
-        let response = Self::send_request(request).await?;
-        let photos = response.json().await.map_err(|_| Error::InvalidResponse)?;
+        let response = self.send_request(request).await?;
+        let photos = match query {
+            Some(Query::Text(_)) => parse_json::<SearchResults>(response)
+                .await
+                .map(SearchResults::into_photos)?,
+
+            _ => parse_json(response).await?,
+        };
 
         Ok(photos)
     }
 
     pub async fn download_photo(&self, photo: &Photo, download: &Download) -> Result<Bytes> {
+        self.track_download(photo).await?;
+
+        if let Some(data) = self.read_shared(photo, download).await {
+            return Ok(data);
+        }
+
+        let mut attempts = 0;
+
+        loop {
+            let response = self.request_file(photo, download).await?;
+            let checksum = Checksum::from_headers(response.headers());
+            self.report_progress(ProgressEvent::Started {
+                id: photo.id().to_owned(),
+                total: response.content_length(),
+            });
+
+            let url = response.url().to_string();
+            let data = response.bytes().await.map_err(|source| Error::Request {
+                url,
+                source: source.without_url(),
+            });
+            if let Ok(data) = &data {
+                self.usage.record_bytes(data.len() as u64);
+                self.report_progress(ProgressEvent::Advanced {
+                    id: photo.id().to_owned(),
+                    bytes: data.len() as u64,
+                });
+            }
+            self.report_progress(ProgressEvent::Finished {
+                id: photo.id().to_owned(),
+            });
+
+            let data = data?;
+
+            let mut hasher = checksum.hasher();
+            hasher.update(&data);
+            let error = if hasher.verify(&checksum).is_none() {
+                Error::ChecksumMismatch(photo.id().to_owned())
+            } else if !download.format.matches_header(&data) {
+                Error::InvalidImage(photo.id().to_owned())
+            } else {
+                return Ok(data);
+            };
+
+            if attempts + 1 >= self.retry.max_attempts {
+                return Err(error);
+            }
+
+            self.wait_to_redownload(photo, &error, attempts).await;
+            attempts += 1;
+        }
+    }
+
+    pub async fn download_photo_to<W: AsyncWrite + Unpin>(
+        &self,
+        photo: &Photo,
+        download: &Download,
+        writer: &mut W,
+    ) -> Result<String> {
+        self.track_download(photo).await?;
+
+        self.write_photo_to(photo, download, writer).await
+    }
+
+    /// Writes the photo without pinging its download location, for retrying a download that
+    /// has already been tracked.
+    async fn write_photo_to<W: AsyncWrite + Unpin>(
+        &self,
+        photo: &Photo,
+        download: &Download,
+        writer: &mut W,
+    ) -> Result<String> {
+        if let Some(data) = self.read_shared(photo, download).await {
+            writer.write_all(&data).await?;
+            writer.flush().await?;
+
+            let mut hasher = Checksum::default().hasher();
+            hasher.update(&data);
+
+            return Ok(hasher.verify(&Checksum::default()).unwrap());
+        }
+
+        let mut response = self.request_file(photo, download).await?;
+
+        let checksum = Checksum::from_headers(response.headers());
+        self.report_progress(ProgressEvent::Started {
+            id: photo.id().to_owned(),
+            total: response.content_length(),
+        });
+
+        let result = self
+            .write_response(photo, &mut response, writer, checksum.hasher())
+            .await;
+        self.report_progress(ProgressEvent::Finished {
+            id: photo.id().to_owned(),
+        });
+        let hasher = result?;
+
+        let valid_header = download.format.matches_header(hasher.header());
+        let sha256 = hasher
+            .verify(&checksum)
+            .ok_or_else(|| Error::ChecksumMismatch(photo.id().to_owned()))?;
+        if !valid_header {
+            return Err(Error::InvalidImage(photo.id().to_owned()));
+        }
+
+        Ok(sha256)
+    }
+
+    async fn write_response<W: AsyncWrite + Unpin>(
+        &self,
+        photo: &Photo,
+        response: &mut Response,
+        writer: &mut W,
+        mut hasher: Hasher,
+    ) -> Result<Hasher> {
+        while let Some(chunk) = response.chunk().await.map_err(|source| Error::Request {
+            url: response.url().to_string(),
+            source: source.without_url(),
+        })? {
+            writer.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            self.usage.record_bytes(chunk.len() as u64);
+            self.report_progress(ProgressEvent::Advanced {
+                id: photo.id().to_owned(),
+                bytes: chunk.len() as u64,
+            });
+        }
+
+        writer.flush().await?;
+
+        Ok(hasher)
+    }
+
+    async fn request_file(&self, photo: &Photo, download: &Download) -> Result<Response> {
+        let download_request = self.http.get(download.url(photo));
+
+        self.send_request(download_request).await
+    }
+
+    async fn wait_to_redownload(&self, photo: &Photo, error: &Error, attempts: u32) {
+        let delay = self.retry.delay(None, attempts);
+        tracing::warn!(
+            id = photo.id(),
+            attempts,
+            error = %error,
+            ?delay,
+            "verification failed, downloading again"
+        );
+
+        tokio::time::sleep(delay).await;
+    }
+
+    async fn track_download(&self, photo: &Photo) -> Result<()> {
         let track_request = self.http.get(photo.download_track_url());
-        Self::send_request(track_request).await?;
+        match self.send_request(track_request).await {
+            Ok(_) => {}
+
+            Err(err @ Error::LimitReached(_)) => return Err(err),
+
+            Err(err) => {
+                let Some(tracks) = &self.tracks else {
+                    return Err(err);
+                };
+
+                tracing::warn!(id = photo.id(), error = %err, "download ping failed, queueing it");
 
-        let download_request = self
+                let mut tracks = tracks.lock().unwrap();
+                tracks.push(photo.id(), photo.download_track_url());
+                tracks.save()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn fetch_photo(&self, id: &str) -> Result<Photo> {
+        let request = self.http.get(unsplash_api!(self, "/photos/{}", id));
+        let response = self.send_request(request).await?;
+
+        parse_json(response).await
+    }
+
+    pub async fn fetch_collection_photos(&self, id: &str) -> Result<Vec<Photo>> {
+        let mut photos = Vec::new();
+        let mut page = 1;
+        loop {
+            let request = self
+                .http
+                .get(unsplash_api!(self, "/collections/{}/photos", id))
+                .query(query_params!(
+                    "page" => page,
+                    "per_page" => MAX_PER_PAGE,
+                ));
+
+            let response = self.send_request(request).await?;
+            let batch: Vec<Photo> = parse_json(response).await?;
+
+            let exhausted = batch.len() < MAX_PER_PAGE as usize;
+            photos.extend(batch);
+
+            if exhausted {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(photos)
+    }
+
+    pub async fn fetch_topics(&self) -> Result<Vec<Topic>> {
+        let mut topics = Vec::new();
+        let mut page = 1;
+        loop {
+            let request = self
+                .http
+                .get(unsplash_api!(self, "/topics"))
+                .query(query_params!(
+                    "page" => page,
+                    "per_page" => MAX_PER_PAGE,
+                ));
+
+            let batch: Vec<Topic> = self.get_json_cached(request).await?;
+
+            let exhausted = batch.len() < MAX_PER_PAGE as usize;
+            topics.extend(batch);
+
+            if exhausted {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(topics)
+    }
+
+    pub async fn list_topics(
+        &self,
+        page: u32,
+        per_page: u32,
+        order_by: TopicOrder,
+    ) -> Result<Vec<Topic>> {
+        let request = self
+            .http
+            .get(unsplash_api!(self, "/topics"))
+            .query(query_params!(
+                "page" => page,
+                "per_page" => per_page.min(MAX_PER_PAGE),
+                "order_by" => serde_variant(&order_by),
+            ));
+
+        self.get_json_cached(request).await
+    }
+
+    pub async fn verify_access(&self) -> Result<()> {
+        let request = self
             .http
-            .get(photo.file_url())
-            .query(&download.to_query_params());
+            .get(unsplash_api!(self, "/photos"))
+            .query(query_params!("per_page" => 1));
 
-        let response = Self::send_request(download_request).await?;
-        let data = response.bytes().await.map_err(|_| Error::InvalidResponse)?;
+        self.send_request(request).await?;
 
-        Ok(data)
+        Ok(())
     }
 
-    async fn find_topic(&self, id_or_slug: &str) -> Result<Topic> {
-        let request = self.http.get(unsplash_api!("/topics/{}", id_or_slug));
+    pub async fn find_topic(&self, id_or_slug: &str) -> Result<Topic> {
+        if let Some(cache) = &self.cache
+            && let Some(topic) = cache.lock().unwrap().topic(id_or_slug)
+        {
+            return Ok(topic.clone());
+        }
+
+        let request = self.http.get(unsplash_api!(self, "/topics/{}", id_or_slug));
+        let topic: Topic = self.get_json_cached(request).await?;
 
-        let response = Self::send_request(request).await?;
-        let topic = response.json().await.map_err(|_| Error::InvalidResponse)?;
+        if let Some(cache) = &self.cache {
+            let mut cache = cache.lock().unwrap();
+            cache.insert_topic(&topic);
+            if let Err(err) = cache.save() {
+                tracing::warn!(error = %err, "failed to save the response cache");
+            }
+        }
 
         Ok(topic)
     }
 
-    async fn send_request(request: RequestBuilder) -> Result<Response> {
-        let response = request.send().await.map_err(|_| Error::Request)?;
+    async fn get_json_cached<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let Some(cache) = &self.cache else {
+            return parse_json(self.send_request(request).await?).await;
+        };
+
+        let url = request
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .map(|request| request.url().to_string())
+            .ok_or(Error::InvalidRequest)?;
+
+        let cached = cache.lock().unwrap().response(&url).cloned();
+        let request = match &cached {
+            Some(cached) => request.header(IF_NONE_MATCH, &cached.etag),
+            None => request,
+        };
+
+        let response = self.send_request(request).await?;
+        let body = match cached {
+            Some(cached) if response.status() == StatusCode::NOT_MODIFIED => {
+                tracing::debug!(url, "response not modified, using the cache");
+
+                cached.body
+            }
+
+            _ => {
+                let etag = response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(ToOwned::to_owned);
+                let body = response.text().await.map_err(|source| Error::Request {
+                    url: url.clone(),
+                    source: source.without_url(),
+                })?;
+
+                if let Some(etag) = etag {
+                    let mut cache = cache.lock().unwrap();
+                    cache.insert_response(
+                        url.clone(),
+                        CachedResponse {
+                            etag,
+                            body: body.clone(),
+                        },
+                    );
+                    if let Err(err) = cache.save() {
+                        tracing::warn!(error = %err, "failed to save the response cache");
+                    }
+                }
+
+                body
+            }
+        };
+
+        serde_json::from_str(&body).map_err(|source| Error::InvalidResponse {
+            url,
+            source: source.into(),
+        })
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(url = tracing::field::Empty))]
+    async fn send_request(&self, request: RequestBuilder) -> Result<Response> {
+        let url = request
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .map(|request| request.url().to_string())
+            .ok_or(Error::InvalidRequest)?;
+        tracing::Span::current().record("url", &url);
+
+        let mut retries = 0;
+
+        loop {
+            let can_retry = retries + 1 < self.retry.max_attempts;
+            let attempt = request.try_clone().ok_or(Error::InvalidRequest)?;
+
+            if let Some(limit) = self.usage.exceeded(&self.limits) {
+                tracing::warn!(%limit, "run limit reached");
+
+                return Err(Error::LimitReached(limit));
+            }
+
+            if url.starts_with(&self.base_url) {
+                self.spend_budget()?;
+            }
+
+            self.usage.record_request();
+            let result = attempt.send().await;
+            if let Ok(response) = &result
+                && let Some(rate_limit) = RateLimit::from_headers(response.headers())
+            {
+                *self.rate_limit.lock().unwrap() = Some(rate_limit);
+            }
+
+            let delay = match result {
+                Ok(response)
+                    if response.status().is_success()
+                        || response.status() == StatusCode::NOT_MODIFIED =>
+                {
+                    tracing::debug!(status = %response.status(), "request succeeded");
+
+                    return Ok(response);
+                }
 
-        if !response.status().is_success() {
-            return Err(Error::Status(response.status()));
+                Ok(response) => {
+                    let status = response.status();
+                    tracing::warn!(%status, retries, "request failed");
+
+                    if status == StatusCode::FORBIDDEN
+                        && self
+                            .rate_limit()
+                            .is_some_and(|rate_limit| rate_limit.is_exhausted())
+                    {
+                        return Err(Error::RateLimitExceeded);
+                    }
+
+                    if !can_retry || !Self::is_transient(status) {
+                        return Err(status_error(response, url, retries).await);
+                    }
+
+                    self.retry.delay(Self::retry_after(&response), retries)
+                }
+
+                Err(err) if can_retry && error::tls_error(&err).is_none() => {
+                    tracing::warn!(error = %err, retries, "request failed");
+
+                    self.retry.delay(None, retries)
+                }
+
+                Err(err) => {
+                    tracing::error!(error = %err, retries, "request failed");
+
+                    return Err(Error::request(url, err));
+                }
+            };
+
+            tracing::debug!(?delay, "retrying request");
+
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+
+    fn spend_budget(&self) -> Result<()> {
+        let Some(budget) = &self.budget else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let mut budget = budget.lock().unwrap();
+        if let Some(available_at) = budget.available_at(now) {
+            tracing::warn!(%available_at, "request budget spent");
+
+            return Err(Error::BudgetSpent(available_at));
+        }
+
+        budget.record(now);
+        budget.save()?;
+
+        Ok(())
+    }
+
+    fn is_transient(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    fn retry_after(response: &Response) -> Option<Duration> {
+        let seconds = response
+            .headers()
+            .get(RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()?;
+
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_is_capped() {
+        let retry = RetryPolicy {
+            max_delay_ms: 5000,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            retry.delay(Some(Duration::from_secs(3600)), 0),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            retry.delay(Some(Duration::from_secs(2)), 0),
+            Duration::from_secs(2)
+        );
+        assert_eq!(retry.delay(None, 10), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn missing_retry_fields_use_defaults() {
+        let retry: RetryPolicy = serde_json::from_str(r#"{ "max_attempts": 5 }"#).unwrap();
+
+        assert_eq!(retry.max_attempts, 5);
+        assert_eq!(retry.base_delay_ms, 1000);
+        assert_eq!(retry.max_delay_ms, 60 * 1000);
+    }
+
+    #[test]
+    fn legacy_query_is_migrated_into_queries() {
+        let fetch: Fetch = toml_edit::de::from_str(
+            r#"
+            count = 5
+            query = { category = "text", value = "mountains" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(fetch.queries.len(), 1);
+        assert!(matches!(&fetch.queries[0].query, Query::Text(text) if text == "mountains"));
+        assert_eq!(fetch.queries[0].weight, 1);
+        assert_eq!(fetch.split_count()[0].1, 5);
+    }
+
+    #[test]
+    fn migrated_query_is_saved_as_queries() {
+        let fetch: Fetch = serde_json::from_str(
+            r#"{ "count": 5, "query": { "category": "topic", "value": "nature" } }"#,
+        )
+        .unwrap();
+
+        let saved = serde_json::to_value(&fetch).unwrap();
+
+        assert!(saved.get("query").is_none());
+        assert_eq!(saved["queries"][0]["category"], "topic");
+        assert_eq!(saved["queries"][0]["value"], "nature");
+    }
+
+    #[tokio::test]
+    async fn redownloads_are_tracked_once() {
+        let (url, paths) = crate::testing::serve(b"not an image").await;
+        let client = Client::new(&Redacted::from("key".to_owned()))
+            .unwrap()
+            .with_base_url(url.clone())
+            .with_retry(RetryPolicy {
+                max_attempts: 3,
+                base_delay_ms: 1,
+                ..Default::default()
+            });
+        let photo = crate::testing::photo("photo", url.as_str());
+
+        let result = client.download_photo(&photo, &Download::default()).await;
+        let paths = paths.lock().unwrap().clone();
+
+        assert!(matches!(result, Err(Error::InvalidImage(_))));
+        assert_eq!(
+            paths
+                .iter()
+                .filter(|path| path.starts_with("/track"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            paths.iter().filter(|path| path.starts_with("/raw")).count(),
+            3
+        );
+    }
+
+    fn search_fetch(query: Query, order_by: Option<OrderBy>) -> Fetch {
+        Fetch {
+            mode: Mode::Search(Search {
+                order_by,
+                ..Default::default()
+            }),
+            queries: vec![WeightedQuery { query, weight: 1 }],
+            color: Some(Color::Blue),
+            ..Default::default()
         }
+    }
+
+    #[test]
+    fn text_searches_send_every_filter() {
+        let fetch = search_fetch(Query::Text("sea".to_owned()), Some(OrderBy::Latest));
+        let Mode::Search(search) = &fetch.mode else {
+            unreachable!()
+        };
+
+        let names: Vec<_> = fetch
+            .query_params_for(Endpoint::Search)
+            .into_iter()
+            .chain(search.query_params_for(Endpoint::Search))
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(
+            names,
+            ["content_filter", "orientation", "color", "order_by"]
+        );
+        assert!(fetch.ignored_filters(Endpoint::Search).is_empty());
+    }
+
+    #[test]
+    fn topic_listings_drop_unsupported_filters() {
+        let fetch = search_fetch(Query::Topic("nature".to_owned()), Some(OrderBy::Relevant));
+        let Mode::Search(search) = &fetch.mode else {
+            unreachable!()
+        };
+
+        let names: Vec<_> = fetch
+            .query_params_for(Endpoint::Topic)
+            .into_iter()
+            .chain(search.query_params_for(Endpoint::Topic))
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(fetch.endpoints(), [Endpoint::Topic]);
+        assert_eq!(names, ["orientation"]);
+        assert_eq!(
+            fetch.ignored_filters(Endpoint::Topic),
+            ["color", "mode.order_by"]
+        );
+    }
+
+    #[test]
+    fn photo_listings_ignore_orientation() {
+        let mut fetch = search_fetch(Query::Topics(Vec::new()), Some(OrderBy::Popular));
+        fetch.color = None;
+
+        assert_eq!(fetch.endpoints(), [Endpoint::List]);
+        assert!(fetch.query_params_for(Endpoint::List).is_empty());
+        assert_eq!(fetch.ignored_filters(Endpoint::List), ["orientation"]);
+    }
+
+    #[test]
+    fn random_photos_ignore_color() {
+        let fetch = Fetch {
+            color: Some(Color::Red),
+            ..Default::default()
+        };
+
+        let names: Vec<_> = fetch
+            .query_params_for(Endpoint::Random)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
 
-        Ok(response)
+        assert_eq!(fetch.endpoints(), [Endpoint::Random]);
+        assert_eq!(names, ["content_filter", "orientation"]);
+        assert_eq!(fetch.ignored_filters(Endpoint::Random), ["color"]);
     }
 }
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Exif {
+    #[serde(default)]
+    make: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    exposure_time: Option<String>,
+    #[serde(default)]
+    aperture: Option<String>,
+    #[serde(default)]
+    focal_length: Option<String>,
+    #[serde(default)]
+    iso: Option<u32>,
+}
+
+impl Exif {
+    pub fn make(&self) -> Option<&str> {
+        self.make.as_deref()
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn camera(&self) -> Option<&str> {
+        self.name.as_deref().or(self.model())
+    }
+
+    pub fn exposure_time(&self) -> Option<&str> {
+        self.exposure_time.as_deref()
+    }
+
+    pub fn aperture(&self) -> Option<f32> {
+        self.aperture.as_deref()?.parse().ok()
+    }
+
+    pub fn focal_length(&self) -> Option<f32> {
+        self.focal_length.as_deref()?.parse().ok()
+    }
+
+    pub fn iso(&self) -> Option<u32> {
+        self.iso
+    }
+}
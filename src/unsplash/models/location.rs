@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    city: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Position {
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+impl Location {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn city(&self) -> Option<&str> {
+        self.city.as_deref()
+    }
+
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        let position = self.position.as_ref()?;
+
+        Some((position.latitude?, position.longitude?))
+    }
+}
@@ -1,5 +1,13 @@
+pub mod exif;
+pub mod location;
 pub mod photo;
+pub mod search;
 pub mod topic;
+pub mod user;
 
+pub use exif::Exif;
+pub use location::Location;
 pub use photo::Photo;
+pub use search::SearchResults;
 pub use topic::Topic;
+pub use user::User;
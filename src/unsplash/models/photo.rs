@@ -2,11 +2,40 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use super::{Exif, Location, User};
+use crate::Query;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Photo {
     id: String,
+    width: u32,
+    height: u32,
+    user: User,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    alt_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blur_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exif: Option<Exif>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
     urls: HashMap<String, String>,
     links: HashMap<String, String>,
+    #[serde(default)]
+    likes: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<Tag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    query: Option<Query>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Tag {
+    title: String,
 }
 
 impl Photo {
@@ -14,10 +43,70 @@ impl Photo {
         &self.id
     }
 
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn alt_description(&self) -> Option<&str> {
+        self.alt_description.as_deref()
+    }
+
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+
+    pub fn blur_hash(&self) -> Option<&str> {
+        self.blur_hash.as_deref()
+    }
+
+    pub fn exif(&self) -> Option<&Exif> {
+        self.exif.as_ref()
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub fn likes(&self) -> u32 {
+        self.likes
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(|tag| tag.title.as_str())
+    }
+
+    pub fn query(&self) -> Option<&Query> {
+        self.query.as_ref()
+    }
+
+    pub(crate) fn set_query(&mut self, query: Option<Query>) {
+        self.query = query;
+    }
+
     pub fn file_url(&self) -> &str {
         &self.urls["raw"]
     }
 
+    pub fn thumb_url(&self) -> &str {
+        &self.urls["thumb"]
+    }
+
+    pub fn page_url(&self) -> Option<&str> {
+        self.links.get("html").map(String::as_str)
+    }
+
     pub fn download_track_url(&self) -> &str {
         &self.links["download_location"]
     }
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+use super::Photo;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    results: Vec<Photo>,
+}
+
+impl SearchResults {
+    pub fn into_photos(self) -> Vec<Photo> {
+        self.results
+    }
+}
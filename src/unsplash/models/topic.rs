@@ -3,10 +3,38 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Topic {
     id: String,
+    #[serde(default)]
+    slug: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default)]
+    total_photos: u32,
 }
 
 impl Topic {
     pub fn id(&self) -> &str {
         &self.id
     }
+
+    pub fn slug(&self) -> &str {
+        &self.slug
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn total_photos(&self) -> u32 {
+        self.total_photos
+    }
+
+    pub fn matches(&self, id_or_slug: &str) -> bool {
+        self.id == id_or_slug || self.slug == id_or_slug
+    }
 }
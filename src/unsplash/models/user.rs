@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    username: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    links: HashMap<String, String>,
+}
+
+impl User {
+    pub fn new<S: Into<String>, T: Into<String>>(username: S, name: T) -> Self {
+        Self {
+            username: username.into(),
+            name: name.into(),
+            links: HashMap::new(),
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn profile_url(&self) -> Option<&str> {
+        self.links.get("html").map(String::as_str)
+    }
+}
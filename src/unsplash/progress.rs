@@ -0,0 +1,6 @@
+#[derive(Debug, Clone)]
+pub enum ProgressEvent {
+    Started { id: String, total: Option<u64> },
+    Advanced { id: String, bytes: u64 },
+    Finished { id: String },
+}
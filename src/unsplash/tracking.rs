@@ -0,0 +1,87 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::{self, Migration};
+
+const MAX_ATTEMPTS: u32 = 10;
+const MIGRATIONS: &[Migration] = &[|data| data];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PendingTrack {
+    pub url: String,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+#[derive(Debug)]
+pub struct TrackQueue {
+    path: PathBuf,
+    pending: BTreeMap<String, PendingTrack>,
+}
+
+impl TrackQueue {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let pending = state::load(&path, MIGRATIONS)?;
+
+        Ok(Self { path, pending })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        state::save(&self.path, &self.pending, MIGRATIONS)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.pending.contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, id: &str, url: &str) {
+        self.pending
+            .entry(id.to_owned())
+            .or_insert_with(|| PendingTrack {
+                url: url.to_owned(),
+                attempts: 0,
+            });
+    }
+
+    pub(crate) fn pending(&self) -> Vec<(String, PendingTrack)> {
+        self.pending
+            .iter()
+            .map(|(id, track)| (id.clone(), track.clone()))
+            .collect()
+    }
+
+    pub(crate) fn complete(&mut self, id: &str) {
+        self.pending.remove(id);
+    }
+
+    pub(crate) fn fail(&mut self, id: &str) {
+        let Some(track) = self.pending.get_mut(id) else {
+            return;
+        };
+
+        track.attempts += 1;
+        if track.attempts >= MAX_ATTEMPTS {
+            tracing::warn!(
+                id,
+                attempts = track.attempts,
+                "giving up on the download ping"
+            );
+            self.pending.remove(id);
+        }
+    }
+}
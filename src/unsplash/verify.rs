@@ -0,0 +1,38 @@
+#[cfg(feature = "verify")]
+use std::path::PathBuf;
+
+use super::Format;
+
+pub(crate) const HEADER_LENGTH: usize = 12;
+
+impl Format {
+    pub(crate) fn matches_header(&self, header: &[u8]) -> bool {
+        match self {
+            Format::Png => header.starts_with(b"\x89PNG\r\n\x1a\n"),
+            Format::Jpeg { .. } => header.starts_with(&[0xFF, 0xD8, 0xFF]),
+            Format::Webp { .. } => {
+                header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP")
+            }
+            Format::Avif { .. } => header.get(4..8) == Some(b"ftyp"),
+        }
+    }
+}
+
+#[cfg(feature = "verify")]
+pub(crate) async fn decodes(path: PathBuf, format: &Format) -> bool {
+    let image_format = match format {
+        Format::Png => image::ImageFormat::Png,
+        Format::Jpeg { .. } => image::ImageFormat::Jpeg,
+        Format::Webp { .. } => image::ImageFormat::WebP,
+        Format::Avif { .. } => return true,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        image::ImageReader::open(path).is_ok_and(|mut reader| {
+            reader.set_format(image_format);
+            reader.decode().is_ok()
+        })
+    })
+    .await
+    .unwrap()
+}
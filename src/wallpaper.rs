@@ -0,0 +1,581 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{self, Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::display::Monitor;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    #[default]
+    Auto,
+    Feh,
+    Swaybg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Desktop,
+    #[serde(rename = "lockscreen")]
+    LockScreen,
+}
+
+pub trait WallpaperSetter {
+    fn set(&self, path: &Path) -> io::Result<()>;
+
+    fn set_per_monitor(&self, assignments: &[(Monitor, PathBuf)]) -> io::Result<()>;
+
+    fn current(&self) -> io::Result<Option<PathBuf>>;
+}
+
+impl WallpaperSetter for Backend {
+    fn set(&self, path: &Path) -> io::Result<()> {
+        set(path, *self)
+    }
+
+    fn set_per_monitor(&self, assignments: &[(Monitor, PathBuf)]) -> io::Result<()> {
+        set_per_monitor(assignments, *self)
+    }
+
+    fn current(&self) -> io::Result<Option<PathBuf>> {
+        current()
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[derive(Debug, Default)]
+pub struct FakeWallpaperSetter {
+    wallpapers: std::sync::Mutex<Vec<(Option<Monitor>, PathBuf)>>,
+}
+
+#[cfg(feature = "test-util")]
+impl FakeWallpaperSetter {
+    pub fn wallpapers(&self) -> Vec<(Option<Monitor>, PathBuf)> {
+        self.wallpapers.lock().unwrap().clone()
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl WallpaperSetter for FakeWallpaperSetter {
+    fn set(&self, path: &Path) -> io::Result<()> {
+        self.wallpapers
+            .lock()
+            .unwrap()
+            .push((None, path.to_owned()));
+
+        Ok(())
+    }
+
+    fn set_per_monitor(&self, assignments: &[(Monitor, PathBuf)]) -> io::Result<()> {
+        self.wallpapers.lock().unwrap().extend(
+            assignments
+                .iter()
+                .map(|(monitor, path)| (Some(monitor.clone()), path.clone())),
+        );
+
+        Ok(())
+    }
+
+    fn current(&self) -> io::Result<Option<PathBuf>> {
+        Ok(self
+            .wallpapers
+            .lock()
+            .unwrap()
+            .last()
+            .map(|(_, path)| path.clone()))
+    }
+}
+
+pub fn set<P: AsRef<Path>>(path: P, backend: Backend) -> io::Result<()> {
+    let path = path::absolute(path)?;
+
+    match backend {
+        Backend::Auto => set_native(&path)?,
+        Backend::Feh => set_feh(&path)?,
+        Backend::Swaybg => set_swaybg(&path)?,
+    }
+
+    #[cfg(not(windows))]
+    fs::write(runtime_path("wallpaper"), path.to_string_lossy().as_bytes())?;
+
+    Ok(())
+}
+
+pub fn set_per_monitor(assignments: &[(Monitor, PathBuf)], backend: Backend) -> io::Result<()> {
+    let assignments = assignments
+        .iter()
+        .map(|(monitor, path)| Ok((monitor, path::absolute(path)?)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    match backend {
+        Backend::Auto => set_per_monitor_native(&assignments)?,
+        Backend::Feh => set_per_monitor_feh(&assignments)?,
+        Backend::Swaybg => set_per_monitor_swaybg(&assignments)?,
+    }
+
+    #[cfg(not(windows))]
+    if let Some((_, path)) = assignments.first() {
+        fs::write(runtime_path("wallpaper"), path.to_string_lossy().as_bytes())?;
+    }
+
+    Ok(())
+}
+
+pub fn set_spanned<P: AsRef<Path>>(path: P, backend: Backend) -> io::Result<()> {
+    let path = path::absolute(path)?;
+
+    match backend {
+        Backend::Auto => set_spanned_native(&path)?,
+        Backend::Feh => set_spanned_feh(&path)?,
+        Backend::Swaybg => return Err(spanning_unsupported()),
+    }
+
+    #[cfg(not(windows))]
+    fs::write(runtime_path("wallpaper"), path.to_string_lossy().as_bytes())?;
+
+    Ok(())
+}
+
+pub fn current() -> io::Result<Option<PathBuf>> {
+    current_native()
+}
+
+pub fn set_lock_screen<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    set_lock_screen_native(&path::absolute(path)?)
+}
+
+#[cfg(windows)]
+fn set_native(path: &Path) -> io::Result<()> {
+    use windows::{
+        core::HSTRING,
+        Win32::UI::WindowsAndMessaging::{
+            SystemParametersInfoW, SPIF_SENDCHANGE, SPIF_UPDATEINIFILE, SPI_SETDESKWALLPAPER,
+        },
+    };
+
+    let path = HSTRING::from(path);
+
+    unsafe {
+        SystemParametersInfoW(
+            SPI_SETDESKWALLPAPER,
+            0,
+            Some(path.as_ptr() as *mut _),
+            SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_per_monitor_native(assignments: &[(&Monitor, PathBuf)]) -> io::Result<()> {
+    use windows::core::HSTRING;
+
+    use crate::display;
+
+    let desktop_wallpaper = display::desktop_wallpaper()
+        .ok_or_else(|| io::Error::other("IDesktopWallpaper is not available"))?;
+
+    for (monitor, path) in assignments {
+        unsafe {
+            desktop_wallpaper
+                .SetWallpaper(&HSTRING::from(&monitor.id), &HSTRING::from(path.as_path()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_spanned_native(path: &Path) -> io::Result<()> {
+    use windows::{
+        core::{HSTRING, PCWSTR},
+        Win32::UI::Shell::DWPOS_SPAN,
+    };
+
+    use crate::display;
+
+    let desktop_wallpaper = display::desktop_wallpaper()
+        .ok_or_else(|| io::Error::other("IDesktopWallpaper is not available"))?;
+
+    unsafe {
+        desktop_wallpaper.SetPosition(DWPOS_SPAN)?;
+        desktop_wallpaper.SetWallpaper(PCWSTR::null(), &HSTRING::from(path))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_lock_screen_native(path: &Path) -> io::Result<()> {
+    use windows::{core::HSTRING, Storage::StorageFile, System::UserProfile::LockScreen};
+
+    let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(path))?.get()?;
+    LockScreen::SetImageFileAsync(&file)?.get()?;
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn set_lock_screen_native(_: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "The lock screen can only be set on Windows",
+    ))
+}
+
+#[cfg(windows)]
+fn current_native() -> io::Result<Option<PathBuf>> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETDESKWALLPAPER, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    const MAX_PATH: usize = 260;
+
+    let mut buffer = [0u16; MAX_PATH];
+
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETDESKWALLPAPER,
+            buffer.len() as u32,
+            Some(buffer.as_mut_ptr() as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )?;
+    }
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    if len == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(OsString::from_wide(&buffer[..len]).into()))
+}
+
+#[cfg(not(windows))]
+fn current_native() -> io::Result<Option<PathBuf>> {
+    match fs::read_to_string(runtime_path("wallpaper")) {
+        Ok(path) => Ok(Some(path.into())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(not(windows))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Desktop {
+    Gnome,
+    Kde,
+    Wayland,
+    X11,
+}
+
+#[cfg(not(windows))]
+impl Desktop {
+    fn detect() -> Self {
+        Self::from_env(
+            env::var("XDG_CURRENT_DESKTOP").ok().as_deref(),
+            env::var_os("WAYLAND_DISPLAY").is_some(),
+        )
+    }
+
+    fn from_env(current_desktop: Option<&str>, wayland: bool) -> Self {
+        let desktops = current_desktop.unwrap_or_default().split(':');
+        for desktop in desktops {
+            match desktop.to_ascii_lowercase().as_str() {
+                "gnome" | "unity" => return Desktop::Gnome,
+                "kde" => return Desktop::Kde,
+                _ => {}
+            }
+        }
+
+        if wayland {
+            Desktop::Wayland
+        } else {
+            Desktop::X11
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn set_native(path: &Path) -> io::Result<()> {
+    match Desktop::detect() {
+        Desktop::Gnome => set_gnome(path),
+        Desktop::Kde => set_kde(path),
+        Desktop::Wayland => set_swaybg(path),
+        Desktop::X11 => set_feh(path),
+    }
+}
+
+#[cfg(not(windows))]
+fn set_gnome(path: &Path) -> io::Result<()> {
+    let uri = reqwest::Url::from_file_path(path)
+        .map_err(|()| io::Error::new(io::ErrorKind::InvalidInput, "The path is not absolute"))?;
+
+    for key in ["picture-uri", "picture-uri-dark"] {
+        run(Command::new("gsettings")
+            .args(["set", "org.gnome.desktop.background", key])
+            .arg(uri.as_str()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn set_kde(path: &Path) -> io::Result<()> {
+    run(Command::new("plasma-apply-wallpaperimage").arg(path))
+}
+
+#[cfg(not(windows))]
+fn set_spanned_native(path: &Path) -> io::Result<()> {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        Err(spanning_unsupported())
+    } else {
+        set_spanned_feh(path)
+    }
+}
+
+#[cfg(not(windows))]
+fn set_per_monitor_native(assignments: &[(&Monitor, PathBuf)]) -> io::Result<()> {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        set_per_monitor_swaybg(assignments)
+    } else {
+        set_per_monitor_feh(assignments)
+    }
+}
+
+fn set_per_monitor_feh(assignments: &[(&Monitor, PathBuf)]) -> io::Result<()> {
+    run(Command::new("feh")
+        .arg("--bg-fill")
+        .args(assignments.iter().map(|(_, path)| path)))
+}
+
+fn set_per_monitor_swaybg(assignments: &[(&Monitor, PathBuf)]) -> io::Result<()> {
+    let mut args = Vec::new();
+    for (monitor, path) in assignments {
+        args.extend([
+            "--output".into(),
+            monitor.id.clone().into(),
+            "--mode".into(),
+            "fill".into(),
+            "--image".into(),
+            path.clone().into_os_string(),
+        ]);
+    }
+
+    spawn_swaybg(&args)
+}
+
+fn set_spanned_feh(path: &Path) -> io::Result<()> {
+    run(Command::new("feh")
+        .args(["--bg-fill", "--no-xinerama"])
+        .arg(path))
+}
+
+fn spanning_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Spanning wallpapers are not supported by swaybg, use per_monitor instead",
+    )
+}
+
+fn set_feh(path: &Path) -> io::Result<()> {
+    run(Command::new("feh").arg("--bg-fill").arg(path))
+}
+
+fn run(command: &mut Command) -> io::Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .map_err(|err| backend_error(&program, err))?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+
+    Ok(())
+}
+
+fn backend_error(program: &str, err: io::Error) -> io::Error {
+    if err.kind() != io::ErrorKind::NotFound {
+        return err;
+    }
+
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "No wallpaper backend found, install `{}` or choose another `wallpaper` backend in the configuration",
+            program
+        ),
+    )
+}
+
+fn set_swaybg(path: &Path) -> io::Result<()> {
+    spawn_swaybg(&[
+        "--mode".into(),
+        "fill".into(),
+        "--image".into(),
+        path.as_os_str().to_owned(),
+    ])
+}
+
+/// The swaybg instance started by this process, which is killed and reaped once it's replaced.
+static SWAYBG: Mutex<Option<Child>> = Mutex::new(None);
+
+fn spawn_swaybg(args: &[OsString]) -> io::Result<()> {
+    let pid_path = runtime_path("swaybg.pid");
+    let previous = fs::read_to_string(&pid_path).ok();
+
+    let child = Command::new("swaybg")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| backend_error("swaybg", err))?;
+
+    fs::write(&pid_path, child.id().to_string())?;
+
+    if !replace_child(&SWAYBG, child)?
+        && let Some(pid) = previous.and_then(|pid| pid.trim().parse().ok())
+    {
+        terminate_swaybg(pid)?;
+    }
+
+    Ok(())
+}
+
+/// Stores `child` in `slot`, killing and reaping the child it replaces. Returns whether there
+/// was one.
+fn replace_child(slot: &Mutex<Option<Child>>, child: Child) -> io::Result<bool> {
+    let Some(mut previous) = slot.lock().unwrap().replace(child) else {
+        return Ok(false);
+    };
+
+    previous.kill()?;
+    previous.wait()?;
+
+    Ok(true)
+}
+
+/// Terminates a swaybg instance left behind by an earlier run. The process is opened through a
+/// pidfd before its name is checked, so the pid can't be reused in between.
+#[cfg(target_os = "linux")]
+fn terminate_swaybg(pid: libc::pid_t) -> io::Result<()> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        let err = io::Error::last_os_error();
+
+        return match err.raw_os_error() {
+            Some(libc::ESRCH) => Ok(()),
+            _ => Err(err),
+        };
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+    let is_swaybg =
+        fs::read_to_string(format!("/proc/{}/comm", pid)).is_ok_and(|name| name.trim() == "swaybg");
+    if !is_swaybg {
+        return Ok(());
+    }
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            fd.as_raw_fd(),
+            libc::SIGTERM,
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    match result {
+        0.. => Ok(()),
+        _ => match io::Error::last_os_error() {
+            err if err.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+            err => Err(err),
+        },
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn terminate_swaybg(_pid: u32) -> io::Result<()> {
+    Ok(())
+}
+
+fn runtime_path(name: &str) -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("backdrop-{}", name))
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_environment_is_preferred_over_the_session_type() {
+        assert_eq!(Desktop::from_env(Some("GNOME"), true), Desktop::Gnome);
+        assert_eq!(
+            Desktop::from_env(Some("ubuntu:GNOME"), true),
+            Desktop::Gnome
+        );
+        assert_eq!(Desktop::from_env(Some("KDE"), true), Desktop::Kde);
+        assert_eq!(Desktop::from_env(Some("sway"), true), Desktop::Wayland);
+        assert_eq!(Desktop::from_env(None, true), Desktop::Wayland);
+        assert_eq!(Desktop::from_env(None, false), Desktop::X11);
+    }
+
+    #[test]
+    fn missing_backend_is_reported() {
+        let err = run(&mut Command::new("backdrop-missing-wallpaper-backend")).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("No wallpaper backend found"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn replaced_children_are_reaped() {
+        let slot = Mutex::new(None);
+        let sleep = || Command::new("sleep").arg("30").spawn().unwrap();
+
+        let first = sleep();
+        let pid = first.id();
+        assert!(!replace_child(&slot, first).unwrap());
+        assert!(replace_child(&slot, sleep()).unwrap());
+        let reaped = !Path::new(&format!("/proc/{}", pid)).exists();
+
+        let mut second = slot.lock().unwrap().take().unwrap();
+        second.kill().unwrap();
+        second.wait().unwrap();
+
+        assert!(reaped);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_swaybg_is_terminated() {
+        let mut other = Command::new("sleep").arg("30").spawn().unwrap();
+
+        terminate_swaybg(other.id() as libc::pid_t).unwrap();
+        let running = other.try_wait().unwrap().is_none();
+        other.kill().unwrap();
+        other.wait().unwrap();
+
+        assert!(running);
+    }
+}
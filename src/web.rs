@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::{Form, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use tokio::{
+    fs,
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+
+#[cfg(feature = "thumbnails")]
+use crate::thumbnail::ThumbnailCache;
+use crate::{
+    control::Action,
+    recap::{escape, photo_link},
+    redact::Redacted,
+};
+
+const THUMBNAIL_SIZE: u32 = 480;
+#[cfg(feature = "thumbnails")]
+const THUMBNAIL_CACHE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct PoolPhoto {
+    pub id: String,
+    pub path: PathBuf,
+    pub photographer: Option<String>,
+    pub profile_url: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub paused: bool,
+    pub query: Option<String>,
+    pub current: Option<String>,
+    pub photos: Vec<PoolPhoto>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    token: Option<Redacted<String>>,
+    status: Arc<RwLock<Status>>,
+    actions: mpsc::Sender<Action>,
+    #[cfg(feature = "thumbnails")]
+    thumbnails: Arc<ThumbnailCache>,
+}
+
+impl AppState {
+    fn token_query(&self) -> String {
+        self.token.as_ref().map_or(String::new(), |token| {
+            format!("?token={}", escape(token.expose()))
+        })
+    }
+
+    fn home(&self) -> Redirect {
+        Redirect::to(&format!("/{}", self.token_query()))
+    }
+}
+
+#[derive(Deserialize)]
+struct QueryForm {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct PhotoQuery {
+    #[serde(default)]
+    size: Option<u32>,
+}
+
+pub async fn serve(
+    address: SocketAddr,
+    token: Option<Redacted<String>>,
+    status: Arc<RwLock<Status>>,
+    actions: mpsc::Sender<Action>,
+) -> io::Result<()> {
+    let state = AppState {
+        token,
+        status,
+        actions,
+        #[cfg(feature = "thumbnails")]
+        thumbnails: Arc::new(ThumbnailCache::new(THUMBNAIL_CACHE_CAPACITY)),
+    };
+
+    let router = Router::new()
+        .route("/", get(index))
+        .route("/photos/{id}", get(photo))
+        .route("/next", post(next))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/query", post(set_query))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
+
+    let listener = TcpListener::bind(address).await?;
+    axum::serve(listener, router).await
+}
+
+async fn authorize(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = &state.token else {
+        return next.run(request).await;
+    };
+
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    let token = token.expose().as_str();
+    if params.get("token").map(String::as_str) == Some(token) || bearer == Some(token) {
+        next.run(request).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+async fn index(State(state): State<AppState>) -> Html<String> {
+    let status = state.status.read().unwrap().clone();
+    let token = state.token_query();
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+    writeln!(
+        html,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )
+    .unwrap();
+    writeln!(html, "<title>Backdrop</title>").unwrap();
+    writeln!(html, "<style>{}</style>", STYLE).unwrap();
+    writeln!(html, "</head><body>").unwrap();
+    writeln!(html, "<h1>Backdrop</h1>").unwrap();
+
+    writeln!(
+        html,
+        "<p>{}, showing {}.</p>",
+        if status.paused { "Paused" } else { "Running" },
+        escape(status.current.as_deref().unwrap_or("nothing"))
+    )
+    .unwrap();
+
+    writeln!(html, "<div class=\"controls\">").unwrap();
+    writeln!(
+        html,
+        "<form method=\"post\" action=\"/next{}\"><button>Next</button></form>",
+        token
+    )
+    .unwrap();
+    let (action, label) = if status.paused {
+        ("resume", "Resume")
+    } else {
+        ("pause", "Pause")
+    };
+    writeln!(
+        html,
+        "<form method=\"post\" action=\"/{}{}\"><button>{}</button></form>",
+        action, token, label
+    )
+    .unwrap();
+    writeln!(html, "</div>").unwrap();
+
+    writeln!(
+        html,
+        "<form method=\"post\" action=\"/query{}\" class=\"query\"><input name=\"query\" value=\"{}\" placeholder=\"topic:nature orientation:landscape\"><button>Save query</button></form>",
+        token,
+        escape(status.query.as_deref().unwrap_or(""))
+    )
+    .unwrap();
+
+    writeln!(html, "<div class=\"photos\">").unwrap();
+    for photo in &status.photos {
+        writeln!(html, "<figure>").unwrap();
+        writeln!(
+            html,
+            "<img src=\"/photos/{0}{1}{2}size={3}\" alt=\"{0}\" loading=\"lazy\">",
+            escape(&photo.id),
+            token,
+            if token.is_empty() { "?" } else { "&amp;" },
+            THUMBNAIL_SIZE
+        )
+        .unwrap();
+
+        let credit = match (&photo.photographer, &photo.profile_url) {
+            (Some(name), Some(url)) => {
+                format!(" by <a href=\"{}\">{}</a>", escape(url), escape(name))
+            }
+            (Some(name), None) => format!(" by {}", escape(name)),
+            _ => String::new(),
+        };
+        writeln!(
+            html,
+            "<figcaption>{}{}</figcaption>",
+            photo_link(&photo.id, photo.url.as_deref()),
+            credit
+        )
+        .unwrap();
+        writeln!(html, "</figure>").unwrap();
+    }
+    writeln!(html, "</div>").unwrap();
+
+    writeln!(html, "</body></html>").unwrap();
+
+    Html(html)
+}
+
+async fn photo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<PhotoQuery>,
+) -> Response {
+    let path = state
+        .status
+        .read()
+        .unwrap()
+        .photos
+        .iter()
+        .find(|photo| photo.id == id)
+        .map(|photo| photo.path.clone());
+
+    let Some(path) = path else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    #[cfg(feature = "thumbnails")]
+    if let Some(size) = query.size {
+        let thumbnails = state.thumbnails.clone();
+        let result = tokio::task::spawn_blocking(move || thumbnails.get(&id, &path, size)).await;
+
+        return match result {
+            Ok(Ok(data)) => ([(header::CONTENT_TYPE, "image/jpeg")], data).into_response(),
+            Ok(Err(err)) => {
+                tracing::warn!(error = %err, "failed to create a thumbnail");
+                StatusCode::NOT_FOUND.into_response()
+            }
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+    }
+    #[cfg(not(feature = "thumbnails"))]
+    let _ = query.size;
+
+    let content_type = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        _ => "application/octet-stream",
+    };
+
+    match fs::read(&path).await {
+        Ok(data) => ([(header::CONTENT_TYPE, content_type)], data).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn next(State(state): State<AppState>) -> Redirect {
+    let _ = state.actions.send(Action::Next).await;
+
+    state.home()
+}
+
+async fn pause(State(state): State<AppState>) -> Redirect {
+    let _ = state.actions.send(Action::Pause).await;
+
+    state.home()
+}
+
+async fn resume(State(state): State<AppState>) -> Redirect {
+    let _ = state.actions.send(Action::Resume).await;
+
+    state.home()
+}
+
+async fn set_query(State(state): State<AppState>, Form(form): Form<QueryForm>) -> Response {
+    let (tx, rx) = oneshot::channel();
+    let _ = state.actions.send(Action::SetQuery(form.query, tx)).await;
+
+    match rx.await {
+        Ok(Err(err)) => (StatusCode::BAD_REQUEST, err).into_response(),
+        _ => state.home().into_response(),
+    }
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 1200px; margin: 1em auto; padding: 0 1em; }
+.controls { display: flex; gap: 0.5em; margin-bottom: 1em; }
+.query { display: flex; gap: 0.5em; margin-bottom: 1em; }
+.query input { flex: 1; }
+button { padding: 0.5em 1em; }
+.photos { display: grid; grid-template-columns: repeat(auto-fill, minmax(160px, 1fr)); gap: 1em; }
+.photos img { width: 100%; height: 110px; object-fit: cover; border-radius: 4px; }
+figure { margin: 0; }
+figcaption { font-size: 0.8em; }";
@@ -0,0 +1,227 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::DateTime;
+use reqwest::{Method, StatusCode, Url};
+
+use crate::{
+    filesystem::{File, Filesystem},
+    redact::Redacted,
+    remote::{element, elements, percent_decode, percent_encode, LazyClient, Secret},
+    storage::Storage,
+};
+
+const PASSWORD: Secret = Secret::new("WEBDAV_PASSWORD", "webdav");
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/></d:prop>
+</d:propfind>"#;
+
+pub fn lookup_password() -> io::Result<Redacted<String>> {
+    PASSWORD.lookup()
+}
+
+#[cfg(feature = "keyring")]
+pub fn store_password(password: &Redacted<String>) -> io::Result<()> {
+    PASSWORD.store(password)
+}
+
+#[derive(Debug)]
+pub struct WebDavStorage {
+    http: LazyClient,
+    url: Url,
+    username: String,
+    password: Redacted<String>,
+    root: PathBuf,
+}
+
+impl WebDavStorage {
+    pub fn new<P: AsRef<Path>>(
+        mut url: Url,
+        username: &str,
+        password: Redacted<String>,
+        root: P,
+    ) -> Self {
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Self {
+            http: LazyClient::default(),
+            url,
+            username: username.to_owned(),
+            password,
+            root: root.as_ref().to_owned(),
+        }
+    }
+
+    fn url(&self, path: &Path, collection: bool) -> io::Result<Url> {
+        let relative = path.strip_prefix(&self.root).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not inside the storage folder", path.display()),
+            )
+        })?;
+
+        let mut segments = Vec::new();
+        for component in relative.components() {
+            let Component::Normal(component) = component else {
+                return Err(io::ErrorKind::InvalidInput.into());
+            };
+
+            segments.push(percent_encode(&component.to_string_lossy(), false));
+        }
+
+        let mut relative = segments.join("/");
+        if collection && !relative.is_empty() {
+            relative.push('/');
+        }
+
+        self.url.join(&relative).map_err(io::Error::other)
+    }
+
+    fn path(&self, href: &str) -> Option<PathBuf> {
+        let href = href.trim();
+        let href = Url::parse(href)
+            .ok()
+            .map_or_else(|| href.to_owned(), |url| url.path().to_owned());
+
+        let relative = percent_decode(href.strip_prefix(self.url.path())?);
+
+        Some(
+            relative
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .fold(self.root.clone(), |path, segment| path.join(segment)),
+        )
+    }
+
+    fn list_collection(&self, folder: &Path) -> io::Result<(Vec<File>, Vec<PathBuf>)> {
+        let body = match self.send(
+            Method::from_bytes(b"PROPFIND").unwrap(),
+            self.url(folder, true)?,
+            Some("1"),
+            PROPFIND_BODY.as_bytes().to_vec(),
+        ) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok((Vec::new(), Vec::new()));
+            }
+            result => result?,
+        };
+        let body = String::from_utf8_lossy(&body);
+
+        let mut files = Vec::new();
+        let mut folders = Vec::new();
+        for response in elements(&body, "response") {
+            let Some(path) = element(response, "href").and_then(|href| self.path(&href)) else {
+                continue;
+            };
+
+            if path == folder {
+                continue;
+            }
+
+            if elements(response, "collection").is_empty() {
+                files.push(File {
+                    path,
+                    size: element(response, "getcontentlength")
+                        .and_then(|size| size.trim().parse().ok())
+                        .unwrap_or(0),
+                    modified: element(response, "getlastmodified")
+                        .and_then(|modified| DateTime::parse_from_rfc2822(modified.trim()).ok())
+                        .map(SystemTime::from),
+                });
+            } else {
+                folders.push(path);
+            }
+        }
+
+        Ok((files, folders))
+    }
+
+    fn create_collections(&self, folder: &Path) -> io::Result<()> {
+        let mut ancestors: Vec<_> = folder
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&self.root) && *ancestor != self.root)
+            .collect();
+        ancestors.reverse();
+
+        for ancestor in ancestors {
+            match self.send(
+                Method::from_bytes(b"MKCOL").unwrap(),
+                self.url(ancestor, true)?,
+                None,
+                Vec::new(),
+            ) {
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                result => drop(result?),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        url: Url,
+        depth: Option<&str>,
+        body: Vec<u8>,
+    ) -> io::Result<Vec<u8>> {
+        let mut request = self
+            .http
+            .get()
+            .request(method, url)
+            .basic_auth(&self.username, Some(self.password.expose()))
+            .body(body);
+        if let Some(depth) = depth {
+            request = request
+                .header("depth", depth)
+                .header("content-type", "application/xml");
+        }
+
+        let response = request.send().map_err(io::Error::other)?;
+        let status = response.status();
+        let body = response.bytes().map_err(io::Error::other)?;
+
+        match status {
+            status if status.is_success() => Ok(body.to_vec()),
+            StatusCode::NOT_FOUND => Err(io::ErrorKind::NotFound.into()),
+            StatusCode::METHOD_NOT_ALLOWED => Err(io::ErrorKind::AlreadyExists.into()),
+            status => Err(io::Error::other(format!(
+                "WebDAV request failed with {}",
+                status
+            ))),
+        }
+    }
+}
+
+impl Filesystem for WebDavStorage {
+    fn list(&self, folder: &Path) -> io::Result<Vec<File>> {
+        self.list_collection(folder).map(|(files, _)| files)
+    }
+
+    fn list_folders(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        self.list_collection(folder).map(|(_, folders)| folders)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.send(Method::DELETE, self.url(path, false)?, None, Vec::new())
+            .map(drop)
+    }
+}
+
+impl Storage for WebDavStorage {
+    fn put(&self, source: &Path, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.create_collections(parent)?;
+        }
+
+        self.send(Method::PUT, self.url(path, false)?, None, fs::read(source)?)?;
+
+        fs::rename(source, path)
+    }
+}
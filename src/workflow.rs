@@ -0,0 +1,196 @@
+use std::{path::Path, sync::Arc, time::SystemTime};
+
+use crate::{
+    cleanup::{self, RetentionPolicy},
+    filesystem::RealFs,
+    history::History,
+    pipeline::{self, Downloaded, Saved},
+    storage::Storage,
+    timing::{Stage, Timer},
+    BatchReport, Client, Download, Fetch, Limit, Photo, Result,
+};
+
+const CAPACITY: usize = 8;
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+type Predicate = Box<dyn FnMut(&Photo) -> bool + Send>;
+type Processor = Box<dyn Fn(&Downloaded) -> Result<()> + Send + Sync>;
+
+pub struct Workflow {
+    client: Client,
+    fetch: Fetch,
+    download: Download,
+    max_concurrent_downloads: usize,
+    processing_workers: usize,
+    storage: Arc<dyn Storage>,
+    filter: Predicate,
+    processor: Processor,
+    retention: Option<RetentionPolicy>,
+    timer: Timer,
+}
+
+impl Workflow {
+    pub fn new(client: Client, fetch: Fetch, download: Download) -> Self {
+        Self {
+            client,
+            fetch,
+            download,
+            max_concurrent_downloads: MAX_CONCURRENT_DOWNLOADS,
+            processing_workers: 1,
+            storage: Arc::new(RealFs),
+            filter: Box::new(|_| true),
+            processor: Box::new(|_| Ok(())),
+            retention: None,
+            timer: Timer::default(),
+        }
+    }
+
+    pub fn with_max_concurrent_downloads(mut self, max_concurrent_downloads: usize) -> Self {
+        self.max_concurrent_downloads = max_concurrent_downloads;
+        self
+    }
+
+    pub fn with_processing_workers(mut self, processing_workers: usize) -> Self {
+        self.processing_workers = processing_workers;
+        self
+    }
+
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: FnMut(&Photo) -> bool + Send + 'static,
+    {
+        self.filter = Box::new(filter);
+        self
+    }
+
+    pub fn with_processor<F>(mut self, processor: F) -> Self
+    where
+        F: Fn(&Downloaded) -> Result<()> + Send + Sync + 'static,
+    {
+        self.processor = Box::new(processor);
+        self
+    }
+
+    pub fn with_retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = Some(policy);
+        self
+    }
+
+    pub async fn run<P: AsRef<Path>>(
+        self,
+        folder: P,
+        history: &History,
+    ) -> Result<(BatchReport<Saved>, Option<Limit>)> {
+        let folder = folder.as_ref();
+
+        let photos = pipeline::fetch(
+            self.client.clone(),
+            self.fetch,
+            self.timer.clone(),
+            CAPACITY,
+        );
+        let photos = pipeline::filter(photos, self.filter, CAPACITY);
+        let downloaded = pipeline::download(
+            self.client,
+            self.download,
+            folder,
+            self.max_concurrent_downloads,
+            photos,
+            self.timer.clone(),
+            CAPACITY,
+        );
+        let processed = pipeline::process(
+            downloaded,
+            self.processor,
+            self.processing_workers,
+            self.timer.clone(),
+            CAPACITY,
+        );
+        let saved = pipeline::persist(
+            processed,
+            self.storage.clone(),
+            self.timer.clone(),
+            CAPACITY,
+        );
+        let (mut report, limit) = pipeline::collect_within_limits(saved).await;
+
+        if let Some(policy) = self.retention {
+            let storage = self.storage;
+            let folder = folder.to_owned();
+            let history = history.clone();
+            let timer = self.timer.clone();
+            tokio::task::spawn_blocking(move || {
+                timer.time(Stage::Cleanup, || {
+                    cleanup::clean_with(&*storage, folder, &policy, &history, SystemTime::now())
+                })
+            })
+            .await
+            .unwrap()?;
+        }
+        report.timings = self.timer.take();
+
+        Ok((report, limit))
+    }
+}
+
+pub async fn save_photos<P: AsRef<Path>>(
+    client: &Client,
+    fetch: &Fetch,
+    download: &Download,
+    folder: P,
+    policy: &RetentionPolicy,
+    history: &History,
+) -> Result<Vec<Saved>> {
+    let (report, _) = Workflow::new(client.clone(), fetch.clone(), download.clone())
+        .with_retention(policy.clone())
+        .run(folder, history)
+        .await?;
+
+    report.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::testing;
+
+    #[tokio::test]
+    async fn retention_runs_on_a_current_thread_runtime() {
+        let folder = testing::temp_path("workflow");
+        fs::create_dir_all(&folder).unwrap();
+        for name in ["a.png", "b.png", "c.png"] {
+            fs::write(folder.join(name), [0; 16]).unwrap();
+        }
+
+        let policy = RetentionPolicy {
+            max_count: Some(0),
+            delete_unshown: true,
+            ..Default::default()
+        };
+        let history = History::open(folder.join("history.json")).unwrap();
+
+        let result = Workflow::new(
+            testing::unreachable_client(),
+            Fetch::default(),
+            Download::default(),
+        )
+        .with_retention(policy)
+        .run(&folder, &history)
+        .await;
+        let remaining = fs::read_dir(&folder).unwrap().count();
+        fs::remove_dir_all(&folder).unwrap();
+
+        let (report, _) = result.unwrap();
+        assert!(report.saved.is_empty());
+        assert!(report.timings.contains_key(&Stage::Fetch));
+        assert!(report.timings.contains_key(&Stage::Cleanup));
+        assert_eq!(remaining, 0);
+    }
+}